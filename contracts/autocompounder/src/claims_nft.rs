@@ -0,0 +1,269 @@
+/// This file contains the manually written messages of the cw721-base contract that are used to tokenise unbonding claims.
+/// Only the subset of the cw721 interface that the autocompounder needs is mirrored here.
+/// The messages can be found here: https://github.com/CosmWasm/cw-nfts/blob/main/contracts/cw721-base/src/msg.rs
+use cosmwasm_std::{
+    to_json_binary, Addr, Binary, CosmosMsg, Deps, StdResult, SubMsg, Uint128, WasmMsg,
+};
+use cw_utils::Expiration;
+use serde::{Deserialize, Serialize};
+
+use crate::contract::CLAIMS_NFT_INSTANTIATE_REPLY_ID;
+use crate::msg::Claim;
+
+pub const CLAIMS_NFT_SYMBOL: &str = "FTTC";
+/// Page size of the claim NFT queries, below the maximum limit of cw721-base
+const CLAIM_NFTS_PAGE_SIZE: u32 = 100;
+
+/// Mirrors `cw721_base::InstantiateMsg`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Cw721InstantiateMsg {
+    pub name: String,
+    pub symbol: String,
+    pub minter: String,
+}
+
+/// Metadata stored with every claim NFT.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ClaimNftExtension {
+    pub amount_of_lp_tokens_to_unbond: Uint128,
+    pub unbonding_timestamp: Expiration,
+}
+
+/// Mirrors the `Mint` and `Burn` variants of `cw721_base::ExecuteMsg`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw721ExecuteMsg {
+    Mint {
+        token_id: String,
+        owner: String,
+        token_uri: Option<String>,
+        extension: ClaimNftExtension,
+    },
+    Burn {
+        token_id: String,
+    },
+}
+
+/// Mirrors `cw721::Cw721ReceiveMsg`, which the claims nft contract sends with `SendNft`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Cw721ReceiveMsg {
+    pub sender: String,
+    pub token_id: String,
+    pub msg: Binary,
+}
+
+/// Mirrors the `OwnerOf` and `Tokens` variants of `cw721::Cw721QueryMsg`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw721QueryMsg {
+    OwnerOf {
+        token_id: String,
+        include_expired: Option<bool>,
+    },
+    Tokens {
+        owner: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct OwnerOfResponse {
+    pub owner: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TokensResponse {
+    pub tokens: Vec<String>,
+}
+
+/// create a SubMsg to instantiate the cw721 contract that holds the claims of the vault.
+pub fn create_claims_nft_submsg(minter: String, name: String, code_id: u64) -> StdResult<SubMsg> {
+    let msg = Cw721InstantiateMsg {
+        name,
+        symbol: CLAIMS_NFT_SYMBOL.to_string(),
+        minter,
+    };
+    Ok(SubMsg::reply_on_success(
+        WasmMsg::Instantiate {
+            admin: None,
+            code_id,
+            msg: to_json_binary(&msg)?,
+            funds: vec![],
+            label: "4T2 Vault Claims".to_string(),
+        },
+        CLAIMS_NFT_INSTANTIATE_REPLY_ID,
+    ))
+}
+
+/// Creates the message to mint a claim NFT with id `token_id` to `owner`
+pub fn mint_claim_nft_msg(
+    claims_nft: &Addr,
+    token_id: String,
+    owner: &Addr,
+    claim: &Claim,
+) -> StdResult<CosmosMsg> {
+    Ok(WasmMsg::Execute {
+        contract_addr: claims_nft.to_string(),
+        msg: to_json_binary(&Cw721ExecuteMsg::Mint {
+            token_id,
+            owner: owner.to_string(),
+            token_uri: None,
+            extension: ClaimNftExtension {
+                amount_of_lp_tokens_to_unbond: claim.amount_of_lp_tokens_to_unbond,
+                unbonding_timestamp: claim.unbonding_timestamp,
+            },
+        })?,
+        funds: vec![],
+    }
+    .into())
+}
+
+/// Creates the message to burn the claim NFT with id `token_id`.
+/// The autocompounder needs to hold the NFT, or be approved by its holder.
+pub fn burn_claim_nft_msg(claims_nft: &Addr, token_id: String) -> StdResult<CosmosMsg> {
+    Ok(WasmMsg::Execute {
+        contract_addr: claims_nft.to_string(),
+        msg: to_json_binary(&Cw721ExecuteMsg::Burn { token_id })?,
+        funds: vec![],
+    }
+    .into())
+}
+
+/// Queries a page of the claim NFTs held by `owner`
+pub fn query_claim_nfts_of(
+    deps: Deps,
+    claims_nft: &Addr,
+    owner: &Addr,
+    start_after: Option<String>,
+    limit: u32,
+) -> StdResult<Vec<String>> {
+    let res: TokensResponse = deps.querier.query_wasm_smart(
+        claims_nft,
+        &Cw721QueryMsg::Tokens {
+            owner: owner.to_string(),
+            start_after,
+            limit: Some(limit),
+        },
+    )?;
+    Ok(res.tokens)
+}
+
+/// Queries all the claim NFTs held by `owner`, page by page
+pub fn query_all_claim_nfts_of(
+    deps: Deps,
+    claims_nft: &Addr,
+    owner: &Addr,
+) -> StdResult<Vec<String>> {
+    let mut token_ids: Vec<String> = vec![];
+    loop {
+        let page = query_claim_nfts_of(
+            deps,
+            claims_nft,
+            owner,
+            token_ids.last().cloned(),
+            CLAIM_NFTS_PAGE_SIZE,
+        )?;
+        if page.is_empty() {
+            return Ok(token_ids);
+        }
+        token_ids.extend(page);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::msg::{AutocompounderExecuteMsg, ContractExecuteMsg, Cw721HookMsg, ExecuteMsg};
+    use cosmwasm_std::testing::mock_dependencies;
+    use cosmwasm_std::{from_json, ContractResult, SystemResult, Timestamp, WasmQuery};
+    use speculoos::prelude::*;
+
+    #[test]
+    fn mint_msg_holds_claim_metadata() -> anyhow::Result<()> {
+        let claim = Claim {
+            unbonding_timestamp: Expiration::AtTime(Timestamp::from_seconds(100)),
+            amount_of_vault_tokens_to_burn: Uint128::new(10),
+            amount_of_lp_tokens_to_unbond: Uint128::new(5),
+        };
+        let msg = mint_claim_nft_msg(
+            &Addr::unchecked("claims_nft"),
+            "1".to_string(),
+            &Addr::unchecked("owner"),
+            &claim,
+        )?;
+
+        let CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr, msg, ..
+        }) = msg
+        else {
+            panic!("expected wasm execute msg");
+        };
+        assert_that!(contract_addr).is_equal_to("claims_nft".to_string());
+        assert_that!(from_json::<Cw721ExecuteMsg>(&msg)?).is_equal_to(Cw721ExecuteMsg::Mint {
+            token_id: "1".to_string(),
+            owner: "owner".to_string(),
+            token_uri: None,
+            extension: ClaimNftExtension {
+                amount_of_lp_tokens_to_unbond: Uint128::new(5),
+                unbonding_timestamp: Expiration::AtTime(Timestamp::from_seconds(100)),
+            },
+        });
+        Ok(())
+    }
+
+    #[test]
+    fn sent_nfts_are_parsed_next_to_the_app_messages() -> anyhow::Result<()> {
+        let receive_nft = Cw721ReceiveMsg {
+            sender: "holder".to_string(),
+            token_id: "1".to_string(),
+            msg: to_json_binary(&Cw721HookMsg::Withdraw {})?,
+        };
+        // the message that the cw721 contract sends
+        #[derive(Serialize)]
+        #[serde(rename_all = "snake_case")]
+        enum Cw721Callback {
+            ReceiveNft(Cw721ReceiveMsg),
+        }
+        let callback = Cw721Callback::ReceiveNft(receive_nft.clone());
+        let msg: ContractExecuteMsg = from_json(to_json_binary(&callback)?)?;
+        assert_that!(msg).is_equal_to(ContractExecuteMsg::ReceiveNft(receive_nft));
+
+        let app_msg = ExecuteMsg::Module(AutocompounderExecuteMsg::Compound {});
+        let msg: ContractExecuteMsg = from_json(to_json_binary(&app_msg)?)?;
+        assert_that!(msg).is_equal_to(ContractExecuteMsg::App(app_msg));
+        Ok(())
+    }
+
+    #[test]
+    fn all_claim_nfts_are_queried_page_by_page() -> anyhow::Result<()> {
+        let mut deps = mock_dependencies();
+        // the owner holds more claim nfts than fit in one page
+        deps.querier.update_wasm(|query| {
+            let WasmQuery::Smart { msg, .. } = query else {
+                panic!("unexpected query");
+            };
+            let Cw721QueryMsg::Tokens {
+                start_after, limit, ..
+            } = from_json(msg).unwrap()
+            else {
+                panic!("unexpected query");
+            };
+            let start = start_after.map_or(0, |id| id.parse::<u32>().unwrap() + 1);
+            let tokens = (start..250).take(limit.unwrap() as usize);
+            let tokens = tokens.map(|id| id.to_string()).collect();
+            SystemResult::Ok(ContractResult::Ok(
+                to_json_binary(&TokensResponse { tokens }).unwrap(),
+            ))
+        });
+
+        let token_ids = query_all_claim_nfts_of(
+            deps.as_ref(),
+            &Addr::unchecked("claims_nft"),
+            &Addr::unchecked("owner"),
+        )?;
+        assert_that!(token_ids).has_length(250);
+        assert_that!(token_ids.last()).is_equal_to(Some(&"249".to_string()));
+        Ok(())
+    }
+}
@@ -5,7 +5,6 @@ use crate::msg::{
     AutocompounderExecuteMsg, AutocompounderInstantiateMsg, AutocompounderMigrateMsg,
     AutocompounderQueryMsg, AUTOCOMPOUNDER_ID,
 };
-use abstract_app::export_endpoints;
 use abstract_app::AppContract;
 use cosmwasm_std::Response;
 use cw20::Cw20ReceiveMsg;
@@ -35,6 +34,14 @@ pub const CP_PROVISION_REPLY_ID: u64 = 4u64;
 pub const LP_WITHDRAWAL_REPLY_ID: u64 = 5u64;
 pub const FEE_SWAPPED_REPLY: u64 = 6u64;
 pub const LP_FEE_WITHDRAWAL_REPLY_ID: u64 = 7u64;
+pub const CLAIMS_NFT_INSTANTIATE_REPLY_ID: u64 = 8u64;
+pub const MATURED_CLAIMS_WITHDRAWAL_REPLY_ID: u64 = 9u64;
+pub const EMERGENCY_WITHDRAWAL_REPLY_ID: u64 = 10u64;
+pub const DEPOSIT_COMPOUND_REPLY_ID: u64 = 11u64;
+
+/// Submessage ids carry the reply id in their lower bits and the nonce of their reply context above them
+pub const REPLY_ID_BITS: u32 = 16;
+pub const REPLY_ID_MASK: u64 = (1 << REPLY_ID_BITS) - 1;
 
 /// Used as the foundation for building your app.
 /// All entrypoints are executed through this const (`instantiate`, `query`, `execute`, `migrate`)
@@ -51,10 +58,89 @@ pub const AUTOCOMPOUNDER_APP: AutocompounderApp =
             (LP_COMPOUND_REPLY_ID, handlers::lp_compound_reply),
             (SWAPPED_REPLY_ID, handlers::swapped_reply),
             (CP_PROVISION_REPLY_ID, handlers::compound_lp_provision_reply),
+            (
+                CLAIMS_NFT_INSTANTIATE_REPLY_ID,
+                handlers::claims_nft_instantiate_reply,
+            ),
+            (
+                MATURED_CLAIMS_WITHDRAWAL_REPLY_ID,
+                handlers::matured_claims_withdrawal_reply,
+            ),
+            (
+                EMERGENCY_WITHDRAWAL_REPLY_ID,
+                handlers::emergency_withdrawal_reply,
+            ),
+            (DEPOSIT_COMPOUND_REPLY_ID, handlers::deposit_compound_reply),
         ])
         .with_receive(handlers::receive)
         .with_dependencies(AUTOCOMPOUNDER_DEPS);
 
-// Export the endpoints for this contract
+// Export the endpoints for this contract.
+// The reply entrypoint is not taken from `export_endpoints!` as it has to strip the reply context nonce from the submessage id.
+#[cfg(feature = "export")]
+mod endpoints {
+    use super::{AutocompounderApp, AutocompounderResult, AUTOCOMPOUNDER_APP, REPLY_ID_MASK};
+    use crate::handlers;
+    use crate::msg::ContractExecuteMsg;
+    use abstract_sdk::base::{
+        ExecuteEndpoint, Handler, InstantiateEndpoint, MigrateEndpoint, QueryEndpoint,
+    };
+    use cosmwasm_std::{Binary, Deps, DepsMut, Env, MessageInfo, Reply};
+
+    /// Instantiate entrypoint
+    #[cosmwasm_std::entry_point]
+    pub fn instantiate(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        msg: <AutocompounderApp as InstantiateEndpoint>::InstantiateMsg,
+    ) -> AutocompounderResult {
+        AUTOCOMPOUNDER_APP.instantiate(deps, env, info, msg)
+    }
+
+    /// Execute entrypoint, which also receives the claim NFTs sent with the cw721 `SendNft`
+    #[cosmwasm_std::entry_point]
+    pub fn execute(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        msg: ContractExecuteMsg,
+    ) -> AutocompounderResult {
+        match msg {
+            ContractExecuteMsg::App(msg) => AUTOCOMPOUNDER_APP.execute(deps, env, info, msg),
+            ContractExecuteMsg::ReceiveNft(msg) => {
+                handlers::receive_nft(deps, env, info, AUTOCOMPOUNDER_APP, msg)
+            }
+        }
+    }
+
+    /// Query entrypoint
+    #[cosmwasm_std::entry_point]
+    pub fn query(
+        deps: Deps,
+        env: Env,
+        msg: <AutocompounderApp as QueryEndpoint>::QueryMsg,
+    ) -> AutocompounderResult<Binary> {
+        AUTOCOMPOUNDER_APP.query(deps, env, msg)
+    }
+
+    /// Migrate entrypoint
+    #[cosmwasm_std::entry_point]
+    pub fn migrate(
+        deps: DepsMut,
+        env: Env,
+        msg: <AutocompounderApp as MigrateEndpoint>::MigrateMsg,
+    ) -> AutocompounderResult {
+        AUTOCOMPOUNDER_APP.migrate(deps, env, msg)
+    }
+
+    /// Reply entrypoint, dispatches on the reply id and passes the full submessage id to the handler
+    #[cosmwasm_std::entry_point]
+    pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> AutocompounderResult {
+        let handler = AUTOCOMPOUNDER_APP.reply_handler(msg.id & REPLY_ID_MASK)?;
+        handler(deps, env, AUTOCOMPOUNDER_APP, msg)
+    }
+}
+
 #[cfg(feature = "export")]
-export_endpoints!(AUTOCOMPOUNDER_APP, AutocompounderApp);
+pub use endpoints::*;
@@ -2,7 +2,7 @@ use abstract_app::AppError;
 use abstract_core::objects::ans_host::AnsHostError;
 use abstract_core::AbstractError;
 use abstract_sdk::AbstractSdkError;
-use cosmwasm_std::{OverflowError, StdError, Uint128};
+use cosmwasm_std::{Addr, OverflowError, StdError, Uint128};
 use cw_asset::AssetError;
 use cw_controllers::AdminError;
 use cw_utils::Expiration;
@@ -55,6 +55,15 @@ pub enum AutocompounderError {
     #[error("Deposit can only be called by the lp token")]
     SenderIsNotLpToken {},
 
+    #[error("Deposit can only be called by a pool asset")]
+    SenderIsNotPoolAsset {},
+
+    #[error("Claims can only be received from the claims nft contract")]
+    SenderIsNotClaimsNft {},
+
+    #[error("Tokenised claims are withdrawn by their holders")]
+    ClaimsAreTokenised {},
+
     #[error("mismatch of sent {sent} but specified deposit amount of {wanted}")]
     FundMismatch { sent: Uint128, wanted: Uint128 },
 
@@ -85,6 +94,9 @@ pub enum AutocompounderError {
     #[error("Zero deposit amount is not allowed")]
     ZeroDepositAmount {},
 
+    #[error("Zero redeem amount is not allowed")]
+    ZeroRedeemAmount {},
+
     #[error("Cannot set recipient to Account")]
     CannotSetRecipientToAccount {},
 
@@ -108,4 +120,73 @@ pub enum AutocompounderError {
 
     #[error("Reward cannot be swapped: {0}")]
     RewardCannotBeSwapped(AnsHostError),
+
+    #[error("The vault is in emergency shutdown")]
+    VaultIsShutdown {},
+
+    #[error("The vault is not in emergency shutdown")]
+    VaultIsNotShutdown {},
+
+    #[error("Liquidity cannot be withdrawn while the lp tokens are unbonding")]
+    CannotWithdrawUnbondingLiquidity {},
+
+    #[error("The lp tokens of the vault are unbonding until {unbonded_at}")]
+    EmergencyLpStillUnbonding { unbonded_at: Expiration },
+
+    #[error("{operation} are paused")]
+    OperationPaused { operation: String },
+
+    #[error("Sender is not the admin or the guardian")]
+    SenderIsNotAdminOrGuardian {},
+
+    #[error("Only the admin can unpause")]
+    GuardianCannotUnpause {},
+
+    #[error("Deposit exceeds the vault capacity of {max_total_lp} lp tokens")]
+    TotalDepositCapExceeded { max_total_lp: Uint128 },
+
+    #[error("Deposit exceeds the maximum balance of {max_vault_tokens} vault tokens per address")]
+    AddressDepositCapExceeded { max_vault_tokens: Uint128 },
+
+    #[error("{address} is not an allowed depositor of this vault")]
+    DepositorNotAllowed { address: Addr },
+
+    #[error("Batch deposit requires at least one deposit")]
+    EmptyBatchDeposit {},
+
+    #[error("Deposit for {recipient} is not in the asset ratio of the batch total")]
+    BatchDepositRatioMismatch { recipient: Addr },
+
+    #[error("Sender is not an approved operator of {owner}")]
+    SenderIsNotOperator { owner: Addr },
+
+    #[error("Operators can only send the proceeds to the owner or themselves, not to {recipient}")]
+    RecipientNotPermitted { recipient: Addr },
+
+    #[error("The operator approval is already expired")]
+    ExpiredApproval {},
+
+    #[error("No tokenfactory is known for the chain of {address}")]
+    UnsupportedTokenFactory { address: Addr },
+
+    #[error("Creating the vault token requires the tokenfactory creation fee of {fee}")]
+    MissingCreationFee { fee: String },
+
+    #[error("The decimal offset can be at most {max}")]
+    InvalidDecimalOffset { max: u32 },
+
+    #[error("Decimals of {token} are unknown, set the decimals of the vault token explicitly")]
+    UnknownTokenDecimals { token: String },
+
+    #[error("{asset} is a core asset of the vault and can't be swept")]
+    CannotSweepCoreAsset { asset: String },
+
+    #[error("{asset} is a core asset of the vault and is always compounded")]
+    CannotFilterCoreAsset { asset: String },
+
+    #[error("The pending rewards of {provider} can't be queried")]
+    PendingRewardsNotQueryable { provider: String },
+
+    #[error("Invalid vault token {field}: {reason}")]
+    InvalidVaultTokenMetadata { field: String, reason: String },
 }
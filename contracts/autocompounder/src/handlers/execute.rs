@@ -1,25 +1,37 @@
 use super::convert_to_shares;
 
 use super::helpers::{
-    burn_vault_tokens_msg, check_fee, convert_to_assets, get_unbonding_period_and_cooldown,
-    mint_vault_tokens_msg, query_stake, stake_lp_tokens, transfer_to_msgs,
-    vault_token_total_supply,
+    burn_vault_tokens_msg, change_vault_token_admin_msg, check_deposit_caps, check_fee,
+    compound_context, convert_to_assets, decimal_offset, get_unbonding_period_and_cooldown,
+    mint_vault_tokens_msgs, pool_assets_context, query_stake, query_total_lp, save_reply_context,
+    stake_lp_tokens, transfer_to_msgs, vault_token_balance, vault_token_total_supply,
 };
 
+use super::reply::fill_pending_claims_msgs;
+
 use abstract_core::objects::AnsEntryConvertor;
 use abstract_sdk::feature_objects::AnsHost;
 use abstract_sdk::{AccountAction, AdapterInterface};
 
+use crate::claims_nft::{
+    burn_claim_nft_msg, mint_claim_nft_msg, query_all_claim_nfts_of, Cw721ReceiveMsg,
+};
 use crate::contract::{
-    AutocompounderApp, AutocompounderResult, LP_COMPOUND_REPLY_ID, LP_PROVISION_REPLY_ID,
-    LP_WITHDRAWAL_REPLY_ID,
+    AutocompounderApp, AutocompounderResult, DEPOSIT_COMPOUND_REPLY_ID,
+    EMERGENCY_WITHDRAWAL_REPLY_ID, LP_COMPOUND_REPLY_ID, LP_PROVISION_REPLY_ID,
+    LP_WITHDRAWAL_REPLY_ID, MATURED_CLAIMS_WITHDRAWAL_REPLY_ID,
 };
 use crate::error::AutocompounderError;
 
-use crate::msg::{AutocompounderExecuteMsg, BondingData};
+use crate::msg::{
+    AccessMode, AutocompounderExecuteMsg, BondingData, Cw20HookMsg, Cw721HookMsg, DepositCaps,
+    EmergencyShutdownState, ExecuteMsg, LiquidityBufferConfig, PauseState,
+};
 use crate::state::{
-    Claim, Config, FeeConfig, CACHED_ASSETS, CACHED_USER_ADDR, CLAIMS, CONFIG, DEFAULT_BATCH_SIZE,
-    FEE_CONFIG, LATEST_UNBONDING, MAX_BATCH_SIZE, PENDING_CLAIMS,
+    Claim, Config, FeeConfig, ReplyContext, ACCESS_MODE, CLAIMS, CLAIMS_NFT, CLAIMS_NFT_COUNT,
+    COMPOUND_ON_DEPOSIT, CONFIG, DEFAULT_BATCH_SIZE, DEPOSITORS, DEPOSIT_CAPS, EMERGENCY_SHUTDOWN,
+    FEE_CONFIG, GUARDIAN, LATEST_UNBONDING, LIQUIDITY_BUFFER, LIQUIDITY_BUFFER_CONFIG,
+    MAX_BATCH_SIZE, NFT_CLAIMS, OPERATORS, PAUSE_STATE, PENDING_CLAIMS, REWARD_FILTER,
 };
 use abstract_cw_staking::msg::{StakingAction, StakingExecuteMsg};
 use abstract_cw_staking::CW_STAKING_ADAPTER_ID;
@@ -32,13 +44,13 @@ use abstract_sdk::{
 };
 use abstract_sdk::{features::AbstractResponse, AbstractSdkError};
 use cosmwasm_std::{
-    Addr, Coin, CosmosMsg, Decimal, Deps, DepsMut, Env, MessageInfo, Order, ReplyOn, Response,
-    StdError, StdResult, SubMsg, Uint128,
+    from_json, wasm_execute, Addr, Coin, CosmosMsg, Decimal, Deps, DepsMut, Empty, Env,
+    MessageInfo, Order, ReplyOn, Response, StdError, StdResult, Storage, SubMsg, Uint128,
 };
 use cw20::Cw20ReceiveMsg;
 use cw_asset::{Asset, AssetBase, AssetInfoBase, AssetList};
 use cw_storage_plus::Bound;
-use cw_utils::Duration;
+use cw_utils::{Duration, Expiration};
 use std::ops::Add;
 
 /// Handle the `AutocompounderExecuteMsg`s sent to this app.
@@ -71,23 +83,592 @@ pub fn execute_handler(
             funds,
             recipient,
             max_spread,
-        } => deposit(deps, info, env, app, funds, recipient, max_spread),
+        } => {
+            assert_not_paused(deps.as_ref(), |paused| paused.deposits, "Deposits")?;
+            assert_not_shutdown(deps.as_ref())?;
+            deposit(deps, info, env, app, funds, recipient, max_spread, None)
+        }
+        AutocompounderExecuteMsg::BatchDeposit {
+            deposits,
+            max_spread,
+        } => {
+            assert_not_paused(deps.as_ref(), |paused| paused.deposits, "Deposits")?;
+            assert_not_shutdown(deps.as_ref())?;
+            batch_deposit(deps, info, env, app, deposits, max_spread)
+        }
         AutocompounderExecuteMsg::DepositLp {
             lp_token,
             recipient: receiver,
-        } => deposit_lp(deps, info, env, app, lp_token, receiver),
-        AutocompounderExecuteMsg::Redeem { amount, recipient } => {
-            redeem(deps, env, app, info, amount, recipient)
+        } => {
+            assert_not_paused(deps.as_ref(), |paused| paused.deposits, "Deposits")?;
+            assert_not_shutdown(deps.as_ref())?;
+            deposit_lp(deps, info, env, app, lp_token, receiver, None)
+        }
+        AutocompounderExecuteMsg::Redeem {
+            amount,
+            owner,
+            recipient,
+        } => {
+            assert_not_paused(deps.as_ref(), |paused| paused.redeems, "Redeems")?;
+            assert_not_shutdown(deps.as_ref())?;
+            redeem(deps, env, app, info, amount, owner, recipient)
+        }
+        AutocompounderExecuteMsg::Withdraw { owner } => {
+            assert_not_paused(deps.as_ref(), |paused| paused.withdrawals, "Withdrawals")?;
+            let owner = owner_if_operator(deps.as_ref(), &env, &info.sender, owner)?;
+            withdraw_claims(deps, app, env, owner)
+        }
+        AutocompounderExecuteMsg::CancelPendingClaim { owner } => {
+            let owner = owner_if_operator(deps.as_ref(), &env, &info.sender, owner)?;
+            cancel_pending_claim(deps, app, owner)
+        }
+        AutocompounderExecuteMsg::ApproveOperator { operator, expires } => {
+            approve_operator(deps, env, info, app, operator, expires)
+        }
+        AutocompounderExecuteMsg::RevokeOperator { operator } => {
+            let operator = deps.api.addr_validate(&operator)?;
+            OPERATORS.remove(deps.storage, (info.sender, operator.clone()));
+            Ok(app.custom_response("revoke_operator", vec![("operator", operator.to_string())]))
         }
-        AutocompounderExecuteMsg::Withdraw {} => withdraw_claims(deps, app, env, info.sender),
         AutocompounderExecuteMsg::BatchUnbond { start_after, limit } => {
+            assert_not_shutdown(deps.as_ref())?;
             batch_unbond(deps, env, app, start_after, limit)
         }
-        AutocompounderExecuteMsg::Compound {} => compound(deps, app),
+        AutocompounderExecuteMsg::ProcessMaturedClaims { start_after, limit } => {
+            assert_not_paused(deps.as_ref(), |paused| paused.withdrawals, "Withdrawals")?;
+            process_matured_claims(deps, env, app, start_after, limit)
+        }
+        AutocompounderExecuteMsg::Compound {} => {
+            assert_not_paused(deps.as_ref(), |paused| paused.compounding, "Compounding")?;
+            assert_not_shutdown(deps.as_ref())?;
+            compound(deps, app)
+        }
         AutocompounderExecuteMsg::UpdateStakingConfig { bonding_data } => {
             update_staking_config(deps, app, info, bonding_data)
         }
+        AutocompounderExecuteMsg::UpdateLiquidityBuffer {
+            target,
+            instant_exit_fee,
+        } => update_liquidity_buffer(deps, info, app, target, instant_exit_fee),
+        AutocompounderExecuteMsg::EmergencyShutdown { withdraw_liquidity } => {
+            emergency_shutdown(deps, env, info, app, withdraw_liquidity)
+        }
+        AutocompounderExecuteMsg::EmergencyRedeem { amount } => {
+            emergency_redeem(deps, env, info, app, amount)
+        }
+        AutocompounderExecuteMsg::UpdateGuardian { guardian } => {
+            update_guardian(deps, info, app, guardian)
+        }
+        AutocompounderExecuteMsg::UpdatePause {
+            deposits,
+            redeems,
+            withdrawals,
+            compounding,
+        } => update_pause(
+            deps,
+            info,
+            app,
+            PauseUpdate {
+                deposits,
+                redeems,
+                withdrawals,
+                compounding,
+            },
+        ),
+        AutocompounderExecuteMsg::UpdateDepositCaps {
+            max_total_lp,
+            max_vault_tokens_per_address,
+        } => update_deposit_caps(
+            deps,
+            info,
+            app,
+            DepositCaps {
+                max_total_lp,
+                max_vault_tokens_per_address,
+            },
+        ),
+        AutocompounderExecuteMsg::UpdateAccessMode { access_mode } => {
+            update_access_mode(deps, info, app, access_mode)
+        }
+        AutocompounderExecuteMsg::UpdateDepositors { to_add, to_remove } => {
+            update_depositors(deps, info, app, to_add, to_remove)
+        }
+        AutocompounderExecuteMsg::ChangeVaultTokenAdmin { new_admin } => {
+            change_vault_token_admin(deps, env, info, app, new_admin)
+        }
+        AutocompounderExecuteMsg::Sweep { assets, recipient } => {
+            sweep(deps, info, app, assets, recipient)
+        }
+        AutocompounderExecuteMsg::UpdateRewardFilter { reward_filter } => {
+            app.admin.assert_admin(deps.as_ref(), &info.sender)?;
+            let config = CONFIG.load(deps.storage)?;
+            if let Some(asset) = reward_filter
+                .allowlist
+                .iter()
+                .chain(&reward_filter.denylist)
+                .find(|asset| config.is_core_asset(asset))
+            {
+                return Err(AutocompounderError::CannotFilterCoreAsset {
+                    asset: asset.to_string(),
+                });
+            }
+            REWARD_FILTER.save(deps.storage, &reward_filter)?;
+            Ok(app.custom_response(
+                "update_reward_filter",
+                vec![(
+                    "denied_rewards",
+                    format!("{:?}", reward_filter.denied_rewards),
+                )],
+            ))
+        }
+        AutocompounderExecuteMsg::UpdateCompoundOnDeposit { enabled } => {
+            app.admin.assert_admin(deps.as_ref(), &info.sender)?;
+            COMPOUND_ON_DEPOSIT.save(deps.storage, &enabled)?;
+            Ok(app.custom_response(
+                "update_compound_on_deposit",
+                vec![("enabled", enabled.to_string())],
+            ))
+        }
+    }
+}
+
+/// Returns an error if the operation selected by `is_paused` is paused
+fn assert_not_paused(
+    deps: Deps,
+    is_paused: impl Fn(&PauseState) -> bool,
+    operation: &str,
+) -> Result<(), AutocompounderError> {
+    let paused = PAUSE_STATE.may_load(deps.storage)?.unwrap_or_default();
+    if is_paused(&paused) {
+        return Err(AutocompounderError::OperationPaused {
+            operation: operation.to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Set or remove the guardian that may pause the vault
+pub fn update_guardian(
+    deps: DepsMut,
+    info: MessageInfo,
+    app: AutocompounderApp,
+    guardian: Option<String>,
+) -> AutocompounderResult {
+    app.admin.assert_admin(deps.as_ref(), &info.sender)?;
+
+    match &guardian {
+        Some(guardian) => {
+            let guardian = deps.api.addr_validate(guardian)?;
+            GUARDIAN.save(deps.storage, &guardian)?;
+        }
+        None => GUARDIAN.remove(deps.storage),
+    }
+
+    Ok(app.custom_response(
+        "update_guardian",
+        vec![("guardian", guardian.unwrap_or_default())],
+    ))
+}
+
+/// Update the deposit caps of the vault
+pub fn update_deposit_caps(
+    deps: DepsMut,
+    info: MessageInfo,
+    app: AutocompounderApp,
+    caps: DepositCaps,
+) -> AutocompounderResult {
+    app.admin.assert_admin(deps.as_ref(), &info.sender)?;
+
+    DEPOSIT_CAPS.save(deps.storage, &caps)?;
+
+    let to_attribute = |cap: Option<Uint128>| cap.map_or("none".to_string(), |cap| cap.to_string());
+    Ok(app.custom_response(
+        "update_deposit_caps",
+        vec![
+            ("max_total_lp", to_attribute(caps.max_total_lp)),
+            (
+                "max_vault_tokens_per_address",
+                to_attribute(caps.max_vault_tokens_per_address),
+            ),
+        ],
+    ))
+}
+
+/// Set who can deposit into the vault
+pub fn update_access_mode(
+    deps: DepsMut,
+    info: MessageInfo,
+    app: AutocompounderApp,
+    access_mode: AccessMode,
+) -> AutocompounderResult {
+    app.admin.assert_admin(deps.as_ref(), &info.sender)?;
+
+    ACCESS_MODE.save(deps.storage, &access_mode)?;
+
+    Ok(app.custom_response(
+        "update_access_mode",
+        vec![("access_mode", format!("{access_mode:?}"))],
+    ))
+}
+
+/// Hand the mint authority of the vault token to `new_admin`, like the module that replaces this one.
+/// The shares stay with their holders, but this module can no longer mint or burn vault tokens.
+pub fn change_vault_token_admin(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    app: AutocompounderApp,
+    new_admin: String,
+) -> AutocompounderResult {
+    app.admin.assert_admin(deps.as_ref(), &info.sender)?;
+
+    let new_admin = deps.api.addr_validate(&new_admin)?;
+    let config = CONFIG.load(deps.storage)?;
+    let msg =
+        change_vault_token_admin_msg(deps.as_ref(), &config, &env.contract.address, &new_admin)?;
+
+    Ok(app
+        .custom_response(
+            "change_vault_token_admin",
+            vec![("new_admin", new_admin.to_string())],
+        )
+        .add_message(msg))
+}
+
+/// Send stray assets from the proxy to `recipient`. Refuses the core assets of the vault.
+pub fn sweep(
+    deps: DepsMut,
+    info: MessageInfo,
+    app: AutocompounderApp,
+    assets: Vec<Asset>,
+    recipient: String,
+) -> AutocompounderResult {
+    app.admin.assert_admin(deps.as_ref(), &info.sender)?;
+
+    let recipient = deps.api.addr_validate(&recipient)?;
+    let config = CONFIG.load(deps.storage)?;
+    if let Some(asset) = assets
+        .iter()
+        .find(|asset| config.is_core_asset(&asset.info))
+    {
+        return Err(AutocompounderError::CannotSweepCoreAsset {
+            asset: asset.info.to_string(),
+        });
+    }
+
+    let swept: Vec<(&str, String)> = assets
+        .iter()
+        .map(|asset| ("swept", asset.to_string()))
+        .chain([("recipient", recipient.to_string())])
+        .collect();
+    let transfer_msg = app.bank(deps.as_ref()).transfer(assets, &recipient)?;
+
+    Ok(app
+        .custom_response("sweep", swept)
+        .add_message(app.executor(deps.as_ref()).execute(vec![transfer_msg])?))
+}
+
+/// Add and remove allowed depositors. Addresses in both lists are removed.
+pub fn update_depositors(
+    deps: DepsMut,
+    info: MessageInfo,
+    app: AutocompounderApp,
+    to_add: Vec<String>,
+    to_remove: Vec<String>,
+) -> AutocompounderResult {
+    app.admin.assert_admin(deps.as_ref(), &info.sender)?;
+
+    for depositor in to_add.iter() {
+        let depositor = deps.api.addr_validate(depositor)?;
+        DEPOSITORS.save(deps.storage, depositor, &Empty {})?;
+    }
+    for depositor in to_remove.iter() {
+        let depositor = deps.api.addr_validate(depositor)?;
+        DEPOSITORS.remove(deps.storage, depositor);
+    }
+
+    Ok(app.custom_response(
+        "update_depositors",
+        vec![
+            ("added", to_add.len().to_string()),
+            ("removed", to_remove.len().to_string()),
+        ],
+    ))
+}
+
+pub fn approve_operator(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    app: AutocompounderApp,
+    operator: String,
+    expires: Option<Expiration>,
+) -> AutocompounderResult {
+    let operator = deps.api.addr_validate(&operator)?;
+    let expires = expires.unwrap_or_default();
+    if expires.is_expired(&env.block) {
+        return Err(AutocompounderError::ExpiredApproval {});
+    }
+    OPERATORS.save(deps.storage, (info.sender, operator.clone()), &expires)?;
+
+    Ok(app.custom_response(
+        "approve_operator",
+        vec![
+            ("operator", operator.to_string()),
+            ("expires", expires.to_string()),
+        ],
+    ))
+}
+
+/// Returns the owner on whose behalf the sender acts, which is the sender itself if no `owner` is given.
+/// Errors if the sender is not an approved operator of the owner.
+fn owner_if_operator(
+    deps: Deps,
+    env: &Env,
+    sender: &Addr,
+    owner: Option<Addr>,
+) -> Result<Addr, AutocompounderError> {
+    let Some(owner) = owner.filter(|owner| owner != sender) else {
+        return Ok(sender.clone());
+    };
+    match OPERATORS.may_load(deps.storage, (owner.clone(), sender.clone()))? {
+        Some(expires) if !expires.is_expired(&env.block) => Ok(owner),
+        _ => Err(AutocompounderError::SenderIsNotOperator { owner }),
+    }
+}
+
+struct PauseUpdate {
+    deposits: Option<bool>,
+    redeems: Option<bool>,
+    withdrawals: Option<bool>,
+    compounding: Option<bool>,
+}
+
+/// Pause or unpause operations of the vault. The guardian can only pause.
+fn update_pause(
+    deps: DepsMut,
+    info: MessageInfo,
+    app: AutocompounderApp,
+    update: PauseUpdate,
+) -> AutocompounderResult {
+    let is_admin = app.admin.is_admin(deps.as_ref(), &info.sender)?;
+    if !is_admin {
+        let guardian = GUARDIAN.may_load(deps.storage)?;
+        if guardian.as_ref() != Some(&info.sender) {
+            return Err(AutocompounderError::SenderIsNotAdminOrGuardian {});
+        }
+        let unpauses = [
+            update.deposits,
+            update.redeems,
+            update.withdrawals,
+            update.compounding,
+        ]
+        .contains(&Some(false));
+        if unpauses {
+            return Err(AutocompounderError::GuardianCannotUnpause {});
+        }
+    }
+
+    let mut paused = PAUSE_STATE.may_load(deps.storage)?.unwrap_or_default();
+    if let Some(deposits) = update.deposits {
+        paused.deposits = deposits;
+    }
+    if let Some(redeems) = update.redeems {
+        paused.redeems = redeems;
     }
+    if let Some(withdrawals) = update.withdrawals {
+        paused.withdrawals = withdrawals;
+    }
+    if let Some(compounding) = update.compounding {
+        paused.compounding = compounding;
+    }
+    PAUSE_STATE.save(deps.storage, &paused)?;
+
+    Ok(app.custom_response(
+        "update_pause",
+        vec![
+            ("deposits", paused.deposits.to_string()),
+            ("redeems", paused.redeems.to_string()),
+            ("withdrawals", paused.withdrawals.to_string()),
+            ("compounding", paused.compounding.to_string()),
+        ],
+    ))
+}
+
+/// Returns an error if the vault is in emergency shutdown
+fn assert_not_shutdown(deps: Deps) -> Result<(), AutocompounderError> {
+    if EMERGENCY_SHUTDOWN.exists(deps.storage) {
+        return Err(AutocompounderError::VaultIsShutdown {});
+    }
+    Ok(())
+}
+
+/// Unstakes all lp tokens of the vault and stops deposits, redemptions and compounding.
+/// If `withdraw_liquidity` is set, the lp tokens are withdrawn and the pool assets are stored by the [`EMERGENCY_WITHDRAWAL_REPLY_ID`] reply.
+pub fn emergency_shutdown(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    app: AutocompounderApp,
+    withdraw_liquidity: bool,
+) -> AutocompounderResult {
+    app.admin.assert_admin(deps.as_ref(), &info.sender)?;
+    assert_not_shutdown(deps.as_ref())?;
+
+    let config = CONFIG.load(deps.storage)?;
+    if withdraw_liquidity && config.unbonding_period.is_some() {
+        return Err(AutocompounderError::CannotWithdrawUnbondingLiquidity {});
+    }
+
+    let staked_lp = query_stake(
+        deps.as_ref(),
+        &app,
+        config.pool_data.dex.clone(),
+        config.lp_asset_entry(),
+        config.unbonding_period,
+    )?;
+    // the liquidity buffer is already unstaked
+    let buffer = LIQUIDITY_BUFFER.may_load(deps.storage)?.unwrap_or_default();
+    LIQUIDITY_BUFFER.remove(deps.storage);
+    let lp_tokens = staked_lp + buffer;
+
+    let unbonded_at = config
+        .unbonding_period
+        .map_or(Expiration::AtHeight(env.block.height), |unbonding_period| {
+            unbonding_period.after(&env.block)
+        });
+    EMERGENCY_SHUTDOWN.save(
+        deps.storage,
+        &EmergencyShutdownState {
+            lp_tokens,
+            assets: vec![],
+            unbonded_at,
+        },
+    )?;
+
+    let mut response = app.custom_response(
+        "emergency_shutdown",
+        vec![
+            ("lp_tokens", lp_tokens.to_string()),
+            ("withdraw_liquidity", withdraw_liquidity.to_string()),
+        ],
+    );
+
+    if !staked_lp.is_zero() {
+        let unstake_msg = unstake_lp_tokens(
+            deps.as_ref(),
+            &app,
+            config.pool_data.dex.clone(),
+            config.lp_asset_entry(),
+            staked_lp,
+            config.unbonding_period,
+        );
+        response = response.add_message(unstake_msg);
+    }
+
+    if withdraw_liquidity && !lp_tokens.is_zero() {
+        let context = pool_assets_context(deps.as_ref(), &app, &config)?;
+        let withdrawal_id =
+            save_reply_context(deps.storage, context, EMERGENCY_WITHDRAWAL_REPLY_ID)?;
+        let dex = app.ans_dex(deps.as_ref(), config.pool_data.dex.clone());
+        let withdraw_msg: CosmosMsg =
+            dex.withdraw_liquidity(AnsAsset::new(config.lp_asset_entry(), lp_tokens))?;
+        response = response.add_submessage(SubMsg::reply_on_success(withdraw_msg, withdrawal_id));
+    }
+
+    Ok(response)
+}
+
+/// Burns vault tokens for their share of the holdings of the vault after an emergency shutdown.
+/// The pending claim of the sender is redeemed as well.
+pub fn emergency_redeem(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    app: AutocompounderApp,
+    amount: Uint128,
+) -> AutocompounderResult {
+    let Some(mut state) = EMERGENCY_SHUTDOWN.may_load(deps.storage)? else {
+        return Err(AutocompounderError::VaultIsNotShutdown {});
+    };
+    let config = CONFIG.load(deps.storage)?;
+
+    // the vault tokens of pending claims are already held by the autocompounder
+    let pending_claim = PENDING_CLAIMS
+        .may_load(deps.storage, info.sender.clone())?
+        .unwrap_or_default();
+    PENDING_CLAIMS.remove(deps.storage, info.sender.clone());
+    let shares = amount + pending_claim;
+    if shares.is_zero() {
+        return Err(AutocompounderError::ZeroRedeemAmount {});
+    }
+
+    // compute the share of the holdings of the vault
+    let total_supply = vault_token_total_supply(deps.as_ref(), &env.contract.address, &config)?;
+    let mut payout: Vec<AnsAsset> = vec![];
+    for asset in state.assets.iter_mut() {
+        let asset_share = asset.amount.multiply_ratio(shares, total_supply);
+        asset.amount -= asset_share;
+        if !asset_share.is_zero() {
+            payout.push(AnsAsset::new(asset.name.clone(), asset_share));
+        }
+    }
+    let lp_share = state.lp_tokens.multiply_ratio(shares, total_supply);
+    state.lp_tokens -= lp_share;
+    EMERGENCY_SHUTDOWN.save(deps.storage, &state)?;
+
+    let mut msgs: Vec<CosmosMsg> = if amount.is_zero() {
+        vec![]
+    } else {
+        let vault_token = AssetBase::new(config.vault_token.clone(), amount);
+        transfer_token_to_autocompounder(vault_token, info.sender.clone(), &env, &info.funds)?
+    };
+    msgs.push(burn_vault_tokens_msg(
+        deps.as_ref(),
+        &config,
+        &env.contract.address,
+        shares,
+    )?);
+
+    if !lp_share.is_zero() {
+        if !state.unbonded_at.is_expired(&env.block) {
+            return Err(AutocompounderError::EmergencyLpStillUnbonding {
+                unbonded_at: state.unbonded_at,
+            });
+        }
+        // claim the unbonded lp tokens if the proxy does not hold them yet
+        let lp_balance = config
+            .liquidity_token
+            .query_balance(&deps.querier, app.proxy_address(deps.as_ref())?)?;
+        if config.unbonding_period.is_some() && lp_balance < lp_share {
+            msgs.push(claim_unbonded_tokens(
+                deps.as_ref(),
+                &app,
+                config.pool_data.dex.clone(),
+                config.lp_asset_entry(),
+            ));
+        }
+        payout.push(AnsAsset::new(config.lp_asset_entry(), lp_share));
+    }
+
+    if !payout.is_empty() {
+        let transfer_msg = app.bank(deps.as_ref()).transfer(payout, &info.sender)?;
+        msgs.push(
+            app.executor(deps.as_ref())
+                .execute(vec![transfer_msg])?
+                .into(),
+        );
+    }
+
+    Ok(app
+        .custom_response(
+            "emergency_redeem",
+            vec![
+                ("vault_token_burn_amount", shares.to_string()),
+                ("lp_token_amount", lp_share.to_string()),
+            ],
+        )
+        .add_messages(msgs))
 }
 
 pub fn update_staking_config(
@@ -118,6 +699,41 @@ pub fn update_staking_config(
     ))
 }
 
+/// Update the liquidity buffer of a bonding vault.
+/// The buffer moves towards the new target when rewards are compounded.
+pub fn update_liquidity_buffer(
+    deps: DepsMut,
+    info: MessageInfo,
+    app: AutocompounderApp,
+    target: Decimal,
+    instant_exit_fee: Decimal,
+) -> AutocompounderResult {
+    app.admin.assert_admin(deps.as_ref(), &info.sender)?;
+
+    let config = CONFIG.load(deps.storage)?;
+    if config.unbonding_period.is_none() {
+        return Err(AutocompounderError::UnbondingNotEnabled {});
+    }
+    check_fee(target)?;
+    check_fee(instant_exit_fee)?;
+
+    LIQUIDITY_BUFFER_CONFIG.save(
+        deps.storage,
+        &LiquidityBufferConfig {
+            target,
+            instant_exit_fee,
+        },
+    )?;
+
+    Ok(app.custom_response(
+        "update_liquidity_buffer",
+        vec![
+            ("target", target.to_string()),
+            ("instant_exit_fee", instant_exit_fee.to_string()),
+        ],
+    ))
+}
+
 /// Update the application configuration.
 pub fn update_fee_config(
     deps: DepsMut,
@@ -166,14 +782,132 @@ pub fn update_fee_config(
 }
 
 // This is the function that is called when the user wants to pool AND stake their funds
+/// Deposits `funds` for `recipient`. `received` is a cw20 pool asset that was already sent to the app with `Send`.
+#[allow(clippy::too_many_arguments)]
 pub fn deposit(
     deps: DepsMut,
     info: MessageInfo,
     env: Env,
     app: AutocompounderApp,
-    mut funds: Vec<AnsAsset>,
+    funds: Vec<AnsAsset>,
     recipient: Option<Addr>,
     max_spread: Option<Decimal>,
+    received: Option<Asset>,
+) -> AutocompounderResult {
+    let recipient = unwrap_recipient_is_allowed(
+        recipient,
+        &info.sender,
+        forbidden_deposit_addresses(deps.as_ref(), &env, &app)?,
+    )?;
+    assert_depositors_allowed(deps.as_ref(), &[&info.sender, &recipient])?;
+
+    let response = app.custom_response("deposit", vec![("recipient", recipient.to_string())]);
+    let context = ReplyContext {
+        recipient: Some(recipient),
+        ..Default::default()
+    };
+    provide_deposit(
+        deps, info, &env, app, funds, max_spread, received, context, response,
+    )
+}
+
+/// Deposit the funds of many recipients at once.
+/// Every deposit must be in the asset ratio of the batch total, so that every recipient is credited
+/// with the same share of each deposited pool asset.
+pub fn batch_deposit(
+    deps: DepsMut,
+    info: MessageInfo,
+    env: Env,
+    app: AutocompounderApp,
+    deposits: Vec<(Addr, Vec<AnsAsset>)>,
+    max_spread: Option<Decimal>,
+) -> AutocompounderResult {
+    if deposits.is_empty() {
+        return Err(AutocompounderError::EmptyBatchDeposit {});
+    }
+
+    let forbidden = forbidden_deposit_addresses(deps.as_ref(), &env, &app)?;
+    let mut addresses = vec![&info.sender];
+    for (recipient, _) in deposits.iter() {
+        unwrap_recipient_is_allowed(Some(recipient.clone()), &info.sender, forbidden.clone())?;
+        addresses.push(recipient);
+    }
+    assert_depositors_allowed(deps.as_ref(), &addresses)?;
+
+    let mut funds: Vec<AnsAsset> = vec![];
+    for asset in deposits.iter().flat_map(|(_, assets)| assets) {
+        match funds.iter_mut().find(|fund| fund.name == asset.name) {
+            Some(fund) => fund.amount += asset.amount,
+            None => funds.push(asset.clone()),
+        }
+    }
+
+    let batch_recipients = batch_deposit_shares(&deposits, &funds)?;
+    let response = app.custom_response(
+        "batch_deposit",
+        vec![("recipients", deposits.len().to_string())],
+    );
+    let context = ReplyContext {
+        batch_recipients,
+        ..Default::default()
+    };
+    provide_deposit(
+        deps, info, &env, app, funds, max_spread, None, context, response,
+    )
+}
+
+/// Calculates the share of every recipient of a batch deposit.
+/// Errors if a deposit is not in the asset ratio of the batch total, as its share would be ambiguous.
+fn batch_deposit_shares(
+    deposits: &[(Addr, Vec<AnsAsset>)],
+    total_funds: &[AnsAsset],
+) -> AutocompounderResult<Vec<(Addr, Decimal)>> {
+    let deposited: Vec<&AnsAsset> = total_funds.iter().filter(|f| !f.amount.is_zero()).collect();
+    let Some(first) = deposited.first() else {
+        return Ok(vec![]);
+    };
+
+    deposits
+        .iter()
+        .map(|(recipient, assets)| {
+            let amount_of = |total: &AnsAsset| -> Uint128 {
+                assets
+                    .iter()
+                    .filter(|asset| asset.name == total.name)
+                    .map(|asset| asset.amount)
+                    .sum()
+            };
+            let first_amount = amount_of(first);
+            // amount / total must be equal for every asset
+            let in_ratio = deposited.iter().all(|total| {
+                amount_of(total).full_mul(first.amount) == first_amount.full_mul(total.amount)
+            });
+            if !in_ratio {
+                return Err(AutocompounderError::BatchDepositRatioMismatch {
+                    recipient: recipient.clone(),
+                });
+            }
+            Ok((
+                recipient.clone(),
+                Decimal::from_ratio(first_amount, first.amount),
+            ))
+        })
+        .collect()
+}
+
+/// Transfers the deposited funds to the Account and provides them as liquidity.
+/// The vault tokens are minted to the recipients of `context` in the reply.
+#[allow(clippy::too_many_arguments)]
+fn provide_deposit(
+    deps: DepsMut,
+    info: MessageInfo,
+    env: &Env,
+    app: AutocompounderApp,
+    mut funds: Vec<AnsAsset>,
+    max_spread: Option<Decimal>,
+    received: Option<Asset>,
+    context: ReplyContext,
+    response: Response,
 ) -> AutocompounderResult {
     let config = CONFIG.load(deps.storage)?;
     let fee_config = FEE_CONFIG.load(deps.storage)?;
@@ -199,6 +933,12 @@ pub fn deposit(
         .deduct_many(&info.funds.clone().into())?
         .purge();
 
+    // forward the cw20 asset that was already sent to the app
+    if let Some(received) = received {
+        claimed_deposits.deduct(&received)?.purge();
+        messages.push(received.transfer_msg(app.proxy_address(deps.as_ref())?)?);
+    }
+
     // if there is only one asset, we need to add the other asset too, but with zero amount
     let cw_20_transfer_msgs_res: Result<Vec<CosmosMsg>, AbstractSdkError> = claimed_deposits
         .into_iter()
@@ -235,34 +975,69 @@ pub fn deposit(
     let provide_liquidity_msg: CosmosMsg =
         dex.provide_liquidity(funds, Some(max_spread.unwrap_or(config.max_swap_spread)))?;
 
+    // save the user address to the cache for later use in reply
+
+    // CACHED_FEE_AMOUNT.save(deps.storage, &current_fee_balance)?;
+
+    // cache the pool assets held before the deposit, to refund the unused deposit assets in the reply
+    let context = ReplyContext {
+        depositor: Some(info.sender.clone()),
+        cached_assets: pool_assets_context(deps.as_ref(), &app, &config)?.cached_assets,
+        ..context
+    };
+    let provision_id = save_reply_context(deps.storage, context, LP_PROVISION_REPLY_ID)?;
+
     let sub_msg = SubMsg {
-        id: LP_PROVISION_REPLY_ID,
+        id: provision_id,
         msg: provide_liquidity_msg,
         gas_limit: None,
         reply_on: ReplyOn::Success,
     };
     submessages.push(sub_msg);
 
-    // save the user address to the cache for later use in reply
-
-    // CACHED_FEE_AMOUNT.save(deps.storage, &current_fee_balance)?;
-    let recipient = unwrap_recipient_is_allowed(
-        recipient,
-        &info.sender,
-        forbidden_deposit_addresses(deps.as_ref(), &env, &app)?,
-    )?;
-    CACHED_USER_ADDR.save(deps.storage, &recipient)?;
+    // compound the pending rewards before the deposit reaches the account, so the new shares are priced with them.
+    // The reply caches the pool assets of the deposit again, so what the compound leaves behind isn't refunded.
+    let compound_submsg = if compound_on_deposit(deps.as_ref())? {
+        let context = ReplyContext {
+            deposit_submsg_id: Some(provision_id),
+            ..Default::default()
+        };
+        let compound_id = save_reply_context(deps.storage, context, DEPOSIT_COMPOUND_REPLY_ID)?;
+        Some(deposit_compound_submsg(env, compound_id)?)
+    } else {
+        None
+    };
 
-    let mut response = app
-        .custom_response("deposit", vec![("recipient", recipient.to_string())])
-        .add_messages(messages)
-        .add_submessages(submessages);
+    let mut response = response
+        .add_submessages(compound_submsg)
+        .add_messages(messages);
 
+    // the fees are sent before providing liquidity so they are not refunded as unused deposit assets
     if !account_msgs.messages().is_empty() {
         response = response.add_message(app.executor(deps.as_ref()).execute(vec![account_msgs])?);
     }
 
-    Ok(response)
+    Ok(response.add_submessages(submessages))
+}
+
+/// Whether the pending rewards are compounded before a deposit
+fn compound_on_deposit(deps: Deps) -> StdResult<bool> {
+    let paused = PAUSE_STATE.may_load(deps.storage)?.unwrap_or_default();
+    Ok(COMPOUND_ON_DEPOSIT
+        .may_load(deps.storage)?
+        .unwrap_or_default()
+        && !paused.compounding)
+}
+
+/// Compounds the pending rewards with a `Compound` call to the app itself.
+/// A failing compound is reverted on its own and the deposit continues in the reply.
+fn deposit_compound_submsg(env: &Env, reply_id: u64) -> StdResult<SubMsg> {
+    let compound_msg = wasm_execute(
+        &env.contract.address,
+        &ExecuteMsg::from(AutocompounderExecuteMsg::Compound {}),
+        vec![],
+    )?;
+    Ok(SubMsg::reply_always(compound_msg, reply_id))
 }
 
 fn consolidate_funds(
@@ -356,6 +1131,21 @@ fn unwrap_recipient_is_allowed(
     }
 }
 
+/// Returns an error if the vault is allowlisted and one of the `addresses` is not an allowed depositor
+fn assert_depositors_allowed(deps: Deps, addresses: &[&Addr]) -> Result<(), AutocompounderError> {
+    if ACCESS_MODE.may_load(deps.storage)?.unwrap_or_default() == AccessMode::Open {
+        return Ok(());
+    }
+    for address in addresses {
+        if !DEPOSITORS.has(deps.storage, (*address).clone()) {
+            return Err(AutocompounderError::DepositorNotAllowed {
+                address: (*address).clone(),
+            });
+        }
+    }
+    Ok(())
+}
+
 fn forbidden_deposit_addresses(
     deps: Deps,
     env: &Env,
@@ -364,6 +1154,7 @@ fn forbidden_deposit_addresses(
     Ok(vec![app.proxy_address(deps)?, env.contract.address.clone()])
 }
 
+/// Deposits lp tokens for `recipient`. `received` are cw20 lp tokens that were already sent to the app with `Send`.
 fn deposit_lp(
     deps: DepsMut,
     info: MessageInfo,
@@ -371,67 +1162,133 @@ fn deposit_lp(
     app: AutocompounderApp,
     lp_asset: AnsAsset,
     recipient: Option<Addr>,
+    received: Option<Asset>,
 ) -> AutocompounderResult {
     let config = CONFIG.load(deps.storage)?;
-    let fee_config = FEE_CONFIG.load(deps.storage)?;
     let ans = app.name_service(deps.as_ref());
     let lp_token = ans.query(&lp_asset)?;
-    let lp_asset_entry = lp_asset.name.clone();
     let recipient = unwrap_recipient_is_allowed(
         recipient,
         &info.sender,
         forbidden_deposit_addresses(deps.as_ref(), &env, &app)?,
     )?;
 
+    assert_depositors_allowed(deps.as_ref(), &[&info.sender, &recipient])?;
+
     if lp_token.info != config.liquidity_token {
         return Err(AutocompounderError::SenderIsNotLpToken {});
     };
 
-    // transfer the asset to the proxy contract
-    let transfer_msg = transfer_token_to_proxy(lp_token, info.sender, &app, deps.as_ref())?;
+    let response = app.custom_response("deposit-lp", vec![("recipient", recipient.to_string())]);
 
-    let staked_lp = query_stake(
-        deps.as_ref(),
+    // compound the pending rewards first, the vault tokens are then minted in the reply
+    if compound_on_deposit(deps.as_ref())? {
+        let context = ReplyContext {
+            recipient: Some(recipient),
+            depositor: Some(info.sender),
+            lp_deposit: Some(lp_asset),
+            received_lp: received,
+            ..Default::default()
+        };
+        let compound_id = save_reply_context(deps.storage, context, DEPOSIT_COMPOUND_REPLY_ID)?;
+        return Ok(response.add_submessage(deposit_compound_submsg(&env, compound_id)?));
+    }
+
+    mint_lp_deposit(
+        deps,
+        &env,
         &app,
-        config.pool_data.dex.clone(),
-        lp_asset_entry,
-        config.unbonding_period,
-    )?;
+        lp_asset,
+        info.sender,
+        recipient,
+        received,
+        response,
+    )
+}
+
+/// Transfers the deposited lp tokens to the Account, stakes them and mints the vault tokens of `recipient`.
+/// The lp tokens first fill pending claims, like the lp tokens of a deposit of pool assets.
+/// `received` are cw20 lp tokens that were already sent to the app, otherwise they are taken from `depositor`.
+#[allow(clippy::too_many_arguments)]
+pub fn mint_lp_deposit(
+    mut deps: DepsMut,
+    env: &Env,
+    app: &AutocompounderApp,
+    lp_asset: AnsAsset,
+    depositor: Addr,
+    recipient: Addr,
+    received: Option<Asset>,
+    response: Response,
+) -> AutocompounderResult {
+    let config = CONFIG.load(deps.storage)?;
+    let fee_config = FEE_CONFIG.load(deps.storage)?;
+    let lp_token = app.name_service(deps.as_ref()).query(&lp_asset)?;
+
+    // transfer the asset to the proxy contract
+    let transfer_msg = match received {
+        Some(received) => received.transfer_msg(app.proxy_address(deps.as_ref())?)?,
+        None => transfer_token_to_proxy(lp_token, depositor, app, deps.as_ref())?,
+    };
+
+    let staked_lp = query_total_lp(deps.as_ref(), app, &config)?;
 
     let (lp_asset, fee_asset) = deduct_fee(lp_asset, fee_config.deposit);
     let fee_msg = transfer_to_msgs(
-        &app,
+        app,
         deps.as_ref(),
         fee_asset,
         &fee_config.fee_collector_addr,
     )?;
 
-    let current_vault_supply = vault_token_total_supply(deps.as_ref(), &config)?;
-    let mint_amount = convert_to_shares(lp_asset.amount, staked_lp, current_vault_supply);
+    let current_vault_supply =
+        vault_token_total_supply(deps.as_ref(), &env.contract.address, &config)?;
+    let mint_amount = convert_to_shares(
+        lp_asset.amount,
+        staked_lp,
+        current_vault_supply,
+        decimal_offset(deps.storage)?,
+    );
     if mint_amount.is_zero() {
         return Err(AutocompounderError::ZeroMintAmount {});
     }
 
-    let mint_msg = mint_vault_tokens_msg(
+    let (filled_lp, claim_msgs) = fill_pending_claims_msgs(
+        deps.branch(),
+        env,
+        app,
+        &config,
+        &fee_config,
+        lp_asset.amount,
+        staked_lp,
+        current_vault_supply,
+    )?;
+    let lp_to_stake = lp_asset.amount.checked_sub(filled_lp)?;
+
+    if let Some(caps) = DEPOSIT_CAPS.may_load(deps.storage)? {
+        let balance = vault_token_balance(deps.as_ref(), &config, recipient.clone())?;
+        check_deposit_caps(&caps, staked_lp + lp_to_stake, balance + mint_amount)?;
+    }
+
+    let mint_msgs = mint_vault_tokens_msgs(
+        deps.as_ref(),
         &config,
         &env.contract.address,
         recipient.clone(),
         mint_amount,
-        config.pool_data.dex.clone(),
-    )?;
-    let stake_msg = stake_lp_tokens(
-        deps.as_ref(),
-        &app,
-        config.pool_data.dex,
-        lp_asset,
-        config.unbonding_period,
     )?;
+    let mut response = response.add_message(transfer_msg).add_messages(mint_msgs);
+    if !lp_to_stake.is_zero() {
+        let stake_msg = stake_lp_tokens(
+            deps.as_ref(),
+            app,
+            config.pool_data.dex,
+            AnsAsset::new(lp_asset.name, lp_to_stake),
+            config.unbonding_period,
+        )?;
+        response = response.add_message(stake_msg);
+    }
 
-    Ok(app
-        .custom_response("deposit-lp", vec![("recipient", recipient.to_string())])
-        .add_message(transfer_msg)
-        .add_messages(vec![mint_msg, stake_msg])
-        .add_message(fee_msg))
+    Ok(response.add_message(fee_msg).add_submessages(claim_msgs))
 }
 
 /// Deducts a specified fee from a given LP asset.
@@ -558,7 +1415,7 @@ pub fn batch_unbond(
         .take(limit)
         .collect::<StdResult<Vec<(Addr, Uint128)>>>()?;
 
-    let (total_lp_amount_to_unbond, total_vault_tokens_to_burn, updated_claims) =
+    let (total_lp_amount_to_unbond, total_vault_tokens_to_burn, new_claims) =
         calculate_withdrawals(
             deps.as_ref(),
             &config,
@@ -572,12 +1429,22 @@ pub fn batch_unbond(
     for claim in pending_claims.iter() {
         PENDING_CLAIMS.remove(deps.storage, claim.0.clone());
     }
-    // update claims
-    updated_claims
-        .into_iter()
-        .try_for_each(|(addr, claims)| -> StdResult<()> {
-            CLAIMS.save(deps.storage, addr, &claims)
-        })?;
+    // register the new claims, either as NFTs or on the claims of the users
+    let claim_nft_msgs = match CLAIMS_NFT.may_load(deps.storage)? {
+        Some(claims_nft) => mint_claim_nfts(deps.storage, &claims_nft, new_claims)?,
+        None => {
+            new_claims
+                .into_iter()
+                .try_for_each(|(addr, claim)| -> StdResult<()> {
+                    let mut claims = CLAIMS
+                        .may_load(deps.storage, addr.clone())?
+                        .unwrap_or_default();
+                    claims.push(claim);
+                    CLAIMS.save(deps.storage, addr, &claims)
+                })?;
+            vec![]
+        }
+    };
 
     let unstake_msg = unstake_lp_tokens(
         deps.as_ref(),
@@ -590,10 +1457,10 @@ pub fn batch_unbond(
     );
 
     let burn_msg = burn_vault_tokens_msg(
+        deps.as_ref(),
         &config,
         &env.contract.address,
         total_vault_tokens_to_burn,
-        config.pool_data.dex.clone(),
     )?;
 
     Ok(app
@@ -604,67 +1471,248 @@ pub fn batch_unbond(
                 ("burn_amount", total_vault_tokens_to_burn.to_string()),
             ],
         )
-        .add_messages(vec![unstake_msg, burn_msg]))
+        .add_messages(vec![unstake_msg, burn_msg])
+        .add_messages(claim_nft_msgs))
+}
+
+/// Mints a claims NFT for each of the new claims and stores the claim under its token id.
+fn mint_claim_nfts(
+    storage: &mut dyn Storage,
+    claims_nft: &Addr,
+    new_claims: Vec<(Addr, Claim)>,
+) -> StdResult<Vec<CosmosMsg>> {
+    let mut count = CLAIMS_NFT_COUNT.may_load(storage)?.unwrap_or_default();
+    let msgs = new_claims
+        .into_iter()
+        .map(|(owner, claim)| {
+            count += 1;
+            let token_id = count.to_string();
+            NFT_CLAIMS.save(storage, token_id.clone(), &claim)?;
+            mint_claim_nft_msg(claims_nft, token_id, &owner, &claim)
+        })
+        .collect::<StdResult<Vec<CosmosMsg>>>()?;
+    CLAIMS_NFT_COUNT.save(storage, &count)?;
+    Ok(msgs)
 }
 
 /// Handles receiving CW20 messages
 pub fn receive(
-    _deps: DepsMut,
-    _env: Env,
-    _info: MessageInfo,
-    _app: AutocompounderApp,
-    _msg: Cw20ReceiveMsg,
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    app: AutocompounderApp,
+    msg: Cw20ReceiveMsg,
 ) -> AutocompounderResult {
-    Err(AutocompounderError::Std(
-        cosmwasm_std::StdError::GenericErr {
-            msg: "cannot recieve c20 tokens. Deposit and redeem using allowance".to_string(),
-        },
-    ))
+    let received = Asset::cw20(info.sender, msg.amount);
+    // act on behalf of the sender of the cw20 tokens
+    let info = MessageInfo {
+        sender: deps.api.addr_validate(&msg.sender)?,
+        funds: vec![],
+    };
+
+    match from_json(&msg.msg)? {
+        Cw20HookMsg::Redeem { recipient } => {
+            assert_not_paused(deps.as_ref(), |paused| paused.redeems, "Redeems")?;
+            assert_not_shutdown(deps.as_ref())?;
+            let config = CONFIG.load(deps.storage)?;
+            if received.info != config.vault_token {
+                return Err(AutocompounderError::SenderIsNotVaultToken {});
+            }
+            let recipient = unwrap_recipient_is_allowed(
+                recipient,
+                &info.sender,
+                forbidden_deposit_addresses(deps.as_ref(), &env, &app)?,
+            )?;
+            redeem_vault_tokens(deps, env, app, config, recipient, received.amount, vec![])
+        }
+        Cw20HookMsg::Deposit {
+            recipient,
+            max_spread,
+        } => {
+            assert_not_paused(deps.as_ref(), |paused| paused.deposits, "Deposits")?;
+            assert_not_shutdown(deps.as_ref())?;
+            let config = CONFIG.load(deps.storage)?;
+            if !config.pool_assets.contains(&received.info) {
+                return Err(AutocompounderError::SenderIsNotPoolAsset {});
+            }
+            let ans_host = app.ans_host(deps.as_ref())?;
+            let funds = vec![received.resolve(&deps.querier, &ans_host)?];
+            deposit(
+                deps,
+                info,
+                env,
+                app,
+                funds,
+                recipient,
+                max_spread,
+                Some(received),
+            )
+        }
+        Cw20HookMsg::DepositLp { recipient } => {
+            assert_not_paused(deps.as_ref(), |paused| paused.deposits, "Deposits")?;
+            assert_not_shutdown(deps.as_ref())?;
+            let config = CONFIG.load(deps.storage)?;
+            if received.info != config.liquidity_token {
+                return Err(AutocompounderError::SenderIsNotLpToken {});
+            }
+            let ans_host = app.ans_host(deps.as_ref())?;
+            let lp_token = received.resolve(&deps.querier, &ans_host)?;
+            deposit_lp(deps, info, env, app, lp_token, recipient, Some(received))
+        }
+    }
+}
+
+/// Handles the claim NFTs sent to the app with the cw721 `SendNft`.
+/// The app holds the NFT during the call, so it can burn the NFT without an approval of its previous holder.
+pub fn receive_nft(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    app: AutocompounderApp,
+    msg: Cw721ReceiveMsg,
+) -> AutocompounderResult {
+    let claims_nft = CLAIMS_NFT
+        .may_load(deps.storage)?
+        .ok_or(AutocompounderError::SenderIsNotClaimsNft {})?;
+    if info.sender != claims_nft {
+        return Err(AutocompounderError::SenderIsNotClaimsNft {});
+    }
+    let holder = deps.api.addr_validate(&msg.sender)?;
+
+    match from_json(&msg.msg)? {
+        Cw721HookMsg::Withdraw {} => {
+            assert_not_paused(deps.as_ref(), |paused| paused.withdrawals, "Withdrawals")?;
+            let config = CONFIG.load(deps.storage)?;
+            let Some(claim) = NFT_CLAIMS.may_load(deps.storage, msg.token_id.clone())? else {
+                return Err(AutocompounderError::NoClaims {});
+            };
+            if !claim.unbonding_timestamp.is_expired(&env.block) {
+                return Err(AutocompounderError::NoMaturedClaims {});
+            }
+            NFT_CLAIMS.remove(deps.storage, msg.token_id.clone());
+            let burn_msg = burn_claim_nft_msg(&claims_nft, msg.token_id)?;
+            withdraw_matured_claims(deps, &app, &config, holder, vec![claim], vec![burn_msg])
+        }
+    }
 }
 
 /// Redeems the vault tokens for the underlying asset.
 /// This function is called by the vault token contract.
 /// It checks whether the lp staking contract has a unbonding period set or not.
 /// If not, it redeems the vault tokens for the underlying asset, swaps them and sends them to the sender.
-/// If yes, it registers a pre-claim for the sender.  This will be processed in batches by calling `ExecuteMsg::BatchUnbond` .
+/// If yes, it redeems instantly from the liquidity buffer when the buffer holds enough lp tokens.
+/// Otherwise, it registers a pre-claim for the sender.  This will be processed in batches by calling `ExecuteMsg::BatchUnbond` .
 fn redeem(
     deps: DepsMut,
     env: Env,
     app: AutocompounderApp,
     info: MessageInfo,
     amount_of_vault_tokens_to_be_burned: Uint128,
+    owner: Option<Addr>,
     recipient: Option<Addr>,
 ) -> AutocompounderResult {
     // parse sender
+    let owner = owner_if_operator(deps.as_ref(), &env, &info.sender, owner)?;
     let recipient = unwrap_recipient_is_allowed(
         recipient,
-        &info.sender,
+        &owner,
         forbidden_deposit_addresses(deps.as_ref(), &env, &app)?,
     )?;
+    // operators can only redeem to the owner or themselves
+    if info.sender != owner && recipient != owner && recipient != info.sender {
+        return Err(AutocompounderError::RecipientNotPermitted { recipient });
+    }
     let config = CONFIG.load(deps.storage)?;
 
+    // the vault tokens are taken from the owner
+    let vault_token = AssetBase::new(
+        config.vault_token.clone(),
+        amount_of_vault_tokens_to_be_burned,
+    );
+    let transfer_msgs = transfer_token_to_autocompounder(vault_token, owner, &env, &info.funds)?;
+
+    redeem_vault_tokens(
+        deps,
+        env,
+        app,
+        config,
+        recipient,
+        amount_of_vault_tokens_to_be_burned,
+        transfer_msgs,
+    )
+}
+
+/// Redeems vault tokens that are transferred to the app by `transfer_msgs`, or were already received with a cw20 `Send`.
+fn redeem_vault_tokens(
+    deps: DepsMut,
+    env: Env,
+    app: AutocompounderApp,
+    config: Config,
+    recipient: Addr,
+    amount_of_vault_tokens_to_be_burned: Uint128,
+    transfer_msgs: Vec<CosmosMsg>,
+) -> AutocompounderResult {
     if config.unbonding_period.is_none() {
         redeem_without_bonding_period(
             deps,
             &env,
             &recipient,
-            info,
+            transfer_msgs,
+            config,
+            &app,
+            amount_of_vault_tokens_to_be_burned,
+        )
+    } else if let Some(lp_tokens_withdraw_amount) = liquidity_buffer_redemption(
+        deps.as_ref(),
+        &env,
+        &app,
+        &config,
+        amount_of_vault_tokens_to_be_burned,
+    )? {
+        redeem_from_liquidity_buffer(
+            deps,
+            &env,
+            &recipient,
+            transfer_msgs,
             config,
             &app,
             amount_of_vault_tokens_to_be_burned,
+            lp_tokens_withdraw_amount,
         )
     } else {
         receive_and_register_claim(
             deps,
-            &env,
             app,
             recipient,
-            info,
+            transfer_msgs,
             amount_of_vault_tokens_to_be_burned,
         )
     }
 }
 
+/// Cancels the pending claim of `owner` and returns its vault tokens.
+fn cancel_pending_claim(
+    deps: DepsMut,
+    app: AutocompounderApp,
+    owner: Addr,
+) -> AutocompounderResult {
+    let config = CONFIG.load(deps.storage)?;
+    let amount = PENDING_CLAIMS
+        .may_load(deps.storage, owner.clone())?
+        .ok_or(AutocompounderError::NoClaims {})?;
+    PENDING_CLAIMS.remove(deps.storage, owner.clone());
+
+    // the vault tokens of pending claims are held by the app until they are burned
+    let transfer_msg = Asset::new(config.vault_token, amount).transfer_msg(&owner)?;
+
+    Ok(app
+        .custom_response(
+            "cancel_pending_claim",
+            vec![("owner", owner.to_string()), ("amount", amount.to_string())],
+        )
+        .add_message(transfer_msg))
+}
+
 /// Registers a pending-claim when Redeem is called for a pool with bonding period.
 /// This will store the claim of te user and add it to any pending claims.
 /// The claim will be processed in the next batch unbonding
@@ -693,18 +1741,11 @@ fn register_pre_claim(
 
 fn receive_and_register_claim(
     deps: DepsMut,
-    env: &Env,
     app: AutocompounderApp,
     recipient: Addr,
-    info: MessageInfo,
+    transfer_msgs: Vec<CosmosMsg>,
     amount_of_vault_tokens_to_be_burned: Uint128,
 ) -> AutocompounderResult {
-    let config = CONFIG.load(deps.as_ref().storage)?;
-    let sender = info.sender.clone();
-
-    let vault_token = AssetBase::new(config.vault_token, amount_of_vault_tokens_to_be_burned);
-    let transfer_msgs = transfer_token_to_autocompounder(vault_token, sender, env, &info.funds)?;
-
     register_pre_claim(deps, recipient.clone(), amount_of_vault_tokens_to_be_burned)?;
 
     Ok(app
@@ -724,33 +1765,23 @@ fn redeem_without_bonding_period(
     deps: DepsMut,
     env: &Env,
     recipient: &Addr,
-    info: MessageInfo,
+    transfer_msgs: Vec<CosmosMsg>,
     config: Config,
     app: &AutocompounderApp,
     amount_of_vault_tokens_to_be_burned: Uint128,
 ) -> Result<Response, AutocompounderError> {
     let fee_config = FEE_CONFIG.load(deps.storage)?;
-    let sender = info.sender.clone();
 
-    let vault_token_asset = AssetBase::new(
-        config.vault_token.clone(),
-        amount_of_vault_tokens_to_be_burned,
-    );
-    let transfer_msgs =
-        transfer_token_to_autocompounder(vault_token_asset, sender, env, &info.funds)?;
-
-    // save the user address and the assets owned by the contract to the cache for later use in reply
-    CACHED_USER_ADDR.save(deps.storage, recipient)?;
-    let owned_assets = app.bank(deps.as_ref()).balances(&config.pool_data.assets)?;
-    owned_assets.into_iter().try_for_each(|asset| {
-        CACHED_ASSETS
-            // CACHED_ASSETS are saved with the key being cwasset::asset:AssetInfo.to_string()
-            .save(deps.storage, asset.info.to_string(), &asset.amount)
-            .map_err(AutocompounderError::Std)
-    })?;
+    // save the user address and the assets owned by the contract in the reply context
+    let context = ReplyContext {
+        recipient: Some(recipient.clone()),
+        ..pool_assets_context(deps.as_ref(), app, &config)?
+    };
+    let withdrawal_id = save_reply_context(deps.storage, context, LP_WITHDRAWAL_REPLY_ID)?;
 
     // 1) get the total supply of Vault token
-    let total_supply_vault = vault_token_total_supply(deps.as_ref(), &config)?;
+    let total_supply_vault =
+        vault_token_total_supply(deps.as_ref(), &env.contract.address, &config)?;
     let lp_asset_entry = config.lp_asset_entry();
 
     // 2) get total staked lp token
@@ -766,6 +1797,7 @@ fn redeem_without_bonding_period(
         amount_of_vault_tokens_to_be_burned,
         total_lp_tokens_staked_in_vault,
         total_supply_vault,
+        decimal_offset(deps.storage)?,
     );
 
     // Substract withdrawal fee from the amount of lp tokens allocated to the user
@@ -782,17 +1814,17 @@ fn redeem_without_bonding_period(
         None,
     );
     let burn_msg = burn_vault_tokens_msg(
+        deps.as_ref(),
         &config,
         &env.contract.address,
         amount_of_vault_tokens_to_be_burned,
-        config.pool_data.dex.clone(),
     )?;
 
     // 3) withdraw lp tokens
     let dex = app.ans_dex(deps.as_ref(), config.pool_data.dex);
     let withdraw_msg: CosmosMsg =
         dex.withdraw_liquidity(AnsAsset::new(lp_asset_entry, lp_tokens_withdraw_amount))?;
-    let sub_msg = SubMsg::reply_on_success(withdraw_msg, LP_WITHDRAWAL_REPLY_ID);
+    let sub_msg = SubMsg::reply_on_success(withdraw_msg, withdrawal_id);
 
     // TODO: Check all the lp_token() calls and make sure they are everywhere.
 
@@ -816,6 +1848,96 @@ fn redeem_without_bonding_period(
         .add_submessage(sub_msg))
 }
 
+/// Returns the lp tokens for redeeming `amount_of_vault_tokens_to_be_burned` from the liquidity buffer,
+/// after the withdrawal and instant exit fees, or `None` if the buffer can't serve the redemption.
+fn liquidity_buffer_redemption(
+    deps: Deps,
+    env: &Env,
+    app: &AutocompounderApp,
+    config: &Config,
+    amount_of_vault_tokens_to_be_burned: Uint128,
+) -> AutocompounderResult<Option<Uint128>> {
+    let Some(buffer_config) = LIQUIDITY_BUFFER_CONFIG.may_load(deps.storage)? else {
+        return Ok(None);
+    };
+    let buffer = LIQUIDITY_BUFFER.may_load(deps.storage)?.unwrap_or_default();
+    if buffer.is_zero() {
+        return Ok(None);
+    }
+    let fee_config = FEE_CONFIG.load(deps.storage)?;
+
+    let lp_tokens = convert_to_assets(
+        amount_of_vault_tokens_to_be_burned,
+        query_total_lp(deps, app, config)?,
+        vault_token_total_supply(deps, &env.contract.address, config)?,
+        decimal_offset(deps.storage)?,
+    );
+    let lp_tokens = lp_tokens.checked_sub(lp_tokens * fee_config.withdrawal)?;
+    let lp_tokens = lp_tokens.checked_sub(lp_tokens * buffer_config.instant_exit_fee)?;
+
+    if lp_tokens.is_zero() || lp_tokens > buffer {
+        return Ok(None);
+    }
+    Ok(Some(lp_tokens))
+}
+
+/// Redeems the vault tokens from the liquidity buffer of a bonding vault.
+/// This will burn the vault tokens, withdraw the lp tokens from the buffer and send the underlying assets to the user
+#[allow(clippy::too_many_arguments)]
+fn redeem_from_liquidity_buffer(
+    deps: DepsMut,
+    env: &Env,
+    recipient: &Addr,
+    transfer_msgs: Vec<CosmosMsg>,
+    config: Config,
+    app: &AutocompounderApp,
+    amount_of_vault_tokens_to_be_burned: Uint128,
+    lp_tokens_withdraw_amount: Uint128,
+) -> AutocompounderResult {
+    // save the user address and the assets owned by the contract in the reply context
+    let context = ReplyContext {
+        recipient: Some(recipient.clone()),
+        ..pool_assets_context(deps.as_ref(), app, &config)?
+    };
+    let withdrawal_id = save_reply_context(deps.storage, context, LP_WITHDRAWAL_REPLY_ID)?;
+
+    LIQUIDITY_BUFFER.update(deps.storage, |buffer| -> StdResult<_> {
+        Ok(buffer.checked_sub(lp_tokens_withdraw_amount)?)
+    })?;
+
+    let burn_msg = burn_vault_tokens_msg(
+        deps.as_ref(),
+        &config,
+        &env.contract.address,
+        amount_of_vault_tokens_to_be_burned,
+    )?;
+
+    let dex = app.ans_dex(deps.as_ref(), config.pool_data.dex.clone());
+    let withdraw_msg: CosmosMsg = dex.withdraw_liquidity(AnsAsset::new(
+        config.lp_asset_entry(),
+        lp_tokens_withdraw_amount,
+    ))?;
+    let sub_msg = SubMsg::reply_on_success(withdraw_msg, withdrawal_id);
+
+    Ok(app
+        .custom_response(
+            "instant_redeem",
+            vec![
+                (
+                    "vault_token_burn_amount",
+                    amount_of_vault_tokens_to_be_burned.to_string(),
+                ),
+                (
+                    "lp_token_withdraw_amount",
+                    lp_tokens_withdraw_amount.to_string(),
+                ),
+            ],
+        )
+        .add_messages(transfer_msgs)
+        .add_message(burn_msg)
+        .add_submessage(sub_msg))
+}
+
 fn compound(deps: DepsMut, app: AutocompounderApp) -> AutocompounderResult {
     let config = CONFIG.load(deps.storage)?;
 
@@ -826,8 +1948,13 @@ fn compound(deps: DepsMut, app: AutocompounderApp) -> AutocompounderResult {
         config.pool_data.dex.clone(),
         config.lp_asset_entry(),
     );
+    let compound_id = save_reply_context(
+        deps.storage,
+        compound_context(deps.as_ref(), &app, &config)?,
+        LP_COMPOUND_REPLY_ID,
+    )?;
     let claim_submsg = SubMsg {
-        id: LP_COMPOUND_REPLY_ID,
+        id: compound_id,
         msg: claim_msg,
         gas_limit: None,
         reply_on: ReplyOn::Success,
@@ -843,49 +1970,48 @@ fn compound(deps: DepsMut, app: AutocompounderApp) -> AutocompounderResult {
 
 /// withdraw all matured claims for a user
 pub fn withdraw_claims(
-    deps: DepsMut,
+    mut deps: DepsMut,
     app: AutocompounderApp,
     env: Env,
     sender: Addr,
 ) -> AutocompounderResult {
     let config = CONFIG.load(deps.storage)?;
-    let pool_assets = config.pool_data.assets.clone();
     if config.unbonding_period.is_none() {
         return Err(AutocompounderError::UnbondingNotEnabled {});
     }
 
-    // cache assets and address for later use in reply
-    CACHED_USER_ADDR.save(deps.storage, &sender)?;
-    let owned_assets = app.bank(deps.as_ref()).balances(&pool_assets)?;
-    owned_assets
-        .into_iter()
-        .enumerate()
-        .for_each(|(_i, asset)| {
-            CACHED_ASSETS
-                .save(deps.storage, asset.info.to_string(), &asset.amount)
-                .unwrap();
-        });
-
-    let Some(claims) = CLAIMS.may_load(deps.storage, sender.clone())? else {
-        return Err(AutocompounderError::NoClaims {});
-    };
-
     // 1) get all matured claims for user
-    let mut ongoing_claims: Vec<Claim> = vec![];
-    let mut matured_claims: Vec<Claim> = vec![];
-    claims.into_iter().for_each(|claim| {
-        if claim.unbonding_timestamp.is_expired(&env.block) {
-            matured_claims.push(claim);
-        } else {
-            ongoing_claims.push(claim);
-        }
-    });
+    let (matured_claims, burn_claim_nft_msgs) = match CLAIMS_NFT.may_load(deps.storage)? {
+        Some(claims_nft) => take_matured_nft_claims(deps.branch(), &env, &claims_nft, &sender)?,
+        None => (take_matured_claims(deps.branch(), &env, &sender)?, vec![]),
+    };
 
-    if matured_claims.is_empty() {
-        return Err(AutocompounderError::NoMaturedClaims {});
-    }
+    withdraw_matured_claims(
+        deps,
+        &app,
+        &config,
+        sender,
+        matured_claims,
+        burn_claim_nft_msgs,
+    )
+}
 
-    CLAIMS.save(deps.storage, sender.clone(), &ongoing_claims)?;
+/// Withdraws the lp tokens of `matured_claims`, which are sent to `recipient` as pool assets in the reply.
+/// `burn_claim_nft_msgs` burn the NFTs of the claims.
+fn withdraw_matured_claims(
+    deps: DepsMut,
+    app: &AutocompounderApp,
+    config: &Config,
+    recipient: Addr,
+    matured_claims: Vec<Claim>,
+    burn_claim_nft_msgs: Vec<CosmosMsg>,
+) -> AutocompounderResult {
+    // save assets and address in the reply context
+    let context = ReplyContext {
+        recipient: Some(recipient.clone()),
+        ..pool_assets_context(deps.as_ref(), app, config)?
+    };
+    let withdrawal_id = save_reply_context(deps.storage, context, LP_WITHDRAWAL_REPLY_ID)?;
 
     // 2) sum up all matured claims
     let lp_tokens_to_withdraw: Uint128 =
@@ -896,7 +2022,7 @@ pub fn withdraw_claims(
     // 3.1) claim all matured claims from staking contract
     let claim_msg = claim_unbonded_tokens(
         deps.as_ref(),
-        &app,
+        app,
         config.pool_data.dex.clone(),
         config.lp_asset_entry(),
     );
@@ -907,18 +2033,158 @@ pub fn withdraw_claims(
         config.lp_asset_entry(),
         lp_tokens_to_withdraw,
     ))?;
-    let sub_msg = SubMsg::reply_on_success(withdraw_msg, LP_WITHDRAWAL_REPLY_ID);
+    let sub_msg = SubMsg::reply_on_success(withdraw_msg, withdrawal_id);
+
+    Ok(app
+        .custom_response(
+            "withdraw_claims",
+            vec![
+                ("recipient", recipient.to_string()),
+                ("lp_tokens_to_withdraw", lp_tokens_to_withdraw.to_string()),
+            ],
+        )
+        .add_messages(burn_claim_nft_msgs)
+        .add_message(claim_msg)
+        .add_submessage(sub_msg))
+}
+
+/// Withdraws the matured claims of up to `limit` claim owners in one batch.
+/// The withdrawn assets are distributed over the owners by the [`MATURED_CLAIMS_WITHDRAWAL_REPLY_ID`] reply,
+/// pro rata to the lp tokens of their matured claims.
+pub fn process_matured_claims(
+    mut deps: DepsMut,
+    env: Env,
+    app: AutocompounderApp,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> AutocompounderResult {
+    let config = CONFIG.load(deps.storage)?;
+    if config.unbonding_period.is_none() {
+        return Err(AutocompounderError::UnbondingNotEnabled {});
+    }
+
+    let limit = limit.unwrap_or(DEFAULT_BATCH_SIZE).min(MAX_BATCH_SIZE) as usize;
+
+    // tokenised claims are withdrawn by their holders, as only then the claim nfts can be burned
+    if CLAIMS_NFT.may_load(deps.storage)?.is_some() {
+        return Err(AutocompounderError::ClaimsAreTokenised {});
+    }
+
+    // 1) take the matured claims of every owner
+    let start = start_after.map(|s| Bound::ExclusiveRaw(s.into_bytes()));
+    let owners = CLAIMS
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<Addr>>>()?;
+
+    let mut payouts: Vec<(Addr, Uint128)> = vec![];
+    for owner in owners {
+        let matured_claims = match take_matured_claims(deps.branch(), &env, &owner) {
+            Ok(matured_claims) => matured_claims,
+            Err(AutocompounderError::NoMaturedClaims {}) => continue,
+            Err(err) => return Err(err),
+        };
+        let lp_tokens = matured_claims
+            .iter()
+            .map(|claim| claim.amount_of_lp_tokens_to_unbond)
+            .sum::<Uint128>();
+        payouts.push((owner, lp_tokens));
+    }
+
+    if payouts.is_empty() {
+        return Err(AutocompounderError::NoMaturedClaims {});
+    }
+
+    // 2) save the owners and the assets in the reply context
+    let owners = payouts.len();
+    let lp_tokens_to_withdraw = payouts.iter().map(|(_, lp_tokens)| *lp_tokens).sum();
+    let context = ReplyContext {
+        claim_payouts: payouts,
+        ..pool_assets_context(deps.as_ref(), &app, &config)?
+    };
+    let withdrawal_id =
+        save_reply_context(deps.storage, context, MATURED_CLAIMS_WITHDRAWAL_REPLY_ID)?;
+
+    // 3) claim the unbonded tokens and withdraw them
+    let claim_msg = claim_unbonded_tokens(
+        deps.as_ref(),
+        &app,
+        config.pool_data.dex.clone(),
+        config.lp_asset_entry(),
+    );
+    let dex = app.ans_dex(deps.as_ref(), config.pool_data.dex.clone());
+    let withdraw_msg: CosmosMsg = dex.withdraw_liquidity(AnsAsset::new(
+        config.lp_asset_entry(),
+        lp_tokens_to_withdraw,
+    ))?;
+    let sub_msg = SubMsg::reply_on_success(withdraw_msg, withdrawal_id);
+
+    Ok(app
+        .custom_response(
+            "process_matured_claims",
+            vec![
+                ("owners", owners.to_string()),
+                ("lp_tokens_to_withdraw", lp_tokens_to_withdraw.to_string()),
+            ],
+        )
+        .add_message(claim_msg)
+        .add_submessage(sub_msg))
+}
+
+/// Removes the matured claims of `owner` from the claims and returns them.
+fn take_matured_claims(
+    deps: DepsMut,
+    env: &Env,
+    owner: &Addr,
+) -> Result<Vec<Claim>, AutocompounderError> {
+    let Some(claims) = CLAIMS.may_load(deps.storage, owner.clone())? else {
+        return Err(AutocompounderError::NoClaims {});
+    };
+
+    let (matured_claims, ongoing_claims): (Vec<Claim>, Vec<Claim>) = claims
+        .into_iter()
+        .partition(|claim| claim.unbonding_timestamp.is_expired(&env.block));
+
+    if matured_claims.is_empty() {
+        return Err(AutocompounderError::NoMaturedClaims {});
+    }
+
+    CLAIMS.save(deps.storage, owner.clone(), &ongoing_claims)?;
+    Ok(matured_claims)
+}
+
+/// Removes the matured claims of the claim NFTs held by `owner` and returns them together with the messages to burn the NFTs.
+/// Burning requires the approval of the NFTs, otherwise the claim NFTs can be sent to the app with [`Cw721HookMsg::Withdraw`].
+fn take_matured_nft_claims(
+    deps: DepsMut,
+    env: &Env,
+    claims_nft: &Addr,
+    owner: &Addr,
+) -> Result<(Vec<Claim>, Vec<CosmosMsg>), AutocompounderError> {
+    let token_ids = query_all_claim_nfts_of(deps.as_ref(), claims_nft, owner)?;
+    if token_ids.is_empty() {
+        return Err(AutocompounderError::NoClaims {});
+    }
+
+    // the matured claims are taken from all the held nfts, up to a batch per withdrawal
+    let mut matured_claims: Vec<Claim> = vec![];
+    let mut burn_msgs: Vec<CosmosMsg> = vec![];
+    for token_id in token_ids {
+        if matured_claims.len() == MAX_BATCH_SIZE as usize {
+            break;
+        }
+        let claim = NFT_CLAIMS.load(deps.storage, token_id.clone())?;
+        if claim.unbonding_timestamp.is_expired(&env.block) {
+            NFT_CLAIMS.remove(deps.storage, token_id.clone());
+            burn_msgs.push(burn_claim_nft_msg(claims_nft, token_id)?);
+            matured_claims.push(claim);
+        }
+    }
 
-    Ok(app
-        .custom_response(
-            "withdraw_claims",
-            vec![
-                ("recipient", sender.to_string()),
-                ("lp_tokens_to_withdraw", lp_tokens_to_withdraw.to_string()),
-            ],
-        )
-        .add_message(claim_msg)
-        .add_submessage(sub_msg))
+    if matured_claims.is_empty() {
+        return Err(AutocompounderError::NoMaturedClaims {});
+    }
+    Ok((matured_claims, burn_msgs))
 }
 
 #[allow(clippy::type_complexity)]
@@ -930,10 +2196,7 @@ fn calculate_withdrawals(
     app: &AutocompounderApp,
     pending_claims: Vec<(Addr, Uint128)>,
     env: &Env,
-) -> Result<(Uint128, Uint128, Vec<(Addr, Vec<Claim>)>), AutocompounderError> {
-    let lp_token =
-        AnsEntryConvertor::new(AnsEntryConvertor::new(config.pool_data.clone()).lp_token())
-            .asset_entry();
+) -> Result<(Uint128, Uint128, Vec<(Addr, Claim)>), AutocompounderError> {
     let unbonding_timestamp = config
         .unbonding_period
         .ok_or(AutocompounderError::UnbondingNotEnabled {})
@@ -944,18 +2207,13 @@ fn calculate_withdrawals(
     let mut total_vault_tokens_to_burn = Uint128::from(0u128);
 
     // 1) get the total supply of Vault token
-    let vault_tokens_total_supply = vault_token_total_supply(deps, config)?;
+    let vault_tokens_total_supply = vault_token_total_supply(deps, &env.contract.address, config)?;
 
-    // 2) get total staked lp token
-    let total_lp_tokens_staked_in_vault = query_stake(
-        deps,
-        app,
-        config.pool_data.dex.clone(),
-        lp_token,
-        config.unbonding_period,
-    )?;
+    // 2) get total lp tokens of the vault
+    let total_lp_tokens_staked_in_vault = query_total_lp(deps, app, config)?;
+    let offset = decimal_offset(deps.storage)?;
 
-    let mut updated_claims: Vec<(Addr, Vec<Claim>)> = vec![];
+    let mut new_claims: Vec<(Addr, Claim)> = vec![];
     for pending_claim in pending_claims {
         let user_address = pending_claim.0;
         let user_amount_of_vault_tokens_to_be_burned = pending_claim.1;
@@ -964,6 +2222,7 @@ fn calculate_withdrawals(
             user_amount_of_vault_tokens_to_be_burned,
             total_lp_tokens_staked_in_vault,
             vault_tokens_total_supply,
+            offset,
         );
 
         // substract withdrawal fees from the amount of lp tokens to unbond
@@ -985,17 +2244,12 @@ fn calculate_withdrawals(
             amount_of_lp_tokens_to_unbond: user_lp_tokens_withdraw_amount,
         };
 
-        if let Some(mut existent_claims) = CLAIMS.may_load(deps.storage, user_address.clone())? {
-            existent_claims.push(new_claim);
-            updated_claims.push((user_address, existent_claims))
-        } else {
-            updated_claims.push((user_address, vec![new_claim]))
-        }
+        new_claims.push((user_address, new_claim));
     }
     Ok((
         total_lp_amount_to_unbond,
         total_vault_tokens_to_burn,
-        updated_claims,
+        new_claims,
     ))
 }
 
@@ -1093,14 +2347,18 @@ mod test {
     use super::{redeem_without_bonding_period, *};
 
     use crate::handlers::helpers::helpers_tests::min_cooldown_config;
+    use crate::handlers::helpers::take_reply_context;
     use crate::msg::ExecuteMsg;
-    use crate::{contract::AUTOCOMPOUNDER_APP, test_common::app_init};
+    use crate::{
+        contract::{AUTOCOMPOUNDER_APP, REPLY_ID_MASK},
+        test_common::app_init,
+    };
 
     use abstract_sdk::base::ExecuteEndpoint;
 
     use abstract_testing::prelude::{TEST_MANAGER, TEST_PROXY};
     use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-    use cosmwasm_std::{Attribute, Coin};
+    use cosmwasm_std::{Attribute, Coin, Reply, SubMsgResult};
     use cw_asset::AssetInfo;
     use cw_controllers::AdminError;
     use cw_utils::Expiration;
@@ -1139,6 +2397,46 @@ mod test {
             }
         }
 
+        #[test]
+        fn batch_deposit_shares_follow_the_batch_ratio() {
+            let deposits = vec![
+                (
+                    Addr::unchecked("alice"),
+                    vec![AnsAsset::new("eur", 300u128), AnsAsset::new("usd", 150u128)],
+                ),
+                (
+                    Addr::unchecked("bob"),
+                    vec![AnsAsset::new("usd", 50u128), AnsAsset::new("eur", 100u128)],
+                ),
+            ];
+            let total = vec![AnsAsset::new("eur", 400u128), AnsAsset::new("usd", 200u128)];
+
+            assert_that!(batch_deposit_shares(&deposits, &total))
+                .is_ok()
+                .is_equal_to(vec![
+                    (Addr::unchecked("alice"), Decimal::percent(75)),
+                    (Addr::unchecked("bob"), Decimal::percent(25)),
+                ]);
+        }
+
+        #[test]
+        fn batch_deposit_shares_reject_other_ratios() {
+            let deposits = vec![
+                (
+                    Addr::unchecked("alice"),
+                    vec![AnsAsset::new("eur", 300u128), AnsAsset::new("usd", 100u128)],
+                ),
+                (Addr::unchecked("bob"), vec![AnsAsset::new("usd", 100u128)]),
+            ];
+            let total = vec![AnsAsset::new("eur", 300u128), AnsAsset::new("usd", 200u128)];
+
+            assert_that!(batch_deposit_shares(&deposits, &total))
+                .is_err()
+                .is_equal_to(AutocompounderError::BatchDepositRatioMismatch {
+                    recipient: Addr::unchecked("alice"),
+                });
+        }
+
         #[test]
         fn consolidate_funds_matching_assets() {
             let info_funds = vec![AnsAsset::new("coin", 100u128)];
@@ -1504,6 +2802,166 @@ mod test {
         }
     }
 
+    mod liquidity_buffer {
+        use super::*;
+
+        fn update_msg() -> AutocompounderExecuteMsg {
+            AutocompounderExecuteMsg::UpdateLiquidityBuffer {
+                target: Decimal::percent(10),
+                instant_exit_fee: Decimal::percent(1),
+            }
+        }
+
+        #[test]
+        fn only_admin() -> anyhow::Result<()> {
+            let mut deps = app_init(true, true);
+
+            let resp = execute_as(deps.as_mut(), "not_mananger", update_msg(), &[]);
+            assert_that!(resp)
+                .is_err()
+                .matches(|e| matches!(e, AutocompounderError::Admin(AdminError::NotAdmin {})));
+
+            execute_as_manager(deps.as_mut(), update_msg())?;
+            assert_that!(LIQUIDITY_BUFFER_CONFIG.load(deps.as_ref().storage)?).is_equal_to(
+                LiquidityBufferConfig {
+                    target: Decimal::percent(10),
+                    instant_exit_fee: Decimal::percent(1),
+                },
+            );
+            Ok(())
+        }
+
+        #[test]
+        fn requires_unbonding_period() -> anyhow::Result<()> {
+            let mut deps = app_init(false, true);
+
+            let resp = execute_as_manager(deps.as_mut(), update_msg());
+            assert_that!(resp)
+                .is_err()
+                .matches(|e| matches!(e, AutocompounderError::UnbondingNotEnabled {}));
+            Ok(())
+        }
+    }
+
+    mod pause {
+        use super::*;
+
+        fn pause_deposits(paused: bool) -> AutocompounderExecuteMsg {
+            AutocompounderExecuteMsg::UpdatePause {
+                deposits: Some(paused),
+                redeems: None,
+                withdrawals: None,
+                compounding: None,
+            }
+        }
+
+        #[test]
+        fn guardian_can_pause_but_not_unpause() -> anyhow::Result<()> {
+            let mut deps = app_init(false, true);
+            execute_as_manager(
+                deps.as_mut(),
+                AutocompounderExecuteMsg::UpdateGuardian {
+                    guardian: Some("guardian".to_string()),
+                },
+            )?;
+
+            execute_as(deps.as_mut(), "guardian", pause_deposits(true), &[])?;
+            assert_that!(PAUSE_STATE.load(deps.as_ref().storage)?.deposits).is_equal_to(true);
+
+            let resp = execute_as(deps.as_mut(), "guardian", pause_deposits(false), &[]);
+            assert_that!(resp)
+                .is_err()
+                .matches(|e| matches!(e, AutocompounderError::GuardianCannotUnpause {}));
+
+            let resp = execute_as(deps.as_mut(), "user", pause_deposits(true), &[]);
+            assert_that!(resp)
+                .is_err()
+                .matches(|e| matches!(e, AutocompounderError::SenderIsNotAdminOrGuardian {}));
+
+            execute_as_manager(deps.as_mut(), pause_deposits(false))?;
+            assert_that!(PAUSE_STATE.load(deps.as_ref().storage)?.deposits).is_equal_to(false);
+            Ok(())
+        }
+
+        #[test]
+        fn paused_deposits_are_rejected() -> anyhow::Result<()> {
+            let mut deps = app_init(false, true);
+            execute_as_manager(deps.as_mut(), pause_deposits(true))?;
+
+            let msg = AutocompounderExecuteMsg::Deposit {
+                funds: vec![AnsAsset::new("eur", Uint128::one())],
+                recipient: None,
+                max_spread: None,
+            };
+            let resp = execute_as(deps.as_mut(), "user", msg, &[Coin::new(1u128, "eur")]);
+            assert_that!(resp)
+                .is_err()
+                .is_equal_to(AutocompounderError::OperationPaused {
+                    operation: "Deposits".to_string(),
+                });
+            Ok(())
+        }
+    }
+
+    mod emergency_shutdown {
+        use super::*;
+
+        #[test]
+        fn blocks_deposits_and_compounding() -> anyhow::Result<()> {
+            let mut deps = app_init(true, true);
+            EMERGENCY_SHUTDOWN.save(
+                deps.as_mut().storage,
+                &EmergencyShutdownState {
+                    lp_tokens: Uint128::new(100),
+                    assets: vec![],
+                    unbonded_at: Expiration::AtHeight(0),
+                },
+            )?;
+
+            let msg = AutocompounderExecuteMsg::Deposit {
+                funds: vec![AnsAsset::new("eur", Uint128::one())],
+                recipient: None,
+                max_spread: None,
+            };
+            let resp = execute_as(deps.as_mut(), "user", msg, &[Coin::new(1u128, "eur")]);
+            assert_that!(resp)
+                .is_err()
+                .matches(|e| matches!(e, AutocompounderError::VaultIsShutdown {}));
+
+            let resp = execute_as_manager(deps.as_mut(), AutocompounderExecuteMsg::Compound {});
+            assert_that!(resp)
+                .is_err()
+                .matches(|e| matches!(e, AutocompounderError::VaultIsShutdown {}));
+            Ok(())
+        }
+
+        #[test]
+        fn cannot_emergency_redeem_before_shutdown() -> anyhow::Result<()> {
+            let mut deps = app_init(false, true);
+            let msg = AutocompounderExecuteMsg::EmergencyRedeem {
+                amount: Uint128::new(100),
+            };
+            let resp = execute_as(deps.as_mut(), "user", msg, &[]);
+            assert_that!(resp)
+                .is_err()
+                .matches(|e| matches!(e, AutocompounderError::VaultIsNotShutdown {}));
+            Ok(())
+        }
+
+        #[test]
+        fn cannot_withdraw_liquidity_with_unbonding_period() -> anyhow::Result<()> {
+            let mut deps = app_init(true, true);
+            let msg = AutocompounderExecuteMsg::EmergencyShutdown {
+                withdraw_liquidity: true,
+            };
+            let resp = execute_as_manager(deps.as_mut(), msg);
+            assert_that!(resp)
+                .is_err()
+                .matches(|e| matches!(e, AutocompounderError::CannotWithdrawUnbondingLiquidity {}));
+            Ok(())
+        }
+    }
+
     #[test]
     fn cannot_batch_unbond_if_unbonding_not_enabled() -> anyhow::Result<()> {
         let mut deps = app_init(false, true);
@@ -1549,7 +3007,7 @@ mod test {
     #[test]
     fn cannot_withdraw_liquidity_if_no_claims() -> anyhow::Result<()> {
         let mut deps = app_init(true, true);
-        let msg = AutocompounderExecuteMsg::Withdraw {};
+        let msg = AutocompounderExecuteMsg::Withdraw { owner: None };
         let resp = execute_as_manager(deps.as_mut(), msg);
         assert_that!(resp)
             .is_err()
@@ -1557,6 +3015,62 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn mint_claim_nfts_stores_claims_by_token_id() -> anyhow::Result<()> {
+        let mut deps = mock_dependencies();
+        let claims_nft = Addr::unchecked("claims_nft");
+        let claim = Claim {
+            unbonding_timestamp: Expiration::AtHeight(100),
+            amount_of_vault_tokens_to_burn: Uint128::new(10),
+            amount_of_lp_tokens_to_unbond: Uint128::new(5),
+        };
+        let new_claims = vec![
+            (Addr::unchecked("alice"), claim.clone()),
+            (Addr::unchecked("bob"), claim.clone()),
+        ];
+
+        let msgs = mint_claim_nfts(deps.as_mut().storage, &claims_nft, new_claims.clone())?;
+        assert_that!(msgs).has_length(2);
+        let msgs = mint_claim_nfts(deps.as_mut().storage, &claims_nft, new_claims)?;
+        assert_that!(msgs).has_length(2);
+
+        assert_that!(CLAIMS_NFT_COUNT.load(deps.as_ref().storage)?).is_equal_to(4);
+        for token_id in ["1", "2", "3", "4"] {
+            assert_that!(NFT_CLAIMS.load(deps.as_ref().storage, token_id.to_string())?)
+                .is_equal_to(claim.clone());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn take_matured_claims_keeps_ongoing_claims() -> anyhow::Result<()> {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let owner = Addr::unchecked("owner");
+        let claim = |height: u64| Claim {
+            unbonding_timestamp: Expiration::AtHeight(height),
+            amount_of_vault_tokens_to_burn: Uint128::new(10),
+            amount_of_lp_tokens_to_unbond: Uint128::new(5),
+        };
+        let matured = claim(env.block.height - 1);
+        let ongoing = claim(env.block.height + 1);
+        CLAIMS.save(
+            deps.as_mut().storage,
+            owner.clone(),
+            &vec![matured.clone(), ongoing.clone()],
+        )?;
+
+        let claims = take_matured_claims(deps.as_mut(), &env, &owner)?;
+        assert_that!(claims).is_equal_to(vec![matured]);
+        assert_that!(CLAIMS.load(deps.as_ref().storage, owner.clone())?).is_equal_to(vec![ongoing]);
+
+        let res = take_matured_claims(deps.as_mut(), &env, &owner);
+        assert_that!(res)
+            .is_err()
+            .matches(|e| matches!(e, AutocompounderError::NoMaturedClaims {}));
+        Ok(())
+    }
+
     #[test]
     fn test_check_unbonding_cooldown_with_no_latest_unbonding() {
         let mut deps = mock_dependencies();
@@ -1614,21 +3128,241 @@ mod test {
             _ => panic!("Unexpected error: {:?}", result),
         }
 
-        // 60 seconds left
-        let latest_unbonding = Expiration::AtTime(env.block.time);
-        LATEST_UNBONDING
-            .save(deps.as_mut().storage, &latest_unbonding)
-            .unwrap();
-        let result = check_unbonding_cooldown(&deps.as_mut(), &config, &env);
-        match result {
-            Err(AutocompounderError::UnbondingCooldownNotExpired {
-                min_cooldown,
-                latest_unbonding: latest_unbonding_error,
-            }) => {
-                assert_eq!(min_cooldown, Duration::Time(60));
-                assert_eq!(latest_unbonding, latest_unbonding_error);
-            }
-            _ => panic!("Unexpected error: {:?}", result),
+        // 60 seconds left
+        let latest_unbonding = Expiration::AtTime(env.block.time);
+        LATEST_UNBONDING
+            .save(deps.as_mut().storage, &latest_unbonding)
+            .unwrap();
+        let result = check_unbonding_cooldown(&deps.as_mut(), &config, &env);
+        match result {
+            Err(AutocompounderError::UnbondingCooldownNotExpired {
+                min_cooldown,
+                latest_unbonding: latest_unbonding_error,
+            }) => {
+                assert_eq!(min_cooldown, Duration::Time(60));
+                assert_eq!(latest_unbonding, latest_unbonding_error);
+            }
+            _ => panic!("Unexpected error: {:?}", result),
+        }
+    }
+
+    mod cw20_hooks {
+        use super::*;
+        use crate::test_common::TEST_VAULT_TOKEN;
+        use cosmwasm_std::to_json_binary;
+
+        fn send(sender: &str, amount: u128, msg: &Cw20HookMsg) -> anyhow::Result<Cw20ReceiveMsg> {
+            Ok(Cw20ReceiveMsg {
+                sender: sender.to_string(),
+                amount: Uint128::new(amount),
+                msg: to_json_binary(msg)?,
+            })
+        }
+
+        #[test]
+        fn redeem_hook_registers_claim_without_transfer() -> anyhow::Result<()> {
+            let mut deps = app_init(true, true);
+            let config = min_cooldown_config(None, false);
+            CONFIG.save(deps.as_mut().storage, &config)?;
+
+            let msg = send("sender", 100, &Cw20HookMsg::Redeem { recipient: None })?;
+            let res = receive(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(TEST_VAULT_TOKEN, &[]),
+                AUTOCOMPOUNDER_APP,
+                msg,
+            )?;
+
+            assert_that!(res.messages).has_length(0);
+            assert_that!(PENDING_CLAIMS.load(deps.as_ref().storage, Addr::unchecked("sender"))?)
+                .is_equal_to(Uint128::new(100));
+            Ok(())
+        }
+
+        #[test]
+        fn redeem_hook_rejects_other_tokens() -> anyhow::Result<()> {
+            let mut deps = app_init(true, true);
+            CONFIG.save(deps.as_mut().storage, &min_cooldown_config(None, false))?;
+
+            let msg = send("sender", 100, &Cw20HookMsg::Redeem { recipient: None })?;
+            let res = receive(
+                deps.as_mut(),
+                mock_env(),
+                mock_info("other_token", &[]),
+                AUTOCOMPOUNDER_APP,
+                msg,
+            );
+            assert_that!(res)
+                .is_err()
+                .is_equal_to(AutocompounderError::SenderIsNotVaultToken {});
+            Ok(())
+        }
+
+        #[test]
+        fn deposit_hook_rejects_other_tokens() -> anyhow::Result<()> {
+            let mut deps = app_init(false, true);
+            let mut config = min_cooldown_config(None, false);
+            config.pool_assets = vec![AssetInfo::cw20(Addr::unchecked("eur"))];
+            CONFIG.save(deps.as_mut().storage, &config)?;
+
+            let msg = Cw20HookMsg::Deposit {
+                recipient: None,
+                max_spread: None,
+            };
+            // the lp token and the vault token are no pool assets
+            for token in ["other_token", "eur_usd_lp", TEST_VAULT_TOKEN] {
+                let res = receive(
+                    deps.as_mut(),
+                    mock_env(),
+                    mock_info(token, &[]),
+                    AUTOCOMPOUNDER_APP,
+                    send("sender", 100, &msg)?,
+                );
+                assert_that!(res)
+                    .is_err()
+                    .is_equal_to(AutocompounderError::SenderIsNotPoolAsset {});
+            }
+            Ok(())
+        }
+
+        #[test]
+        fn deposit_lp_hook_rejects_other_tokens() -> anyhow::Result<()> {
+            let mut deps = app_init(false, true);
+            let mut config = min_cooldown_config(None, false);
+            config.pool_assets = vec![AssetInfo::cw20(Addr::unchecked("eur"))];
+            CONFIG.save(deps.as_mut().storage, &config)?;
+
+            let msg = Cw20HookMsg::DepositLp { recipient: None };
+            // pool assets have to be deposited with the deposit hook
+            for token in ["other_token", "eur", TEST_VAULT_TOKEN] {
+                let res = receive(
+                    deps.as_mut(),
+                    mock_env(),
+                    mock_info(token, &[]),
+                    AUTOCOMPOUNDER_APP,
+                    send("sender", 100, &msg)?,
+                );
+                assert_that!(res)
+                    .is_err()
+                    .is_equal_to(AutocompounderError::SenderIsNotLpToken {});
+            }
+            Ok(())
+        }
+    }
+
+    mod operators {
+        use super::*;
+
+        fn approve(
+            deps: DepsMut,
+            owner: &str,
+            expires: Option<Expiration>,
+        ) -> AutocompounderResult {
+            let msg = AutocompounderExecuteMsg::ApproveOperator {
+                operator: "operator".to_string(),
+                expires,
+            };
+            execute_as(deps, owner, msg, &[])
+        }
+
+        #[test]
+        fn operator_acts_for_owner_until_expired_or_revoked() -> anyhow::Result<()> {
+            let mut deps = app_init(false, true);
+            let env = mock_env();
+            let operator = Addr::unchecked("operator");
+            let owner = Some(Addr::unchecked("owner"));
+
+            assert_that!(owner_if_operator(
+                deps.as_ref(),
+                &env,
+                &operator,
+                owner.clone()
+            ))
+            .is_err()
+            .is_equal_to(AutocompounderError::SenderIsNotOperator {
+                owner: Addr::unchecked("owner"),
+            });
+            assert_that!(owner_if_operator(deps.as_ref(), &env, &operator, None)?)
+                .is_equal_to(operator.clone());
+
+            let expired = Expiration::AtHeight(env.block.height);
+            assert_that!(approve(deps.as_mut(), "owner", Some(expired)))
+                .is_err()
+                .is_equal_to(AutocompounderError::ExpiredApproval {});
+
+            let expires = Expiration::AtHeight(env.block.height + 10);
+            approve(deps.as_mut(), "owner", Some(expires))?;
+            assert_that!(owner_if_operator(
+                deps.as_ref(),
+                &env,
+                &operator,
+                owner.clone()
+            )?)
+            .is_equal_to(Addr::unchecked("owner"));
+
+            let mut later = env.clone();
+            later.block.height += 10;
+            assert_that!(owner_if_operator(
+                deps.as_ref(),
+                &later,
+                &operator,
+                owner.clone()
+            ))
+            .is_err();
+
+            let msg = AutocompounderExecuteMsg::RevokeOperator {
+                operator: "operator".to_string(),
+            };
+            execute_as(deps.as_mut(), "owner", msg, &[])?;
+            assert_that!(owner_if_operator(deps.as_ref(), &env, &operator, owner)).is_err();
+            Ok(())
+        }
+
+        #[test]
+        fn operator_cancels_pending_claim_of_owner() -> anyhow::Result<()> {
+            let mut deps = app_init(true, true);
+            let config = min_cooldown_config(None, false);
+            CONFIG.save(deps.as_mut().storage, &config)?;
+            PENDING_CLAIMS.save(
+                deps.as_mut().storage,
+                Addr::unchecked("owner"),
+                &100u128.into(),
+            )?;
+            approve(deps.as_mut(), "owner", None)?;
+
+            let msg = AutocompounderExecuteMsg::CancelPendingClaim {
+                owner: Some(Addr::unchecked("owner")),
+            };
+            let res = execute_as(deps.as_mut(), "operator", msg.clone(), &[])?;
+
+            assert_that!(res.messages[0].msg)
+                .is_equal_to(Asset::new(config.vault_token, 100u128).transfer_msg("owner")?);
+            assert_that!(PENDING_CLAIMS.has(deps.as_ref().storage, Addr::unchecked("owner")))
+                .is_equal_to(false);
+
+            assert_that!(execute_as(deps.as_mut(), "operator", msg, &[]))
+                .is_err()
+                .is_equal_to(AutocompounderError::NoClaims {});
+            Ok(())
+        }
+
+        #[test]
+        fn operator_redeems_only_to_owner_or_itself() -> anyhow::Result<()> {
+            let mut deps = app_init(false, true);
+            approve(deps.as_mut(), "owner", None)?;
+
+            let msg = AutocompounderExecuteMsg::Redeem {
+                amount: Uint128::new(100),
+                owner: Some(Addr::unchecked("owner")),
+                recipient: Some(Addr::unchecked("stranger")),
+            };
+            assert_that!(execute_as(deps.as_mut(), "operator", msg, &[]))
+                .is_err()
+                .is_equal_to(AutocompounderError::RecipientNotPermitted {
+                    recipient: Addr::unchecked("stranger"),
+                });
+            Ok(())
         }
     }
 
@@ -1718,13 +3452,15 @@ mod test {
             let sender = info.sender.clone();
 
             let amount = Uint128::new(100);
+            let vault_token = AssetBase::new(config.vault_token, amount);
+            let transfer_msgs =
+                transfer_token_to_autocompounder(vault_token, sender.clone(), env, &info.funds)?;
 
             let res = receive_and_register_claim(
                 deps.as_mut(),
-                env,
                 AUTOCOMPOUNDER_APP,
                 sender.clone(),
-                info.clone(),
+                transfer_msgs,
                 amount,
             )?;
             assert_that!(res.messages).has_length(0);
@@ -1743,21 +3479,18 @@ mod test {
 
             let amount = Uint128::new(100);
             let lp_asset_base = AssetBase::new(config.vault_token, amount);
+            let transfer_msgs =
+                transfer_token_to_autocompounder(lp_asset_base, sender.clone(), env, &info.funds)?;
 
             let res = receive_and_register_claim(
                 deps.as_mut(),
-                env,
                 AUTOCOMPOUNDER_APP,
                 sender.clone(),
-                info.clone(),
+                transfer_msgs.clone(),
                 amount,
             )?;
             assert_that!(res.messages).has_length(1);
-            assert_that!(res.messages[0].msg).is_equal_to(
-                transfer_token_to_autocompounder(lp_asset_base, sender, env, &info.funds)?
-                    .first()
-                    .unwrap(),
-            );
+            assert_that!(res.messages[0].msg).is_equal_to(transfer_msgs[0].clone());
 
             Ok(())
         }
@@ -1773,7 +3506,14 @@ mod test {
             let sender = info.sender.clone();
             let amount = Uint128::new(100);
 
-            let err = CACHED_ASSETS.load(&deps.storage, "native:eur".to_string());
+            let withdrawal_reply = |id| Reply {
+                id,
+                result: SubMsgResult::Err("withdrawal failed".to_string()),
+            };
+            let err = take_reply_context(
+                deps.as_mut().storage,
+                &withdrawal_reply(LP_WITHDRAWAL_REPLY_ID),
+            );
             assert_that!(err).is_err();
 
             // 1. set up the balances(1000eur, 1000usd) of the proxy contract in the bank
@@ -1791,28 +3531,29 @@ mod test {
                 ],
             );
 
+            let vault_token = AssetBase::new(config.vault_token.clone(), amount);
+            let transfer_msgs =
+                transfer_token_to_autocompounder(vault_token, sender.clone(), env, &info.funds)?;
             let response = redeem_without_bonding_period(
                 deps.as_mut(),
                 env,
                 &sender,
-                info,
+                transfer_msgs,
                 config.clone(),
                 &AUTOCOMPOUNDER_APP,
                 amount,
             )?;
 
-            // The sender addr should be cached
-            assert_that!(CACHED_USER_ADDR.load(&deps.storage)?).is_equal_to(sender);
-
-            // The contract should not own assets at this point and should have stored them correctly
-            let cached_assets: Vec<(String, Uint128)> = CACHED_ASSETS
-                .range(&deps.storage, None, None, Order::Ascending)
-                .map(|x| x.unwrap())
-                .map(|(k, v)| (k, v))
-                .collect();
-            assert_that!(cached_assets).has_length(2);
-            assert_that!(cached_assets[0]).is_equal_to(("native:eur".to_string(), 1000u128.into()));
-            assert_that!(cached_assets[1]).is_equal_to(("native:usd".to_string(), 1000u128.into()));
+            // The sender addr and the owned assets should be saved in the reply context
+            let withdrawal_id = response.messages.last().unwrap().id;
+            assert_that!(withdrawal_id & REPLY_ID_MASK).is_equal_to(LP_WITHDRAWAL_REPLY_ID);
+            let context =
+                take_reply_context(deps.as_mut().storage, &withdrawal_reply(withdrawal_id))?;
+            assert_that!(context.recipient).is_equal_to(Some(sender));
+            assert_that!(context.cached_assets).is_equal_to(vec![
+                Asset::native("eur", 1000u128),
+                Asset::native("usd", 1000u128),
+            ]);
 
             // The contract should have sent the correct messages
             assert_that!(response.messages).has_length(4);
@@ -1836,6 +3577,8 @@ mod test {
 
     mod deposit_recipient {
         use super::*;
+        use crate::handlers::reply::deposit_compound_reply;
+        use speculoos::prelude::*;
 
         #[test]
         fn deposit_recipient() -> anyhow::Result<()> {
@@ -1862,6 +3605,7 @@ mod test {
                 mock_env(),
                 AUTOCOMPOUNDER_APP,
                 lp_asset.clone(),
+                None,
                 None
             ))
             .is_ok();
@@ -1872,6 +3616,7 @@ mod test {
                 mock_env(),
                 AUTOCOMPOUNDER_APP,
                 not_lp_asset,
+                None,
                 None
             ))
             .is_err()
@@ -1879,6 +3624,158 @@ mod test {
             Ok(())
         }
 
+        #[test]
+        fn allowlist_requires_sender_and_recipient() -> anyhow::Result<()> {
+            let mut app = app_init(false, true);
+            CONFIG.save(&mut app.storage, &min_cooldown_config(None, false))?;
+            execute_as_manager(
+                app.as_mut(),
+                AutocompounderExecuteMsg::UpdateAccessMode {
+                    access_mode: AccessMode::Allowlist,
+                },
+            )?;
+            execute_as_manager(
+                app.as_mut(),
+                AutocompounderExecuteMsg::UpdateDepositors {
+                    to_add: vec!["sender".to_string()],
+                    to_remove: vec![],
+                },
+            )?;
+
+            let info = mock_info("sender", &[]);
+            let lp_asset = AnsAsset::new("eur_usd_lp", Uint128::new(100));
+            assert_that!(deposit_lp(
+                app.as_mut(),
+                info.clone(),
+                mock_env(),
+                AUTOCOMPOUNDER_APP,
+                lp_asset.clone(),
+                None,
+                None
+            ))
+            .is_ok();
+
+            assert_that!(deposit_lp(
+                app.as_mut(),
+                info,
+                mock_env(),
+                AUTOCOMPOUNDER_APP,
+                lp_asset.clone(),
+                Some(Addr::unchecked("recipient")),
+                None
+            ))
+            .is_err()
+            .is_equal_to(AutocompounderError::DepositorNotAllowed {
+                address: Addr::unchecked("recipient"),
+            });
+
+            assert_that!(deposit_lp(
+                app.as_mut(),
+                mock_info("other", &[]),
+                mock_env(),
+                AUTOCOMPOUNDER_APP,
+                lp_asset,
+                None,
+                None
+            ))
+            .is_err()
+            .is_equal_to(AutocompounderError::DepositorNotAllowed {
+                address: Addr::unchecked("other"),
+            });
+            Ok(())
+        }
+
+        #[test]
+        fn lp_deposit_is_minted_after_the_compound() -> anyhow::Result<()> {
+            let mut app = app_init(false, true);
+            CONFIG.save(&mut app.storage, &min_cooldown_config(None, false))?;
+            COMPOUND_ON_DEPOSIT.save(&mut app.storage, &true)?;
+
+            let lp_asset = AnsAsset::new("eur_usd_lp", Uint128::new(100));
+            let res = deposit_lp(
+                app.as_mut(),
+                mock_info("sender", &[]),
+                mock_env(),
+                AUTOCOMPOUNDER_APP,
+                lp_asset.clone(),
+                None,
+                None,
+            )?;
+            // only the compound is dispatched, its failure doesn't revert the deposit
+            assert_that!(res.messages).has_length(1);
+            let compound = &res.messages[0];
+            assert_that!(compound.reply_on).is_equal_to(ReplyOn::Always);
+            assert_that!(compound.id & REPLY_ID_MASK).is_equal_to(DEPOSIT_COMPOUND_REPLY_ID);
+
+            let reply = Reply {
+                id: compound.id,
+                result: SubMsgResult::Err("no rewards".to_string()),
+            };
+            let res = deposit_compound_reply(app.as_mut(), mock_env(), AUTOCOMPOUNDER_APP, reply)?;
+            assert_that!(res.attributes).contains(Attribute::new("compound_error", "no rewards"));
+            assert_that!(res.messages).is_not_empty();
+            Ok(())
+        }
+
+        #[test]
+        fn lp_deposit_fills_pending_claims() -> anyhow::Result<()> {
+            let mut app = app_init(true, true);
+            CONFIG.save(&mut app.storage, &min_cooldown_config(None, false))?;
+            let owner = Addr::unchecked("owner");
+            PENDING_CLAIMS.save(&mut app.storage, owner.clone(), &Uint128::new(100))?;
+
+            let lp_asset = AnsAsset::new("eur_usd_lp", Uint128::new(100));
+            let res = deposit_lp(
+                app.as_mut(),
+                mock_info("sender", &[]),
+                mock_env(),
+                AUTOCOMPOUNDER_APP,
+                lp_asset,
+                None,
+                None,
+            )?;
+
+            // the claim is paid out with the deposited lp tokens instead of being unbonded
+            assert_that!(PENDING_CLAIMS.may_load(&app.storage, owner.clone())?).is_none();
+            let withdrawal = res.messages.last().unwrap();
+            assert_that!(withdrawal.id & REPLY_ID_MASK)
+                .is_equal_to(MATURED_CLAIMS_WITHDRAWAL_REPLY_ID);
+            let reply = Reply {
+                id: withdrawal.id,
+                result: SubMsgResult::Err("withdrawal failed".to_string()),
+            };
+            let payouts = take_reply_context(app.as_mut().storage, &reply)?.claim_payouts;
+            assert_that!(payouts).has_length(1);
+            assert_that!(payouts[0].0).is_equal_to(owner);
+            Ok(())
+        }
+
+        #[test]
+        fn batch_deposit_validates_recipients() -> anyhow::Result<()> {
+            let mut app = app_init(false, true);
+            CONFIG.save(&mut app.storage, &min_cooldown_config(None, false))?;
+
+            let msg = AutocompounderExecuteMsg::BatchDeposit {
+                deposits: vec![],
+                max_spread: None,
+            };
+            assert_that!(execute_as(app.as_mut(), "sender", msg, &[]))
+                .is_err()
+                .is_equal_to(AutocompounderError::EmptyBatchDeposit {});
+
+            let msg = AutocompounderExecuteMsg::BatchDeposit {
+                deposits: vec![(
+                    Addr::unchecked(TEST_PROXY),
+                    vec![AnsAsset::new("eur", 100u128)],
+                )],
+                max_spread: None,
+            };
+            assert_that!(execute_as(app.as_mut(), "sender", msg, &[]))
+                .is_err()
+                .is_equal_to(AutocompounderError::CannotSetRecipientToAccount {});
+            Ok(())
+        }
+
         #[test]
         fn unwrap_recipient() -> anyhow::Result<()> {
             let not_allowed = vec![
@@ -1908,4 +3805,329 @@ mod test {
             Ok(())
         }
     }
+
+    mod vault_token_admin {
+        use super::*;
+
+        #[test]
+        fn only_admin_can_change_vault_token_admin() {
+            let mut deps = app_init(false, false);
+            let msg = AutocompounderExecuteMsg::ChangeVaultTokenAdmin {
+                new_admin: "new_module".to_string(),
+            };
+            assert_that!(execute_as(deps.as_mut(), "not_admin", msg, &[]))
+                .is_err()
+                .matches(|e| matches!(e, AutocompounderError::Admin(AdminError::NotAdmin {})));
+        }
+
+        #[test]
+        fn change_denom_admin_of_native_vault_token() -> anyhow::Result<()> {
+            let mut deps = app_init(false, false);
+            let msg = AutocompounderExecuteMsg::ChangeVaultTokenAdmin {
+                new_admin: "new_module".to_string(),
+            };
+            let res = execute_as_manager(deps.as_mut(), msg)?;
+
+            assert_that!(res.messages).has_length(1);
+            let CosmosMsg::Stargate { type_url, .. } = &res.messages[0].msg else {
+                panic!("expected stargate msg");
+            };
+            assert_that!(type_url.as_str())
+                .is_equal_to("/juno.tokenfactory.v1beta1.MsgChangeAdmin");
+            Ok(())
+        }
+    }
+
+    mod sweep {
+        use super::*;
+        use cw_asset::AssetInfo;
+
+        fn sweep_msg(assets: Vec<Asset>) -> AutocompounderExecuteMsg {
+            AutocompounderExecuteMsg::Sweep {
+                assets,
+                recipient: "treasury".to_string(),
+            }
+        }
+
+        #[test]
+        fn only_admin_can_sweep() {
+            let mut deps = app_init(false, false);
+            let msg = sweep_msg(vec![Asset::native("airdrop", 100u128)]);
+            assert_that!(execute_as(deps.as_mut(), "not_admin", msg, &[]))
+                .is_err()
+                .matches(|e| matches!(e, AutocompounderError::Admin(AdminError::NotAdmin {})));
+        }
+
+        #[test]
+        fn core_assets_cannot_be_swept() -> anyhow::Result<()> {
+            let mut deps = app_init(false, false);
+            let config = CONFIG.load(deps.as_ref().storage)?;
+            let core_assets: Vec<AssetInfo> =
+                [config.liquidity_token.clone(), config.vault_token.clone()]
+                    .into_iter()
+                    .chain(config.pool_assets.clone())
+                    .collect();
+
+            for info in core_assets {
+                let msg = sweep_msg(vec![
+                    Asset::native("airdrop", 100u128),
+                    Asset::new(info.clone(), 100u128),
+                ]);
+                assert_that!(execute_as_manager(deps.as_mut(), msg))
+                    .is_err()
+                    .is_equal_to(AutocompounderError::CannotSweepCoreAsset {
+                        asset: info.to_string(),
+                    });
+            }
+            Ok(())
+        }
+
+        #[test]
+        fn sweep_stray_tokens() -> anyhow::Result<()> {
+            let mut deps = app_init(false, false);
+            let msg = sweep_msg(vec![Asset::native("airdrop", 100u128)]);
+            let res = execute_as_manager(deps.as_mut(), msg)?;
+
+            assert_that!(res.messages).has_length(1);
+            Ok(())
+        }
+    }
+
+    mod reward_filter {
+        use super::*;
+        use crate::msg::{DeniedRewards, RewardFilter};
+        use speculoos::prelude::*;
+
+        #[test]
+        fn only_admin_can_update_reward_filter() {
+            let mut deps = app_init(false, false);
+            let msg = AutocompounderExecuteMsg::UpdateRewardFilter {
+                reward_filter: RewardFilter::default(),
+            };
+            assert_that!(execute_as(deps.as_mut(), "not_admin", msg, &[]))
+                .is_err()
+                .matches(|e| matches!(e, AutocompounderError::Admin(AdminError::NotAdmin {})));
+        }
+
+        #[test]
+        fn update_reward_filter() -> anyhow::Result<()> {
+            let mut deps = app_init(false, false);
+            let reward_filter = RewardFilter {
+                allowlist: vec![],
+                denylist: vec![AssetInfo::native("spam")],
+                denied_rewards: DeniedRewards::SendToFeeCollector,
+            };
+            let msg = AutocompounderExecuteMsg::UpdateRewardFilter {
+                reward_filter: reward_filter.clone(),
+            };
+            execute_as_manager(deps.as_mut(), msg)?;
+
+            assert_that!(REWARD_FILTER.load(deps.as_ref().storage)?).is_equal_to(reward_filter);
+            Ok(())
+        }
+
+        #[test]
+        fn core_assets_cannot_be_filtered() -> anyhow::Result<()> {
+            let mut deps = app_init(false, false);
+            let config = CONFIG.load(deps.as_ref().storage)?;
+
+            let pool_asset = config.pool_assets[0].clone();
+            let lp_token = config.liquidity_token.clone();
+            for (reward_filter, asset) in [
+                (
+                    RewardFilter {
+                        allowlist: vec![pool_asset.clone()],
+                        ..Default::default()
+                    },
+                    pool_asset,
+                ),
+                (
+                    RewardFilter {
+                        denylist: vec![lp_token.clone()],
+                        ..Default::default()
+                    },
+                    lp_token,
+                ),
+            ] {
+                let msg = AutocompounderExecuteMsg::UpdateRewardFilter { reward_filter };
+                assert_that!(execute_as_manager(deps.as_mut(), msg))
+                    .is_err()
+                    .is_equal_to(AutocompounderError::CannotFilterCoreAsset {
+                        asset: asset.to_string(),
+                    });
+            }
+            Ok(())
+        }
+
+        #[test]
+        fn denylist_takes_precedence_over_allowlist() {
+            let reward_filter = RewardFilter {
+                allowlist: vec![AssetInfo::native("juno"), AssetInfo::native("spam")],
+                denylist: vec![AssetInfo::native("spam")],
+                denied_rewards: DeniedRewards::Skip,
+            };
+            assert_that!(reward_filter.is_allowed(&AssetInfo::native("juno"))).is_true();
+            assert_that!(reward_filter.is_allowed(&AssetInfo::native("spam"))).is_false();
+            assert_that!(reward_filter.is_allowed(&AssetInfo::native("other"))).is_false();
+            assert_that!(RewardFilter::default().is_allowed(&AssetInfo::native("other"))).is_true();
+        }
+    }
+
+    mod receive_claim_nft {
+        use super::*;
+        use crate::claims_nft::Cw721ExecuteMsg;
+        use crate::contract::REPLY_ID_MASK;
+        use abstract_testing::MockDeps;
+        use cosmwasm_std::{to_json_binary, Timestamp, WasmMsg};
+        use speculoos::prelude::*;
+
+        fn send_nft(deps: DepsMut, sender: &str, token_id: &str) -> AutocompounderResult {
+            let msg = Cw721ReceiveMsg {
+                sender: "holder".to_string(),
+                token_id: token_id.to_string(),
+                msg: to_json_binary(&Cw721HookMsg::Withdraw {})?,
+            };
+            receive_nft(
+                deps,
+                mock_env(),
+                mock_info(sender, &[]),
+                AUTOCOMPOUNDER_APP,
+                msg,
+            )
+        }
+
+        fn setup() -> anyhow::Result<MockDeps> {
+            let mut deps = app_init(true, true);
+            CLAIMS_NFT.save(deps.as_mut().storage, &Addr::unchecked("claims_nft"))?;
+            let claim = |seconds: u64| Claim {
+                unbonding_timestamp: Expiration::AtTime(Timestamp::from_seconds(seconds)),
+                amount_of_vault_tokens_to_burn: Uint128::new(100),
+                amount_of_lp_tokens_to_unbond: Uint128::new(10),
+            };
+            NFT_CLAIMS.save(deps.as_mut().storage, "1".to_string(), &claim(100))?;
+            let ongoing = claim(mock_env().block.time.seconds() + 100);
+            NFT_CLAIMS.save(deps.as_mut().storage, "2".to_string(), &ongoing)?;
+            Ok(deps)
+        }
+
+        #[test]
+        fn pays_the_holder_and_burns_the_nft() -> anyhow::Result<()> {
+            let mut deps = setup()?;
+
+            let res = send_nft(deps.as_mut(), "claims_nft", "1")?;
+
+            let CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr, msg, ..
+            }) = &res.messages[0].msg
+            else {
+                panic!("expected wasm execute msg");
+            };
+            assert_that!(contract_addr).is_equal_to(&"claims_nft".to_string());
+            assert_that!(from_json::<Cw721ExecuteMsg>(msg)?).is_equal_to(Cw721ExecuteMsg::Burn {
+                token_id: "1".to_string(),
+            });
+            assert_that!(NFT_CLAIMS.has(deps.as_ref().storage, "1".to_string())).is_false();
+
+            // the withdrawn assets are sent to the holder
+            let withdrawal_id = res.messages.last().unwrap().id;
+            assert_that!(withdrawal_id & REPLY_ID_MASK).is_equal_to(LP_WITHDRAWAL_REPLY_ID);
+            let reply = Reply {
+                id: withdrawal_id,
+                result: SubMsgResult::Err("withdrawal failed".to_string()),
+            };
+            assert_that!(take_reply_context(deps.as_mut().storage, &reply)?.recipient)
+                .is_equal_to(Some(Addr::unchecked("holder")));
+            Ok(())
+        }
+
+        #[test]
+        fn rejects_ongoing_claims_and_other_nfts() -> anyhow::Result<()> {
+            let mut deps = setup()?;
+
+            assert_that!(send_nft(deps.as_mut(), "claims_nft", "2"))
+                .is_err()
+                .matches(|e| matches!(e, AutocompounderError::NoMaturedClaims {}));
+            assert_that!(send_nft(deps.as_mut(), "other_nft", "1"))
+                .is_err()
+                .matches(|e| matches!(e, AutocompounderError::SenderIsNotClaimsNft {}));
+            assert_that!(NFT_CLAIMS.has(deps.as_ref().storage, "1".to_string())).is_true();
+            Ok(())
+        }
+    }
+
+    mod process_matured_claims {
+        use super::*;
+        use crate::contract::REPLY_ID_MASK;
+        use cosmwasm_std::Timestamp;
+
+        fn claim(seconds: u64, lp_tokens: u128) -> Claim {
+            Claim {
+                unbonding_timestamp: Expiration::AtTime(Timestamp::from_seconds(seconds)),
+                amount_of_vault_tokens_to_burn: Uint128::new(lp_tokens * 10),
+                amount_of_lp_tokens_to_unbond: Uint128::new(lp_tokens),
+            }
+        }
+
+        fn process_msg() -> AutocompounderExecuteMsg {
+            AutocompounderExecuteMsg::ProcessMaturedClaims {
+                start_after: None,
+                limit: None,
+            }
+        }
+
+        /// Takes the payouts from the reply context of the withdrawal submessage
+        fn claim_payouts(deps: DepsMut, res: &Response) -> anyhow::Result<Vec<(Addr, Uint128)>> {
+            let withdrawal_id = res.messages.last().unwrap().id;
+            assert_that!(withdrawal_id & REPLY_ID_MASK)
+                .is_equal_to(MATURED_CLAIMS_WITHDRAWAL_REPLY_ID);
+            let reply = Reply {
+                id: withdrawal_id,
+                result: SubMsgResult::Err("withdrawal failed".to_string()),
+            };
+            Ok(take_reply_context(deps.storage, &reply)?.claim_payouts)
+        }
+
+        #[test]
+        fn pays_out_matured_claims() -> anyhow::Result<()> {
+            let mut deps = app_init(true, true);
+            let ongoing = claim(mock_env().block.time.seconds() + 100, 50);
+            CLAIMS.save(
+                deps.as_mut().storage,
+                Addr::unchecked("owner1"),
+                &vec![claim(100, 10), ongoing.clone()],
+            )?;
+            CLAIMS.save(
+                deps.as_mut().storage,
+                Addr::unchecked("owner2"),
+                &vec![ongoing.clone()],
+            )?;
+
+            let res = execute_as(deps.as_mut(), "anyone", process_msg(), &[])?;
+            assert_that!(claim_payouts(deps.as_mut(), &res)?)
+                .is_equal_to(vec![(Addr::unchecked("owner1"), Uint128::new(10))]);
+            assert_that!(CLAIMS.load(deps.as_ref().storage, Addr::unchecked("owner1"))?)
+                .is_equal_to(vec![ongoing]);
+
+            // the matured claims were taken
+            assert_that!(execute_as(deps.as_mut(), "anyone", process_msg(), &[]))
+                .is_err()
+                .matches(|e| matches!(e, AutocompounderError::NoMaturedClaims {}));
+            Ok(())
+        }
+
+        #[test]
+        fn leaves_nft_claims_to_their_holders() -> anyhow::Result<()> {
+            let mut deps = app_init(true, true);
+            CLAIMS_NFT.save(deps.as_mut().storage, &Addr::unchecked("claims_nft"))?;
+            NFT_CLAIMS.save(deps.as_mut().storage, "1".to_string(), &claim(100, 10))?;
+
+            // the vault can't burn the claim nft, so the claim is left to its holder
+            assert_that!(execute_as(deps.as_mut(), "anyone", process_msg(), &[]))
+                .is_err()
+                .matches(|e| matches!(e, AutocompounderError::ClaimsAreTokenised {}));
+            assert_that!(NFT_CLAIMS.load(deps.as_ref().storage, "1".to_string())?)
+                .is_equal_to(claim(100, 10));
+            Ok(())
+        }
+    }
 }
@@ -1,14 +1,29 @@
 use crate::contract::INSTANTIATE_REPLY_ID;
+use crate::contract::REPLY_ID_BITS;
 
+use crate::kujira_tx::encode_query_denom_metadata;
 use crate::kujira_tx::encode_query_supply_of;
-use crate::kujira_tx::max_subdenom_length_for_chain;
-use crate::kujira_tx::tokenfactory_burn_msg;
-use crate::kujira_tx::tokenfactory_create_denom_msg;
-use crate::kujira_tx::tokenfactory_mint_msg;
+use crate::kujira_tx::BankDenomMetadata;
+use crate::kujira_tx::DenomMetadata;
+use crate::kujira_tx::DenomMetadataResponse;
+use crate::kujira_tx::TokenFactory;
+use crate::kujira_tx::DENOM_METADATA_PATH;
+use crate::kujira_tx::MAX_SUBDENOM_LEN_KUJIRA;
+use crate::kujira_tx::OSMOSIS_POOL_SHARE_DECIMALS;
+use crate::kujira_tx::OSMOSIS_POOL_SHARE_PREFIX;
 use crate::kujira_tx::SUPPLY_OF_PATH;
 use crate::msg::Config;
+use crate::msg::DepositCaps;
+use crate::msg::VaultTokenMetadata;
+use crate::state::ReplyContext;
 use crate::state::CONFIG;
 use crate::state::DECIMAL_OFFSET;
+use crate::state::DEFAULT_VAULT_TOKEN_DECIMALS;
+use crate::state::LIQUIDITY_BUFFER;
+use crate::state::REPLY_CONTEXTS;
+use crate::state::REPLY_CONTEXT_NONCE;
+use crate::state::TOKEN_FACTORY;
+use crate::state::VAULT_DECIMAL_OFFSET;
 
 use crate::state::VAULT_TOKEN_SYMBOL;
 use crate::{
@@ -32,13 +47,16 @@ use cosmwasm_std::Reply;
 use cosmwasm_std::SupplyResponse;
 
 use cosmwasm_std::{
-    to_json_binary, wasm_execute, Addr, CosmosMsg, Decimal, Deps, ReplyOn, StdError, SubMsg,
-    Uint128, WasmMsg,
+    to_json_binary, wasm_execute, Addr, CosmosMsg, Decimal, Deps, ReplyOn, StdError, StdResult,
+    Storage, SubMsg, Uint128, WasmMsg,
 };
+use cw20::Logo;
 use cw20::MinterResponse;
 use cw20::{Cw20QueryMsg, TokenInfoResponse};
 use cw20_base::msg::ExecuteMsg::Mint;
+use cw20_base::msg::InstantiateMarketingInfo;
 use cw20_base::msg::InstantiateMsg as TokenInstantiateMsg;
+use cw_asset::Asset;
 use cw_asset::AssetError;
 use cw_asset::AssetInfo;
 use cw_utils::Duration;
@@ -50,7 +68,7 @@ use cw_utils::parse_reply_instantiate_data;
 // ------------------------------------------------------------
 /// performs stargate query for the following path: "/cosmos.bank.v1beta1.Query/SupplyOf".
 pub fn query_supply_with_stargate(deps: Deps, denom: &str) -> AutocompounderResult<Coin> {
-    // this may not work because kujira has its own custom bindings. https://docs.rs/kujira-std/0.8.4/kujira_std/enum.KujiraQuery.html
+    // kujira has its own custom bindings, see `query_supply_with_kujira_bindings`. https://docs.rs/kujira-std/0.8.4/kujira_std/enum.KujiraQuery.html
     let request = QueryRequest::Stargate {
         path: SUPPLY_OF_PATH.to_string(),
         data: encode_query_supply_of(denom).into(),
@@ -59,21 +77,82 @@ pub fn query_supply_with_stargate(deps: Deps, denom: &str) -> AutocompounderResu
     Ok(res.amount)
 }
 
-/// create a SubMsg to instantiate the Vault token with either the tokenfactory(kujira) or a cw20.
+/// Loads the tokenfactory of the chain. Vaults instantiated before it was stored use the chain of the contract address.
+pub fn vault_token_factory(deps: Deps, contract: &Addr) -> AutocompounderResult<TokenFactory> {
+    match TOKEN_FACTORY.may_load(deps.storage)? {
+        Some(token_factory) => Ok(token_factory),
+        None => TokenFactory::from_address(contract),
+    }
+}
+
+/// performs stargate query for the following path: "/cosmos.bank.v1beta1.Query/DenomMetadata".
+pub fn query_denom_metadata_with_stargate(
+    deps: Deps,
+    denom: &str,
+) -> AutocompounderResult<BankDenomMetadata> {
+    let request = QueryRequest::Stargate {
+        path: DENOM_METADATA_PATH.to_string(),
+        data: encode_query_denom_metadata(denom).into(),
+    };
+    let res: DenomMetadataResponse = deps.querier.query(&request)?;
+    Ok(res.metadata)
+}
+
+/// Queries the supply of a denom with the kujira custom bindings, which kujira supports instead of the stargate supply query.
+#[cfg(feature = "kujira")]
+pub fn query_supply_with_kujira_bindings(deps: Deps, denom: &str) -> AutocompounderResult<Coin> {
+    use cosmwasm_std::QuerierWrapper;
+    use kujira::{BankQuery, Denom, KujiraQuery, SupplyResponse};
+
+    let request: QueryRequest<KujiraQuery> = KujiraQuery::Bank(BankQuery::Supply {
+        denom: Denom::from(denom),
+    })
+    .into();
+    let res: SupplyResponse = QuerierWrapper::<KujiraQuery>::new(&*deps.querier).query(&request)?;
+    Ok(res.amount)
+}
+
+/// Queries the supply of a native vault token with the backend of the tokenfactory of `contract`.
+/// Kujira vaults use the custom bindings when the `kujira` feature is enabled, other chains the stargate query.
+/// The denom messages stay stargate messages on kujira, see [`mint_vault_tokens_msgs`].
+#[cfg_attr(not(feature = "kujira"), allow(unused_variables))]
+pub fn query_native_vault_token_supply(
+    deps: Deps,
+    contract: &Addr,
+    denom: &str,
+) -> AutocompounderResult<Coin> {
+    #[cfg(feature = "kujira")]
+    if vault_token_factory(deps, contract)? == TokenFactory::Kujira {
+        return query_supply_with_kujira_bindings(deps, denom);
+    }
+    query_supply_with_stargate(deps, denom)
+}
+
+/// create a SubMsg to instantiate the Vault token with either the tokenfactory or a cw20.
 pub fn create_vault_token_submsg(
     minter: String,
     subdenom: String,
     code_id: Option<u64>,
-    dex: String,
+    token_factory: Option<TokenFactory>,
+    metadata: &VaultTokenMetadata,
 ) -> Result<SubMsg, AutocompounderError> {
     if let Some(code_id) = code_id {
+        let marketing = metadata
+            .marketing
+            .clone()
+            .map(|marketing| InstantiateMarketingInfo {
+                project: marketing.project,
+                description: marketing.description,
+                marketing: marketing.marketing,
+                logo: marketing.logo_url.map(Logo::Url),
+            });
         let msg = TokenInstantiateMsg {
-            name: subdenom,
-            symbol: VAULT_TOKEN_SYMBOL.to_string(),
-            decimals: 6,
+            name: metadata.name.clone().unwrap_or(subdenom),
+            symbol: vault_token_symbol(metadata),
+            decimals: metadata.decimals.unwrap_or(DEFAULT_VAULT_TOKEN_DECIMALS),
             initial_balances: vec![],
             mint: Some(MinterResponse { minter, cap: None }),
-            marketing: None,
+            marketing,
         };
         Ok(SubMsg {
             msg: WasmMsg::Instantiate {
@@ -89,7 +168,11 @@ pub fn create_vault_token_submsg(
             reply_on: ReplyOn::Success,
         })
     } else {
-        let cosmos_msg = tokenfactory_create_denom_msg(minter, subdenom, dex.as_str());
+        let token_factory = match token_factory {
+            Some(token_factory) => token_factory,
+            None => TokenFactory::from_address(&Addr::unchecked(&minter))?,
+        };
+        let cosmos_msg = token_factory.create_denom_msg(minter, subdenom);
         let sub_msg = SubMsg {
             msg: cosmos_msg,
             gas_limit: None,
@@ -102,6 +185,40 @@ pub fn create_vault_token_submsg(
 }
 // factory/cosmos2contract/V-4T2/wyndex:eur,usd:constant_product
 
+/// Creates the message to set the bank metadata of a tokenfactory vault token, if the chain supports it.
+pub fn vault_token_denom_metadata_msg(
+    admin: &Addr,
+    denom: String,
+    subdenom: String,
+    token_factory: TokenFactory,
+    metadata: &VaultTokenMetadata,
+) -> Option<CosmosMsg> {
+    if !token_factory.supports_denom_metadata() {
+        return None;
+    }
+    let marketing = metadata.marketing.clone().unwrap_or_default();
+    let denom_metadata = DenomMetadata {
+        description: marketing.description.unwrap_or_default(),
+        base: denom,
+        display: vault_token_symbol(metadata),
+        exponent: metadata
+            .decimals
+            .unwrap_or(DEFAULT_VAULT_TOKEN_DECIMALS)
+            .into(),
+        name: metadata.name.clone().unwrap_or(subdenom),
+        symbol: vault_token_symbol(metadata),
+        uri: marketing.logo_url.unwrap_or_default(),
+    };
+    Some(token_factory.set_denom_metadata_msg(admin, &denom_metadata))
+}
+
+fn vault_token_symbol(metadata: &VaultTokenMetadata) -> String {
+    metadata
+        .symbol
+        .clone()
+        .unwrap_or_else(|| VAULT_TOKEN_SYMBOL.to_string())
+}
+
 /// parses the instantiate reply to get the contract address of the vault token or None if kujira. for kujira the denom is already set in instantiate.
 pub fn parse_instantiate_reply_cw20(
     reply: Reply,
@@ -113,18 +230,20 @@ pub fn parse_instantiate_reply_cw20(
     Ok(Some(vault_token))
 }
 
-/// Creates the message to mint tokens to `recipient`
-pub fn mint_vault_tokens_msg(
+/// Creates the messages to mint tokens to `recipient`
+/// Kujira vaults also mint with the stargate `MsgMint`: app handlers return a `Response<Empty>`,
+/// which can't carry `CosmosMsg<KujiraMsg>`, so the create, mint and burn messages can't use the kujira bindings.
+pub fn mint_vault_tokens_msgs(
+    deps: Deps,
     config: &Config,
     minter: &Addr,
     recipient: Addr,
     amount: Uint128,
-    dex: String,
-) -> Result<CosmosMsg, AutocompounderError> {
+) -> Result<Vec<CosmosMsg>, AutocompounderError> {
     match config.vault_token.clone() {
         AssetInfo::Native(denom) => {
-            tokenfactory_mint_msg(minter, denom, amount, recipient.as_str(), dex.as_str())
-                .map_err(|e| e.into())
+            let token_factory = vault_token_factory(deps, minter)?;
+            Ok(token_factory.mint_msgs(minter, denom, amount, recipient.as_str()))
         }
         AssetInfo::Cw20(token_addr) => {
             let mint_msg = wasm_execute(
@@ -136,7 +255,7 @@ pub fn mint_vault_tokens_msg(
                 vec![],
             )?
             .into();
-            Ok(mint_msg)
+            Ok(vec![mint_msg])
         }
         _ => Err(AutocompounderError::Std(StdError::generic_err(
             "Vault token is not a cw20 token",
@@ -146,14 +265,15 @@ pub fn mint_vault_tokens_msg(
 
 /// Creates the message to burn tokens from contract
 pub fn burn_vault_tokens_msg(
+    deps: Deps,
     config: &Config,
     minter: &Addr,
     amount: Uint128,
-    dex: String,
 ) -> AutocompounderResult<CosmosMsg> {
     match config.vault_token.clone() {
         AssetInfo::Native(denom) => {
-            tokenfactory_burn_msg(minter, denom, amount, dex.as_str()).map_err(|e| e.into())
+            let token_factory = vault_token_factory(deps, minter)?;
+            Ok(token_factory.burn_msg(minter, denom, amount))
         }
         AssetInfo::Cw20(token_addr) => {
             let msg = cw20_base::msg::ExecuteMsg::Burn { amount };
@@ -165,11 +285,40 @@ pub fn burn_vault_tokens_msg(
     }
 }
 
-/// query the total supply of the vault token
-pub fn vault_token_total_supply(deps: Deps, config: &Config) -> AutocompounderResult<Uint128> {
+/// Creates the message that hands the mint authority of the vault token to `new_admin`.
+/// For native vault tokens this changes the denom admin, for cw20 vault tokens the minter.
+pub fn change_vault_token_admin_msg(
+    deps: Deps,
+    config: &Config,
+    admin: &Addr,
+    new_admin: &Addr,
+) -> AutocompounderResult<CosmosMsg> {
+    match config.vault_token.clone() {
+        AssetInfo::Native(denom) => {
+            let token_factory = vault_token_factory(deps, admin)?;
+            Ok(token_factory.change_admin_msg(admin, denom, new_admin))
+        }
+        AssetInfo::Cw20(token_addr) => {
+            let msg = cw20_base::msg::ExecuteMsg::UpdateMinter {
+                new_minter: Some(new_admin.to_string()),
+            };
+            Ok(wasm_execute(token_addr, &msg, vec![])?.into())
+        }
+        _ => Err(AutocompounderError::AssetError(
+            AssetError::InvalidAssetType { ty: "".to_string() },
+        )),
+    }
+}
+
+/// query the total supply of the vault token of `contract`
+pub fn vault_token_total_supply(
+    deps: Deps,
+    contract: &Addr,
+    config: &Config,
+) -> AutocompounderResult<Uint128> {
     match config.vault_token.clone() {
         AssetInfo::Native(denom) => {
-            let supply = query_supply_with_stargate(deps, &denom)?;
+            let supply = query_native_vault_token_supply(deps, contract, &denom)?;
             Ok(supply.amount)
         }
         AssetInfo::Cw20(token_addr) => {
@@ -187,6 +336,22 @@ pub fn vault_token_total_supply(deps: Deps, config: &Config) -> AutocompounderRe
     }
 }
 
+/// query the total supply of `token`, native supplies are queried with stargate
+pub fn query_token_supply(deps: Deps, token: &AssetInfo) -> AutocompounderResult<Uint128> {
+    match token {
+        AssetInfo::Native(denom) => Ok(query_supply_with_stargate(deps, denom)?.amount),
+        AssetInfo::Cw20(token_addr) => {
+            let TokenInfoResponse { total_supply, .. } = deps
+                .querier
+                .query_wasm_smart(token_addr, &Cw20QueryMsg::TokenInfo {})?;
+            Ok(total_supply)
+        }
+        _ => Err(AutocompounderError::Std(StdError::generic_err(
+            "Unsupported token type",
+        ))),
+    }
+}
+
 /// query the balance of the vault token for user with `addr`
 pub fn vault_token_balance(
     deps: Deps,
@@ -230,6 +395,117 @@ pub fn query_stake(
     Ok(*amount)
 }
 
+/// queries the staking module for the reward tokens of the lp token.
+/// The reward token sets of all reward pools (e.g. external incentives or gauges) are merged.
+pub fn query_reward_tokens(
+    deps: Deps,
+    app: &AutocompounderApp,
+    config: &Config,
+) -> AbstractSdkResult<Vec<AssetInfo>> {
+    let adapters = app.adapters(deps);
+    let query = StakingQueryMsg::RewardTokens {
+        provider: config.pool_data.dex.clone(),
+        staking_tokens: vec![config.lp_asset_entry()],
+    };
+    let RewardTokensResponse { tokens } = adapters.query(CW_STAKING_ADAPTER_ID, query)?;
+
+    Ok(merge_reward_tokens(tokens))
+}
+
+/// queries the rewards that the proxy can claim from the staking contract of the lp token
+pub fn query_pending_rewards(
+    deps: Deps,
+    app: &AutocompounderApp,
+    config: &Config,
+) -> AutocompounderResult<Vec<Asset>> {
+    let adapters = app.adapters(deps);
+    let query = StakingQueryMsg::Info {
+        provider: config.pool_data.dex.clone(),
+        staking_tokens: vec![config.lp_asset_entry()],
+    };
+    let StakingInfoResponse { infos } = adapters.query(CW_STAKING_ADAPTER_ID, query)?;
+    let staking_contract = match infos.into_iter().next().map(|info| info.staking_target) {
+        Some(StakingTarget::Contract(staking_contract)) => staking_contract,
+        _ => {
+            return Err(AutocompounderError::PendingRewardsNotQueryable {
+                provider: config.pool_data.dex.clone(),
+            })
+        }
+    };
+
+    crate::pending_rewards::query_pending_rewards(
+        deps,
+        &config.pool_data.dex,
+        &staking_contract,
+        &app.proxy_address(deps)?,
+    )
+}
+
+/// Flattens the reward token sets, keeping the first occurrence of every token
+fn merge_reward_tokens(token_sets: Vec<Vec<AssetInfo>>) -> Vec<AssetInfo> {
+    let mut reward_tokens: Vec<AssetInfo> = vec![];
+    for token in token_sets.into_iter().flatten() {
+        if !reward_tokens.contains(&token) {
+            reward_tokens.push(token);
+        }
+    }
+    reward_tokens
+}
+
+/// queries the total lp tokens of the vault, being the staked lp tokens and the liquidity buffer
+pub fn query_total_lp(
+    deps: Deps,
+    app: &AutocompounderApp,
+    config: &Config,
+) -> AutocompounderResult<Uint128> {
+    let staked_lp = query_stake(
+        deps,
+        app,
+        config.pool_data.dex.clone(),
+        config.lp_asset_entry(),
+        config.unbonding_period,
+    )?;
+    let buffer = LIQUIDITY_BUFFER.may_load(deps.storage)?.unwrap_or_default();
+    Ok(staked_lp + buffer)
+}
+
+/// Splits new lp tokens between staking and the liquidity buffer, moving the buffer towards `target` of the vault's lp tokens.
+/// The buffer is only topped up with new lp tokens, as unstaking lp tokens is subject to the unbonding period.
+/// Returns the lp tokens to stake and the new buffer.
+pub fn rebalance_liquidity_buffer(
+    new_lp: Uint128,
+    staked_lp: Uint128,
+    buffer: Uint128,
+    target: Decimal,
+) -> (Uint128, Uint128) {
+    let target_buffer = (staked_lp + buffer + new_lp) * target;
+    if buffer < target_buffer {
+        let top_up = (target_buffer - buffer).min(new_lp);
+        (new_lp - top_up, buffer + top_up)
+    } else {
+        (new_lp + (buffer - target_buffer), target_buffer)
+    }
+}
+
+/// Checks the vault's lp tokens and the vault token balance of the depositor after a deposit against the `caps`
+pub fn check_deposit_caps(
+    caps: &DepositCaps,
+    total_lp_after: Uint128,
+    balance_after: Uint128,
+) -> Result<(), AutocompounderError> {
+    if let Some(max_total_lp) = caps.max_total_lp {
+        if total_lp_after > max_total_lp {
+            return Err(AutocompounderError::TotalDepositCapExceeded { max_total_lp });
+        }
+    }
+    if let Some(max_vault_tokens) = caps.max_vault_tokens_per_address {
+        if balance_after > max_vault_tokens {
+            return Err(AutocompounderError::AddressDepositCapExceeded { max_vault_tokens });
+        }
+    }
+    Ok(())
+}
+
 pub fn stake_lp_tokens(
     deps: Deps,
     app: &AutocompounderApp,
@@ -250,34 +526,84 @@ pub fn stake_lp_tokens(
     )
 }
 
-/// creates subdenom that is truncated by the max denom length per app
+/// creates subdenom that is truncated by the max denom length of the tokenfactory, see [`TokenFactory::max_subdenom_length`]
 /// For osmosis: // max length of subdenom for osmosis is 44 https://github.com/osmosis-labs/osmosis/blob/6a53f5611ae27b653a5758333c9a0862835917f4/x/tokenfactory/types/denoms.go#L10-L36
 /// For Kujira: // max length of subdenom for kujira is 64 (+8 + 32 ) (they use comsos-sdk validateDenom function https://github.com/Team-Kujira/core/blob/554950147825e94fa52c3ff0a3b138568cf7c774/x/denom/types/denoms.go#L31 https://github.com/cosmos/cosmos-sdk/blob/47770f332c0181924a04c1d87684b8fc62a3bc69/types/coin.go#L833-L841)
-pub fn create_subdenom_from_pool_assets(pool_data: &PoolMetadata) -> String {
+/// cw20 vault tokens use the kujira length.
+pub fn create_subdenom_from_pool_assets(
+    pool_data: &PoolMetadata,
+    token_factory: Option<TokenFactory>,
+) -> String {
     let mut full_denom = format!("VT_4T2/{}", pool_data)
         .replace(',', "_")
         .replace('>', "-");
-    full_denom.truncate(max_subdenom_length_for_chain(&pool_data.dex));
+    full_denom.truncate(token_factory.map_or(MAX_SUBDENOM_LEN_KUJIRA, |factory| {
+        factory.max_subdenom_length()
+    }));
     full_denom
 }
 
+/// Loads the decimal offset of the vault. Vaults instantiated before it was configurable use the default.
+pub fn decimal_offset(storage: &dyn Storage) -> StdResult<u32> {
+    Ok(VAULT_DECIMAL_OFFSET
+        .may_load(storage)?
+        .unwrap_or(DECIMAL_OFFSET))
+}
+
 /// Convert vault tokens to lp assets
-pub fn convert_to_assets(shares: Uint128, total_assets: Uint128, total_supply: Uint128) -> Uint128 {
+pub fn convert_to_assets(
+    shares: Uint128,
+    total_assets: Uint128,
+    total_supply: Uint128,
+    decimal_offset: u32,
+) -> Uint128 {
     shares.multiply_ratio(
         total_assets + Uint128::from(1u128),
-        total_supply + Uint128::from(10u128).pow(DECIMAL_OFFSET),
+        total_supply + Uint128::from(10u128).pow(decimal_offset),
     )
 }
 
 /// Convert lp assets to shares
 /// Uses virtual assets to mitigate asset inflation attack. description: https://gist.github.com/Amxx/ec7992a21499b6587979754206a48632
-pub fn convert_to_shares(assets: Uint128, total_assets: Uint128, total_supply: Uint128) -> Uint128 {
+pub fn convert_to_shares(
+    assets: Uint128,
+    total_assets: Uint128,
+    total_supply: Uint128,
+    decimal_offset: u32,
+) -> Uint128 {
     assets.multiply_ratio(
-        total_supply + Uint128::from(10u128).pow(DECIMAL_OFFSET),
+        total_supply + Uint128::from(10u128).pow(decimal_offset),
         total_assets + Uint128::from(1u128),
     )
 }
 
+/// Queries the decimals of a token, from the cw20 token info or the bank metadata of a native denom.
+/// Errors for native denoms whose decimals can't be queried, so that they are set explicitly.
+pub fn query_token_decimals(deps: Deps, token: &AssetInfo) -> AutocompounderResult<u8> {
+    match token {
+        AssetInfo::Cw20(token_addr) => {
+            let TokenInfoResponse { decimals, .. } = deps
+                .querier
+                .query_wasm_smart(token_addr, &Cw20QueryMsg::TokenInfo {})?;
+            Ok(decimals)
+        }
+        AssetInfo::Native(denom) => {
+            // osmosis pool shares have no bank metadata
+            if denom.starts_with(OSMOSIS_POOL_SHARE_PREFIX) {
+                return Ok(OSMOSIS_POOL_SHARE_DECIMALS);
+            }
+            query_denom_metadata_with_stargate(deps, denom)?
+                .decimals()
+                .ok_or_else(|| AutocompounderError::UnknownTokenDecimals {
+                    token: denom.clone(),
+                })
+        }
+        _ => Err(AutocompounderError::AssetError(
+            AssetError::InvalidAssetType { ty: "".to_string() },
+        )),
+    }
+}
+
 pub fn check_fee(fee: Decimal) -> Result<(), AutocompounderError> {
     if fee > Decimal::percent(99) {
         return Err(AutocompounderError::InvalidFee {});
@@ -285,6 +611,59 @@ pub fn check_fee(fee: Decimal) -> Result<(), AutocompounderError> {
     Ok(())
 }
 
+/// Checks that the name and symbol of the vault token are accepted by cw20 and by the bank module.
+/// Follows the cw20-base rules: a name of 3 to 50 characters and a symbol of 3 to 12 letters or `-`.
+pub fn check_vault_token_metadata(metadata: &VaultTokenMetadata) -> AutocompounderResult<()> {
+    let invalid = |field: &str, reason: &str| AutocompounderError::InvalidVaultTokenMetadata {
+        field: field.to_string(),
+        reason: reason.to_string(),
+    };
+    if let Some(name) = &metadata.name {
+        if !(3..=50).contains(&name.chars().count()) {
+            return Err(invalid("name", "must be between 3 and 50 characters"));
+        }
+    }
+    if let Some(symbol) = &metadata.symbol {
+        if !(3..=12).contains(&symbol.len()) {
+            return Err(invalid("symbol", "must be between 3 and 12 characters"));
+        }
+        if !symbol.bytes().all(|c| c.is_ascii_alphabetic() || c == b'-') {
+            return Err(invalid("symbol", "can only contain letters and '-'"));
+        }
+    }
+    Ok(())
+}
+
+/// Checks that the tokenfactory creation fee, which is charged from the contract when the denom is created, can be paid.
+/// The fee can be sent along with the instantiation, to the contract address beforehand, or be held by the proxy.
+/// Returns the part of the fee that the proxy has to transfer to the contract.
+pub fn check_creation_fee(
+    deps: Deps,
+    contract: &Addr,
+    proxy: &Addr,
+    creation_fee: &[Coin],
+) -> AutocompounderResult<Vec<Coin>> {
+    let mut from_proxy = vec![];
+    for fee in creation_fee {
+        let held = deps.querier.query_balance(contract, &fee.denom)?.amount;
+        if held >= fee.amount {
+            continue;
+        }
+        let missing = fee.amount - held;
+        if deps.querier.query_balance(proxy, &fee.denom)?.amount < missing {
+            return Err(AutocompounderError::MissingCreationFee {
+                fee: creation_fee
+                    .iter()
+                    .map(Coin::to_string)
+                    .collect::<Vec<_>>()
+                    .join(","),
+            });
+        }
+        from_proxy.push(Coin::new(missing.u128(), fee.denom.clone()));
+    }
+    Ok(from_proxy)
+}
+
 /// swaps all rewards that are not in the target assets and add a reply id to the latest swapmsg
 pub fn swap_rewards(
     app: &AutocompounderApp,
@@ -294,30 +673,45 @@ pub fn swap_rewards(
     let config = CONFIG.load(deps.storage)?;
     let dex_name = config.pool_data.dex;
     let max_spread = config.max_swap_spread;
-    let target_assets = config.pool_data.assets;
 
-    let dex = app.ans_dex(deps, dex_name.clone());
-    let ans_host = app.ans_host(deps)?;
+    let dex = app.ans_dex(deps, dex_name);
 
     let mut swap_msgs = Vec::new();
-    for reward in &rewards {
+    for (reward, target_asset) in reward_swaps(app, deps, rewards)? {
+        let swap_msg = dex.swap(reward, target_asset, Some(max_spread), None)?;
+        swap_msgs.push(swap_msg);
+    }
+    Ok(swap_msgs)
+}
+
+/// pairs all rewards that are not in the target assets with the pool asset they are swapped to
+pub fn reward_swaps(
+    app: &AutocompounderApp,
+    deps: Deps,
+    rewards: Vec<AnsAsset>,
+) -> AutocompounderResult<Vec<(AnsAsset, AssetEntry)>> {
+    let config = CONFIG.load(deps.storage)?;
+    let dex_name = config.pool_data.dex;
+    let target_assets = config.pool_data.assets;
+    let ans_host = app.ans_host(deps)?;
+
+    let mut swaps = Vec::new();
+    for reward in rewards {
         if !target_assets
             .iter()
             .any(|target_asset| &reward.name == target_asset)
         {
             let target_asset = match_reward_asset_with_pool_asset(
-                reward,
+                &reward,
                 &target_assets,
                 &dex_name,
                 &ans_host,
                 deps,
             )?;
-
-            let swap_msg = dex.swap(reward.clone(), target_asset, Some(max_spread), None)?;
-            swap_msgs.push(swap_msg);
+            swaps.push((reward, target_asset));
         }
     }
-    Ok(swap_msgs)
+    Ok(swaps)
 }
 
 fn match_reward_asset_with_pool_asset(
@@ -380,6 +774,75 @@ pub fn transfer_to_msgs(
     Ok(app.executor(deps).execute(actions)?.into())
 }
 
+/// Creates a reply context holding the pool assets owned by the proxy, so the reply can compute the received assets.
+pub fn pool_assets_context(
+    deps: Deps,
+    app: &AutocompounderApp,
+    config: &Config,
+) -> AutocompounderResult<ReplyContext> {
+    let cached_assets = app.bank(deps).balances(&config.pool_data.assets)?;
+    Ok(ReplyContext {
+        cached_assets,
+        ..Default::default()
+    })
+}
+
+/// Creates a reply context holding the pool assets and reward tokens owned by the proxy before the rewards are claimed,
+/// so the compound only uses the harvested rewards.
+pub fn compound_context(
+    deps: Deps,
+    app: &AutocompounderApp,
+    config: &Config,
+) -> AutocompounderResult<ReplyContext> {
+    let mut cached_assets = pool_assets_context(deps, app, config)?.cached_assets;
+    let proxy = app.proxy_address(deps)?;
+    for reward in query_reward_tokens(deps, app, config)? {
+        if cached_assets.iter().all(|cached| cached.info != reward) {
+            let balance = reward.query_balance(&deps.querier, &proxy)?;
+            cached_assets.push(Asset::new(reward, balance));
+        }
+    }
+    Ok(ReplyContext {
+        cached_assets,
+        ..Default::default()
+    })
+}
+
+/// Saves the context of a submessage and returns the submessage id, which carries the context nonce next to `reply_id`
+pub fn save_reply_context(
+    storage: &mut dyn Storage,
+    context: ReplyContext,
+    reply_id: u64,
+) -> StdResult<u64> {
+    let nonce = REPLY_CONTEXT_NONCE.may_load(storage)?.unwrap_or_default() + 1;
+    REPLY_CONTEXT_NONCE.save(storage, &nonce)?;
+    REPLY_CONTEXTS.save(storage, nonce, &context)?;
+    Ok(nonce << REPLY_ID_BITS | reply_id)
+}
+
+/// Updates the context saved for the submessage `submsg_id`, which is yet to be executed
+pub fn update_reply_context(
+    storage: &mut dyn Storage,
+    submsg_id: u64,
+    update: impl FnOnce(ReplyContext) -> ReplyContext,
+) -> StdResult<()> {
+    let nonce = submsg_id >> REPLY_ID_BITS;
+    let context = REPLY_CONTEXTS
+        .may_load(storage, nonce)?
+        .ok_or_else(|| StdError::not_found("ReplyContext"))?;
+    REPLY_CONTEXTS.save(storage, nonce, &update(context))
+}
+
+/// Loads and removes the context of the reply that is being handled
+pub fn take_reply_context(storage: &mut dyn Storage, reply: &Reply) -> StdResult<ReplyContext> {
+    let nonce = reply.id >> REPLY_ID_BITS;
+    let context = REPLY_CONTEXTS
+        .may_load(storage, nonce)?
+        .ok_or_else(|| StdError::not_found("ReplyContext"))?;
+    REPLY_CONTEXTS.remove(storage, nonce);
+    Ok(context)
+}
+
 /// computes the minimum cooldown period based on the max claims and unbonding duration.
 fn compute_min_unbonding_cooldown(
     max_claims: Option<u32>,
@@ -420,22 +883,25 @@ pub fn get_unbonding_period_and_cooldown(
 #[cfg(test)]
 pub mod helpers_tests {
     use crate::{
-        contract::AUTOCOMPOUNDER_APP,
+        contract::{AUTOCOMPOUNDER_APP, LP_PROVISION_REPLY_ID, REPLY_ID_MASK},
         kujira_tx::format_tokenfactory_denom,
         test_common::{app_base_mock_querier, app_init, TEST_VAULT_TOKEN},
     };
 
     use super::*;
     use abstract_core::objects::{pool_id::PoolAddressBase, PoolMetadata};
-    use abstract_testing::prelude::{EUR, USD};
+    use abstract_testing::prelude::{EUR, TEST_PROXY, USD};
     use cosmwasm_std::{
-        from_json,
-        testing::{mock_dependencies, MockApi, MockStorage},
-        Empty, OwnedDeps, Querier, SystemResult,
+        coins, from_json,
+        testing::{
+            mock_dependencies, mock_dependencies_with_balance, MockApi, MockStorage,
+            MOCK_CONTRACT_ADDR,
+        },
+        BankMsg, Empty, OwnedDeps, Querier, SubMsgResult, SystemResult,
     };
 
     use cw_asset::AssetInfoBase;
-    use speculoos::{assert_that, result::ResultAssertions};
+    use speculoos::prelude::*;
     use wyndex_bundle::WYND_TOKEN;
 
     type AResult = anyhow::Result<()>;
@@ -477,6 +943,17 @@ pub mod helpers_tests {
                         SystemResult::Ok(cosmwasm_std::ContractResult::Ok(
                             to_json_binary(&supply).unwrap(),
                         ))
+                    } else if path == DENOM_METADATA_PATH {
+                        // metadata without a display unit
+                        let metadata = DenomMetadataResponse {
+                            metadata: BankDenomMetadata {
+                                denom_units: vec![],
+                                display: String::new(),
+                            },
+                        };
+                        SystemResult::Ok(cosmwasm_std::ContractResult::Ok(
+                            to_json_binary(&metadata).unwrap(),
+                        ))
                     } else {
                         SystemResult::Err(cosmwasm_std::SystemError::UnsupportedRequest {
                             kind: format!("query for path: {path}"),
@@ -545,17 +1022,49 @@ pub mod helpers_tests {
     fn vault_token_total_supply_native() -> AResult {
         let deps = mock_deps_with_stargate();
         let config = min_cooldown_config(Some(Duration::Time(1)), true);
-        let supply = vault_token_total_supply(deps.as_ref(), &config)?;
+        let supply =
+            vault_token_total_supply(deps.as_ref(), &Addr::unchecked(MOCK_CONTRACT_ADDR), &config)?;
         assert_that!(supply).is_equal_to(Uint128::from(100u128));
         Ok(())
     }
 
+    #[cfg(feature = "kujira")]
+    #[test]
+    fn vault_token_total_supply_kujira_bindings() -> AResult {
+        use cosmwasm_std::{coin, testing::MockQuerier, ContractResult};
+        use kujira::{BankQuery, KujiraQuery, SupplyResponse};
+
+        let querier = MockQuerier::<KujiraQuery>::new(&[]).with_custom_handler(|query| {
+            let KujiraQuery::Bank(BankQuery::Supply { denom }) = query else {
+                panic!("unexpected query {query:?}");
+            };
+            let supply = SupplyResponse {
+                amount: coin(1000, denom.to_string()),
+            };
+            SystemResult::Ok(ContractResult::Ok(to_json_binary(&supply).unwrap()))
+        });
+        let mut deps = OwnedDeps {
+            storage: MockStorage::default(),
+            api: MockApi::default(),
+            querier,
+            custom_query_type: Default::default(),
+        };
+        TOKEN_FACTORY.save(deps.as_mut().storage, &TokenFactory::Kujira)?;
+
+        let config = min_cooldown_config(Some(Duration::Time(1)), true);
+        let supply =
+            vault_token_total_supply(deps.as_ref(), &Addr::unchecked(MOCK_CONTRACT_ADDR), &config)?;
+        assert_that!(supply).is_equal_to(Uint128::from(1000u128));
+        Ok(())
+    }
+
     #[test]
     fn vault_token_total_supply_cw20() -> AResult {
         let mut deps = mock_dependencies();
         deps.querier = app_base_mock_querier().build();
         let config = min_cooldown_config(Some(Duration::Time(1)), false);
-        let supply = vault_token_total_supply(deps.as_ref(), &config)?;
+        let supply =
+            vault_token_total_supply(deps.as_ref(), &Addr::unchecked(MOCK_CONTRACT_ADDR), &config)?;
         assert_that!(supply).is_equal_to(Uint128::from(1000u128));
         Ok(())
     }
@@ -577,7 +1086,13 @@ pub mod helpers_tests {
         let subdenom = "subdenom".to_string();
         let code_id = Some(1u64);
 
-        let result = create_vault_token_submsg(minter, subdenom, code_id, "kujira".to_string());
+        let result = create_vault_token_submsg(
+            minter,
+            subdenom,
+            code_id,
+            None,
+            &VaultTokenMetadata::default(),
+        );
         assert_that!(result).is_ok();
 
         let submsg = result.unwrap();
@@ -585,12 +1100,127 @@ pub mod helpers_tests {
         assert_that!(submsg.id).is_equal_to(INSTANTIATE_REPLY_ID);
     }
 
+    #[test]
+    fn create_cw20_vault_token_with_metadata() -> AResult {
+        let metadata = VaultTokenMetadata {
+            name: Some("4T2 EUR-USD".to_string()),
+            symbol: Some("vEURUSD".to_string()),
+            decimals: Some(8),
+            marketing: Some(crate::msg::VaultTokenMarketing {
+                logo_url: Some("https://example.com/logo.png".to_string()),
+                ..Default::default()
+            }),
+        };
+        let submsg = create_vault_token_submsg(
+            "minter".to_string(),
+            "subdenom".to_string(),
+            Some(1),
+            None,
+            &metadata,
+        )?;
+
+        let CosmosMsg::Wasm(WasmMsg::Instantiate { msg, .. }) = submsg.msg else {
+            panic!("expected instantiate msg");
+        };
+        let msg: TokenInstantiateMsg = from_json(msg)?;
+        assert_that!(msg.name).is_equal_to("4T2 EUR-USD".to_string());
+        assert_that!(msg.symbol).is_equal_to("vEURUSD".to_string());
+        assert_that!(msg.decimals).is_equal_to(8);
+        assert_that!(msg.marketing.and_then(|m| m.logo))
+            .is_equal_to(Some(Logo::Url("https://example.com/logo.png".to_string())));
+        Ok(())
+    }
+
+    #[test]
+    fn denom_metadata_is_not_set_on_kujira() {
+        let admin = Addr::unchecked("admin");
+        let metadata = VaultTokenMetadata::default();
+        let denom = |token_factory: TokenFactory| {
+            vault_token_denom_metadata_msg(
+                &admin,
+                "factory/admin/subdenom".to_string(),
+                "subdenom".to_string(),
+                token_factory,
+                &metadata,
+            )
+        };
+
+        assert_that!(denom(TokenFactory::Kujira)).is_equal_to(None);
+        let Some(CosmosMsg::Stargate { type_url, .. }) = denom(TokenFactory::Neutron) else {
+            panic!("expected stargate msg");
+        };
+        assert_that!(type_url)
+            .is_equal_to("/osmosis.tokenfactory.v1beta1.MsgSetDenomMetadata".to_string());
+    }
+
+    #[test]
+    fn denom_metadata_without_decimals_has_no_display_unit() {
+        let units = |decimals: u8| {
+            let metadata = VaultTokenMetadata {
+                symbol: Some("vEURUSD".to_string()),
+                decimals: Some(decimals),
+                ..Default::default()
+            };
+            let Some(CosmosMsg::Stargate { value, .. }) = vault_token_denom_metadata_msg(
+                &Addr::unchecked("admin"),
+                "factory/admin/subdenom".to_string(),
+                "subdenom".to_string(),
+                TokenFactory::Neutron,
+                &metadata,
+            ) else {
+                panic!("expected stargate msg");
+            };
+            // the symbol is encoded once as symbol, and as display unit and display denom
+            value
+                .as_slice()
+                .windows(b"vEURUSD".len())
+                .filter(|window| *window == b"vEURUSD")
+                .count()
+        };
+
+        assert_that!(units(6)).is_equal_to(3);
+        assert_that!(units(0)).is_equal_to(1);
+    }
+
+    #[test]
+    fn vault_token_metadata_is_validated() {
+        let metadata = |name: &str, symbol: &str| VaultTokenMetadata {
+            name: Some(name.to_string()),
+            symbol: Some(symbol.to_string()),
+            ..Default::default()
+        };
+
+        assert_that!(check_vault_token_metadata(&VaultTokenMetadata::default())).is_ok();
+        assert_that!(check_vault_token_metadata(&metadata(
+            "4T2 EUR-USD",
+            "vEUR-USD"
+        )))
+        .is_ok();
+        for invalid in [
+            metadata("4T", "vEURUSD"),
+            metadata(&"4".repeat(51), "vEURUSD"),
+            metadata("4T2 EUR-USD", "vE"),
+            metadata("4T2 EUR-USD", "vEURUSDEURUSD"),
+            metadata("4T2 EUR-USD", "vEUR/USD"),
+            metadata("4T2 EUR-USD", "v4T2"),
+        ] {
+            assert_that!(check_vault_token_metadata(&invalid))
+                .is_err()
+                .matches(|e| matches!(e, AutocompounderError::InvalidVaultTokenMetadata { .. }));
+        }
+    }
+
     #[test]
     fn test_create_lp_token_submsg_without_code_id() {
         let minter = "minter".to_string();
 
-        let result =
-            create_vault_token_submsg(minter, "subdenom".to_string(), None, "kujira".to_string());
+        let result = create_vault_token_submsg(
+            minter,
+            "subdenom".to_string(),
+            None,
+            Some(TokenFactory::Kujira),
+            &VaultTokenMetadata::default(),
+        );
         assert!(result.is_ok());
 
         let submsg = result.unwrap();
@@ -598,30 +1228,62 @@ pub mod helpers_tests {
         assert_that!(submsg.id).is_equal_to(0);
     }
 
+    #[test]
+    fn native_vault_token_requires_known_chain() {
+        let result = create_vault_token_submsg(
+            "cosmos2contract".to_string(),
+            "subdenom".to_string(),
+            None,
+            None,
+            &VaultTokenMetadata::default(),
+        );
+        assert_that!(result)
+            .is_err()
+            .is_equal_to(AutocompounderError::UnsupportedTokenFactory {
+                address: Addr::unchecked("cosmos2contract"),
+            });
+
+        let submsg = create_vault_token_submsg(
+            "inj1contract".to_string(),
+            "subdenom".to_string(),
+            None,
+            None,
+            &VaultTokenMetadata::default(),
+        )
+        .unwrap();
+        let CosmosMsg::Stargate { type_url, .. } = submsg.msg else {
+            panic!("expected stargate msg");
+        };
+        assert_that!(type_url)
+            .is_equal_to("/injective.tokenfactory.v1beta1.MsgCreateDenom".to_string());
+    }
+
     #[test]
     fn test_mint_vault_tokens_msg() {
-        let minter = &Addr::unchecked("minter");
+        let deps = mock_dependencies();
+        let minter = &Addr::unchecked("kujira1minter");
         let recipient = Addr::unchecked("recipient");
         let amount = Uint128::from(100u128);
-        let dex = "kujira".to_string();
 
         // native token
         let config = min_cooldown_config(Some(Duration::Time(1)), true);
-        let result = mint_vault_tokens_msg(&config, minter, recipient.clone(), amount, dex.clone());
+        let result =
+            mint_vault_tokens_msgs(deps.as_ref(), &config, minter, recipient.clone(), amount);
         assert_that!(result.is_ok());
-        let msg = result.unwrap();
+        let mut msgs = result.unwrap();
 
-        let CosmosMsg::Stargate { type_url, value: _ } = msg else {
+        let CosmosMsg::Stargate { type_url, value: _ } = msgs.remove(0) else {
             panic!("Expected a Stargate message");
         };
 
         assert_that!(type_url).is_equal_to("/kujira.denom.MsgMint".to_string());
+        assert_that!(msgs.len()).is_equal_to(0);
 
         // cw20 token
         let config = min_cooldown_config(Some(Duration::Time(1)), false);
-        let result = mint_vault_tokens_msg(&config, minter, recipient, amount, dex);
+        let result = mint_vault_tokens_msgs(deps.as_ref(), &config, minter, recipient, amount);
         assert_that!(result).is_ok();
-        let msg = result.unwrap();
+        let msg = result.unwrap().remove(0);
         let CosmosMsg::Wasm(WasmMsg::Execute {
             contract_addr,
             msg: _,
@@ -633,15 +1295,48 @@ pub mod helpers_tests {
 
         assert_that!(contract_addr).is_equal_to("test_vault_token".to_string());
     }
+
     #[test]
-    fn test_mint_burn_tokens_msg() {
+    fn mint_without_recipient_sends_minted_tokens() {
+        let mut deps = mock_dependencies();
         let minter = &Addr::unchecked("minter");
+        let recipient = Addr::unchecked("recipient");
+        let amount = Uint128::from(100u128);
+        TOKEN_FACTORY
+            .save(deps.as_mut().storage, &TokenFactory::Sei)
+            .unwrap();
+
+        let config = min_cooldown_config(Some(Duration::Time(1)), true);
+        let msgs =
+            mint_vault_tokens_msgs(deps.as_ref(), &config, minter, recipient.clone(), amount)
+                .unwrap();
+
+        let [CosmosMsg::Stargate { type_url, .. }, CosmosMsg::Bank(BankMsg::Send {
+            to_address,
+            amount: sent,
+        })] = &msgs[..]
+        else {
+            panic!("expected a mint and a send msg");
+        };
+        assert_that!(type_url.as_str()).is_equal_to("/seiprotocol.seichain.tokenfactory.MsgMint");
+        assert_that!(to_address).is_equal_to(&recipient.to_string());
+        assert_that!(sent).is_equal_to(&coins(100, TEST_VAULT_TOKEN));
+
+        // minting to the minter itself needs no send
+        let msgs =
+            mint_vault_tokens_msgs(deps.as_ref(), &config, minter, minter.clone(), amount).unwrap();
+        assert_that!(msgs.len()).is_equal_to(1);
+    }
+
+    #[test]
+    fn test_mint_burn_tokens_msg() {
+        let deps = mock_dependencies();
+        let minter = &Addr::unchecked("kujira1minter");
         let amount = Uint128::from(100u128);
 
-        let dex = "kujira".to_string();
         // native token
         let config = min_cooldown_config(Some(Duration::Time(1)), true);
-        let result = burn_vault_tokens_msg(&config, minter, amount, dex.clone());
+        let result = burn_vault_tokens_msg(deps.as_ref(), &config, minter, amount);
         assert_that!(result.is_ok());
         let msg = result.unwrap();
         let CosmosMsg::Stargate { type_url, value: _ } = msg else {
@@ -652,7 +1347,7 @@ pub mod helpers_tests {
 
         // cw20 token
         let config = min_cooldown_config(Some(Duration::Time(1)), false);
-        let result = burn_vault_tokens_msg(&config, minter, amount, dex);
+        let result = burn_vault_tokens_msg(deps.as_ref(), &config, minter, amount);
         assert_that!(result).is_ok();
 
         let msg = result.unwrap();
@@ -680,13 +1375,46 @@ pub mod helpers_tests {
         Ok(())
     }
 
+    #[test]
+    fn creation_fee_is_held_by_the_contract_or_the_proxy() -> AResult {
+        let mut deps = mock_dependencies_with_balance(&coins(100, "ukuji"));
+        deps.querier.update_balance(
+            TEST_PROXY,
+            vec![Coin::new(50, "ukuji"), Coin::new(1, "uosmo")],
+        );
+        let contract = Addr::unchecked(MOCK_CONTRACT_ADDR);
+        let proxy = Addr::unchecked(TEST_PROXY);
+
+        assert_that!(check_creation_fee(deps.as_ref(), &contract, &proxy, &[])?).is_empty();
+        assert_that!(check_creation_fee(
+            deps.as_ref(),
+            &contract,
+            &proxy,
+            &coins(100, "ukuji")
+        )?)
+        .is_empty();
+
+        // the proxy transfers what the contract is missing
+        let fee = vec![Coin::new(150, "ukuji"), Coin::new(1, "uosmo")];
+        assert_that!(check_creation_fee(deps.as_ref(), &contract, &proxy, &fee)?)
+            .is_equal_to(vec![Coin::new(50, "ukuji"), Coin::new(1, "uosmo")]);
+
+        let fee = vec![Coin::new(151, "ukuji")];
+        assert_that!(check_creation_fee(deps.as_ref(), &contract, &proxy, &fee))
+            .is_err()
+            .is_equal_to(AutocompounderError::MissingCreationFee {
+                fee: "151ukuji".to_string(),
+            });
+        Ok(())
+    }
+
     #[test]
     fn test_convert_to_assets() {
         let shares = Uint128::from(100u128);
         let total_assets = Uint128::from(1000u128);
         let total_supply = Uint128::from(500u128);
-        let result = convert_to_assets(shares, total_assets, total_supply);
-        let reverse = convert_to_shares(result, total_assets, total_supply);
+        let result = convert_to_assets(shares, total_assets, total_supply, DECIMAL_OFFSET);
+        let reverse = convert_to_shares(result, total_assets, total_supply, DECIMAL_OFFSET);
         assert_eq!(result, Uint128::from(200u128 - 4u128)); // rounding error leads to -4
         assert_that!(reverse).is_equal_to(Uint128::from(shares.u128() - 1u128));
         // rounding error leads to -1
@@ -697,10 +1425,42 @@ pub mod helpers_tests {
         let assets = Uint128::from(100u128);
         let total_assets = Uint128::from(1000u128);
         let total_supply = Uint128::from(500u128);
-        let result = convert_to_shares(assets, total_assets, total_supply);
+        let result = convert_to_shares(assets, total_assets, total_supply, DECIMAL_OFFSET);
         assert_eq!(result, Uint128::from(50u128));
     }
 
+    #[test]
+    fn decimal_offset_scales_the_first_shares() {
+        let assets = Uint128::from(100u128);
+        let shares = convert_to_shares(assets, Uint128::zero(), Uint128::zero(), 3);
+        assert_that!(shares).is_equal_to(Uint128::from(100_000u128));
+        assert_that!(convert_to_assets(shares, assets, shares, 3)).is_equal_to(assets);
+    }
+
+    #[test]
+    fn lp_token_decimals() -> AResult {
+        let mut deps = mock_dependencies();
+        deps.querier = app_base_mock_querier().build();
+
+        let cw20_lp = AssetInfo::cw20(Addr::unchecked("usd_eur_lp"));
+        assert_that!(query_token_decimals(deps.as_ref(), &cw20_lp)?).is_equal_to(18);
+
+        let pool_share = AssetInfo::native("gamm/pool/1");
+        assert_that!(query_token_decimals(deps.as_ref(), &pool_share)?).is_equal_to(18);
+
+        // the decimals of native denoms without metadata are unknown
+        let native_lp = AssetInfo::native("factory/pool/lp");
+        assert_that!(query_token_decimals(deps.as_ref(), &native_lp)).is_err();
+
+        let deps = mock_deps_with_stargate();
+        assert_that!(query_token_decimals(deps.as_ref(), &native_lp))
+            .is_err()
+            .is_equal_to(AutocompounderError::UnknownTokenDecimals {
+                token: "factory/pool/lp".to_string(),
+            });
+        Ok(())
+    }
+
     mod denom {
         use super::*;
         use abstract_core::objects::PoolMetadata;
@@ -747,25 +1507,29 @@ pub mod helpers_tests {
         #[test]
         fn create_denom_from_pool() {
             let pool = eur_usd_pool();
-            let denom = create_subdenom_from_pool_assets(&pool);
+            let denom = create_subdenom_from_pool_assets(&pool, None);
             assert_eq!(denom, "VT_4T2/wyndex/juno/eur_juno/usd:constant_product");
 
-            // checks whether the denom is truncated to the max length for any dex
+            // checks whether the denom is truncated to the max length for cw20 vault tokens
             let long_pool = verylongasset1_verylongasset2_pool_long_wyndex();
-            let denom = create_subdenom_from_pool_assets(&long_pool);
+            let denom = create_subdenom_from_pool_assets(&long_pool, None);
             assert_eq!(
                 denom,
                 "VT_4T2/wyndex/neutron/verylongasset1_neutron/verylongasset2:cons"
             );
 
+            // checks whether the denom is truncated to the max length for juno (44)
+            let denom = create_subdenom_from_pool_assets(&long_pool, Some(TokenFactory::Juno));
+            assert_eq!(denom, "VT_4T2/wyndex/neutron/verylongasset1_neutron");
+
             // checks whether the denom is truncated to the max length for osmosis (44)
             let long_pool = eur_usd_pool_long_osmosis();
-            let denom = create_subdenom_from_pool_assets(&long_pool);
+            let denom = create_subdenom_from_pool_assets(&long_pool, Some(TokenFactory::Osmosis));
             assert_eq!(denom, "VT_4T2/osmosis/neutron/eur_neutron/usd:const");
 
             // checks whether the denom is truncated to the max length for kujira (64)
             let long_pool = verylongasset1_verylongasset2_pool_long_kujira();
-            let denom = create_subdenom_from_pool_assets(&long_pool);
+            let denom = create_subdenom_from_pool_assets(&long_pool, Some(TokenFactory::Kujira));
             assert_eq!(
                 denom,
                 "VT_4T2/kujira/neutron/verylongasset1_neutron/verylongasset2:cons"
@@ -804,6 +1568,79 @@ pub mod helpers_tests {
         }
     }
 
+    mod liquidity_buffer {
+        use super::*;
+
+        #[test]
+        fn tops_up_buffer_with_new_lp() {
+            // target buffer is 10% of 1_100
+            let (lp_to_stake, buffer) = rebalance_liquidity_buffer(
+                Uint128::new(100),
+                Uint128::new(950),
+                Uint128::new(50),
+                Decimal::percent(10),
+            );
+            assert_that!(lp_to_stake).is_equal_to(Uint128::new(40));
+            assert_that!(buffer).is_equal_to(Uint128::new(110));
+        }
+
+        #[test]
+        fn top_up_is_limited_to_new_lp() {
+            let (lp_to_stake, buffer) = rebalance_liquidity_buffer(
+                Uint128::new(10),
+                Uint128::new(1_000),
+                Uint128::zero(),
+                Decimal::percent(50),
+            );
+            assert_that!(lp_to_stake).is_equal_to(Uint128::zero());
+            assert_that!(buffer).is_equal_to(Uint128::new(10));
+        }
+
+        #[test]
+        fn trims_excess_buffer() {
+            // target buffer is 10% of 1_000
+            let (lp_to_stake, buffer) = rebalance_liquidity_buffer(
+                Uint128::new(100),
+                Uint128::new(600),
+                Uint128::new(300),
+                Decimal::percent(10),
+            );
+            assert_that!(lp_to_stake).is_equal_to(Uint128::new(300));
+            assert_that!(buffer).is_equal_to(Uint128::new(100));
+        }
+    }
+
+    mod deposit_caps {
+        use super::*;
+
+        fn caps() -> DepositCaps {
+            DepositCaps {
+                max_total_lp: Some(Uint128::new(1_000)),
+                max_vault_tokens_per_address: Some(Uint128::new(100)),
+            }
+        }
+
+        #[test]
+        fn deposits_up_to_the_caps_are_allowed() {
+            let res = check_deposit_caps(&caps(), Uint128::new(1_000), Uint128::new(100));
+            assert_that!(res).is_ok();
+            let res = check_deposit_caps(&DepositCaps::default(), Uint128::MAX, Uint128::new(100));
+            assert_that!(res).is_ok();
+        }
+
+        #[test]
+        fn deposits_over_the_caps_are_rejected() {
+            let res = check_deposit_caps(&caps(), Uint128::new(1_001), Uint128::new(100));
+            assert_that!(res).is_err_containing(AutocompounderError::TotalDepositCapExceeded {
+                max_total_lp: Uint128::new(1_000),
+            });
+            let res = check_deposit_caps(&caps(), Uint128::new(1_000), Uint128::new(101));
+            assert_that!(res).is_err_containing(AutocompounderError::AddressDepositCapExceeded {
+                max_vault_tokens: Uint128::new(100),
+            });
+        }
+    }
+
     mod cooldown_tests {
         type AResult = anyhow::Result<()>;
 
@@ -940,4 +1777,50 @@ pub mod helpers_tests {
             .is_err()
             .matches(|e| matches!(e, AutocompounderError::RewardCannotBeSwapped(_)));
     }
+
+    #[test]
+    fn reply_contexts_are_taken_by_nonce() -> AResult {
+        let mut deps = mock_dependencies();
+        let context = |recipient: &str| ReplyContext {
+            recipient: Some(Addr::unchecked(recipient)),
+            ..Default::default()
+        };
+        let reply = |id: u64| Reply {
+            id,
+            result: SubMsgResult::Err("failed".to_string()),
+        };
+
+        let a = save_reply_context(deps.as_mut().storage, context("a"), LP_PROVISION_REPLY_ID)?;
+        let b = save_reply_context(deps.as_mut().storage, context("b"), LP_PROVISION_REPLY_ID)?;
+        assert_that!(a & REPLY_ID_MASK).is_equal_to(LP_PROVISION_REPLY_ID);
+        assert_that!(a).is_not_equal_to(b);
+
+        // replies are matched to their context regardless of the order they are handled in
+        assert_that!(take_reply_context(deps.as_mut().storage, &reply(b))?.recipient)
+            .is_equal_to(Some(Addr::unchecked("b")));
+        assert_that!(take_reply_context(deps.as_mut().storage, &reply(a))?.recipient)
+            .is_equal_to(Some(Addr::unchecked("a")));
+        assert_that!(take_reply_context(deps.as_mut().storage, &reply(a))).is_err();
+        assert_that!(take_reply_context(
+            deps.as_mut().storage,
+            &reply(LP_PROVISION_REPLY_ID)
+        ))
+        .is_err();
+        Ok(())
+    }
+
+    #[test]
+    fn reward_token_sets_are_merged() {
+        let wynd = AssetInfo::cw20(Addr::unchecked(WYND_TOKEN));
+        let juno = AssetInfo::native("ujuno");
+        let incentive = AssetInfo::native("incentive");
+
+        let merged = merge_reward_tokens(vec![
+            vec![wynd.clone(), juno.clone()],
+            vec![],
+            vec![juno.clone(), incentive.clone()],
+        ]);
+        assert_that!(merged).is_equal_to(vec![wynd, juno, incentive]);
+        assert!(merge_reward_tokens(vec![]).is_empty());
+    }
 }
@@ -1,9 +1,13 @@
+use crate::claims_nft::create_claims_nft_submsg;
 use crate::contract::{AutocompounderApp, AutocompounderResult};
 use crate::error::AutocompounderError;
-use crate::handlers::helpers::check_fee;
-use crate::kujira_tx::format_tokenfactory_denom;
+use crate::handlers::helpers::{check_creation_fee, check_fee, check_vault_token_metadata};
+use crate::kujira_tx::{format_tokenfactory_denom, TokenFactory};
 use crate::msg::{AutocompounderInstantiateMsg, FeeConfig, AUTOCOMPOUNDER};
-use crate::state::{Config, CONFIG, DEFAULT_MAX_SPREAD, FEE_CONFIG};
+use crate::state::{
+    Config, CONFIG, DECIMAL_OFFSET, DEFAULT_MAX_SPREAD, FEE_CONFIG, MAX_DECIMAL_OFFSET,
+    TOKEN_FACTORY, VAULT_DECIMAL_OFFSET,
+};
 use abstract_core::objects::{AnsEntryConvertor, AssetEntry};
 use abstract_cw_staking::msg::{StakingInfoResponse, StakingQueryMsg};
 use abstract_cw_staking::CW_STAKING_ADAPTER_ID;
@@ -11,12 +15,16 @@ use abstract_sdk::{
     core::objects::{LpToken, PoolReference},
     features::AbstractNameService,
 };
-use abstract_sdk::{AbstractResponse, AdapterInterface};
+use abstract_sdk::{
+    features::AccountIdentification, AbstractResponse, AdapterInterface, Execution,
+    TransferInterface,
+};
 use cosmwasm_std::{Addr, Decimal, DepsMut, Env, MessageInfo};
-use cw_asset::AssetInfo;
+use cw_asset::{Asset, AssetInfo};
 
 use super::helpers::{
     create_subdenom_from_pool_assets, create_vault_token_submsg, get_unbonding_period_and_cooldown,
+    query_token_decimals, vault_token_denom_metadata_msg,
 };
 
 /// Initial instantiation of the contract
@@ -40,16 +48,35 @@ pub fn instantiate_handler(
         pool_assets,
         bonding_data: manual_bonding_data,
         max_swap_spread,
+        claims_nft_code_id,
+        vault_token_metadata,
+        token_factory,
+        decimal_offset,
     } = msg;
+    let mut vault_token_metadata = vault_token_metadata.unwrap_or_default();
 
     check_fee(performance_fees)?;
     check_fee(deposit_fees)?;
     check_fee(withdrawal_fees)?;
+    check_vault_token_metadata(&vault_token_metadata)?;
 
     if pool_assets.len() > 2 {
         return Err(AutocompounderError::PoolWithMoreThanTwoAssets {});
     }
 
+    let decimal_offset = decimal_offset.unwrap_or(DECIMAL_OFFSET);
+    if decimal_offset > MAX_DECIMAL_OFFSET {
+        return Err(AutocompounderError::InvalidDecimalOffset {
+            max: MAX_DECIMAL_OFFSET,
+        });
+    }
+    VAULT_DECIMAL_OFFSET.save(deps.storage, &decimal_offset)?;
+
+    // claims only exist for vaults with an unbonding period
+    if claims_nft_code_id.is_some() && manual_bonding_data.is_none() {
+        return Err(AutocompounderError::UnbondingNotEnabled {});
+    }
+
     let lp_token = LpToken::new(dex.clone(), pool_assets.clone());
     let lp_asset: AssetEntry = AnsEntryConvertor::new(lp_token.clone()).asset_entry();
     let pairing = AnsEntryConvertor::new(lp_token.clone()).dex_asset_pairing()?;
@@ -76,13 +103,47 @@ pub fn instantiate_handler(
 
     let resolved_pool_assets = ans.query(&pool_data.assets)?;
 
+    // the vault token has the decimals of the lp token, unless they are set explicitly
+    if vault_token_metadata.decimals.is_none() {
+        vault_token_metadata.decimals = Some(query_token_decimals(
+            deps.as_ref(),
+            &staking_info.infos[0].staking_token,
+        )?);
+    }
+
     // default max swap spread
     let max_swap_spread =
         max_swap_spread.unwrap_or_else(|| Decimal::percent(DEFAULT_MAX_SPREAD.into()));
 
     // vault_token will be overwritten in the instantiate reply if we are using a cw20
 
-    let subdenom = create_subdenom_from_pool_assets(&pool_data);
+    let mut creation_fee_msg = None;
+    let token_factory = if code_id.is_some() {
+        None
+    } else {
+        let token_factory = match token_factory {
+            Some(token_factory) => token_factory,
+            None => TokenFactory::from_address(&env.contract.address)?,
+        };
+        TOKEN_FACTORY.save(deps.storage, &token_factory)?;
+        let creation_fee = token_factory.query_creation_fee(deps.as_ref())?;
+        let from_proxy = check_creation_fee(
+            deps.as_ref(),
+            &env.contract.address,
+            &app.proxy_address(deps.as_ref())?,
+            &creation_fee,
+        )?;
+        // the proxy sends the rest of the fee to the contract before the denom is created
+        if !from_proxy.is_empty() {
+            let transfer = app.bank(deps.as_ref()).transfer(
+                from_proxy.into_iter().map(Asset::from).collect(),
+                &env.contract.address,
+            )?;
+            creation_fee_msg = Some(app.executor(deps.as_ref()).execute(vec![transfer])?);
+        }
+        Some(token_factory)
+    };
+    let subdenom = create_subdenom_from_pool_assets(&pool_data, token_factory);
     let vault_token = if code_id.is_some() {
         AssetInfo::cw20(Addr::unchecked(""))
     } else {
@@ -114,17 +175,44 @@ pub fn instantiate_handler(
 
     FEE_CONFIG.save(deps.storage, &fee_config)?;
 
+    // create the claims NFT SubMsg if claims are tokenised
+    let claims_nft_sub_msg = claims_nft_code_id
+        .map(|code_id| {
+            create_claims_nft_submsg(
+                env.contract.address.to_string(),
+                format!("{subdenom} claims"),
+                code_id,
+            )
+        })
+        .transpose()?;
+
+    // set the denom metadata of tokenfactory vault tokens, so wallets and explorers can show them
+    let denom_metadata_msg = match (&config.vault_token, token_factory) {
+        (AssetInfo::Native(denom), Some(token_factory)) => vault_token_denom_metadata_msg(
+            &env.contract.address,
+            denom.clone(),
+            subdenom.clone(),
+            token_factory,
+            &vault_token_metadata,
+        ),
+        _ => None,
+    };
+
     // create LP token SubMsg
     let sub_msg = create_vault_token_submsg(
         env.contract.address.to_string(),
         subdenom,
         code_id, // if code_id is none, submsg will be like normal msg: no reply (for now).
-        config.pool_data.dex,
+        token_factory,
+        &vault_token_metadata,
     )?;
 
     Ok(app
         .response("instantiate")
+        .add_messages(creation_fee_msg)
         .add_submessage(sub_msg)
+        .add_messages(denom_metadata_msg)
+        .add_submessages(claims_nft_sub_msg)
         .add_attribute("action", "instantiate")
         .add_attribute("contract", AUTOCOMPOUNDER))
 }
@@ -174,11 +262,13 @@ mod test {
         // test native token factory asset
         let deps = app_init(false, false);
         let config = CONFIG.load(deps.as_ref().storage).unwrap();
-        let expected_subdenom = create_subdenom_from_pool_assets(&config.pool_data);
+        let expected_subdenom =
+            create_subdenom_from_pool_assets(&config.pool_data, Some(TokenFactory::Juno));
         assert_that!(config.vault_token).is_equal_to(AssetInfo::Native(format_tokenfactory_denom(
             "cosmos2contract",
             &expected_subdenom,
         )));
+        assert_that!(TOKEN_FACTORY.load(deps.as_ref().storage)?).is_equal_to(TokenFactory::Juno);
         Ok(())
     }
 
@@ -204,6 +294,10 @@ mod test {
                     withdrawal_fees: Decimal::percent(3),
                     bonding_data: None,
                     max_swap_spread: None,
+                    claims_nft_code_id: None,
+                    vault_token_metadata: None,
+                    token_factory: None,
+                    decimal_offset: None,
                 },
                 base: abstract_core::app::BaseInstantiateMsg {
                     version_control_address: TEST_VERSION_CONTROL.to_string(),
@@ -222,6 +316,113 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn claims_nft_requires_unbonding_period() -> anyhow::Result<()> {
+        let mut deps = mock_dependencies();
+        let info = mock_info(TEST_MODULE_FACTORY, &[]);
+
+        deps.querier = app_base_mock_querier().build();
+
+        let resp = AUTOCOMPOUNDER_APP.instantiate(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            abstract_core::app::InstantiateMsg {
+                module: crate::msg::AutocompounderInstantiateMsg {
+                    code_id: Some(1),
+                    commission_addr: COMMISSION_RECEIVER.to_string(),
+                    deposit_fees: Decimal::percent(3),
+                    dex: ASTROPORT.to_string(),
+                    performance_fees: Decimal::percent(3),
+                    pool_assets: vec!["eur".into(), "usd".into()],
+                    withdrawal_fees: Decimal::percent(3),
+                    bonding_data: None,
+                    max_swap_spread: None,
+                    claims_nft_code_id: Some(2),
+                    vault_token_metadata: None,
+                    token_factory: None,
+                    decimal_offset: None,
+                },
+                base: abstract_core::app::BaseInstantiateMsg {
+                    version_control_address: TEST_VERSION_CONTROL.to_string(),
+                    ans_host_address: TEST_ANS_HOST.to_string(),
+                    account_base: AccountBase {
+                        manager: Addr::unchecked(TEST_MANAGER),
+                        proxy: Addr::unchecked(TEST_PROXY),
+                    },
+                },
+            },
+        );
+
+        assert_that!(resp)
+            .is_err()
+            .matches(|e| matches!(e, AutocompounderError::UnbondingNotEnabled {}));
+        Ok(())
+    }
+
+    fn instantiate_cw20_vault(
+        decimal_offset: Option<u32>,
+    ) -> Result<cosmwasm_std::Response, AutocompounderError> {
+        let mut deps = mock_dependencies();
+        deps.querier = app_base_mock_querier().build();
+
+        AUTOCOMPOUNDER_APP.instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(TEST_MODULE_FACTORY, &[]),
+            abstract_core::app::InstantiateMsg {
+                module: crate::msg::AutocompounderInstantiateMsg {
+                    code_id: Some(1),
+                    commission_addr: COMMISSION_RECEIVER.to_string(),
+                    deposit_fees: Decimal::percent(3),
+                    dex: "wyndex".to_string(),
+                    performance_fees: Decimal::percent(3),
+                    pool_assets: vec!["eur".into(), "usd".into()],
+                    withdrawal_fees: Decimal::percent(3),
+                    bonding_data: None,
+                    max_swap_spread: None,
+                    claims_nft_code_id: None,
+                    vault_token_metadata: None,
+                    token_factory: None,
+                    decimal_offset,
+                },
+                base: abstract_core::app::BaseInstantiateMsg {
+                    version_control_address: TEST_VERSION_CONTROL.to_string(),
+                    ans_host_address: TEST_ANS_HOST.to_string(),
+                    account_base: AccountBase {
+                        manager: Addr::unchecked(TEST_MANAGER),
+                        proxy: Addr::unchecked(TEST_PROXY),
+                    },
+                },
+            },
+        )
+    }
+
+    #[test]
+    fn vault_token_has_lp_token_decimals() -> anyhow::Result<()> {
+        let resp = instantiate_cw20_vault(None)?;
+
+        let cosmwasm_std::CosmosMsg::Wasm(cosmwasm_std::WasmMsg::Instantiate { msg, .. }) =
+            &resp.messages[0].msg
+        else {
+            panic!("expected the vault token instantiation");
+        };
+        let msg: TokenInstantiateMsg = cosmwasm_std::from_json(msg)?;
+        // the lp token of the mock has 18 decimals
+        assert_that!(msg.decimals).is_equal_to(18);
+        Ok(())
+    }
+
+    #[test]
+    fn decimal_offset_is_bounded() {
+        assert_that!(instantiate_cw20_vault(Some(MAX_DECIMAL_OFFSET + 1)))
+            .is_err()
+            .is_equal_to(AutocompounderError::InvalidDecimalOffset {
+                max: MAX_DECIMAL_OFFSET,
+            });
+        assert_that!(instantiate_cw20_vault(Some(MAX_DECIMAL_OFFSET))).is_ok();
+    }
+
     #[test]
     fn test_cw_20_init() {
         let pairing = DexAssetPairing::new(
@@ -245,4 +446,72 @@ mod test {
 
         msg.validate().unwrap();
     }
+
+    mod creation_fee {
+        use super::*;
+        use crate::test_common::{
+            instantiate_with_creation_fee, test_instantiate_msg, TEST_CREATION_FEE_DENOM,
+        };
+        use cosmwasm_std::{coins, testing::MOCK_CONTRACT_ADDR, Coin, CosmosMsg, WasmMsg};
+
+        fn fee() -> Vec<Coin> {
+            coins(100, TEST_CREATION_FEE_DENOM)
+        }
+
+        fn create_denom_type_url(msg: &CosmosMsg) -> &str {
+            let CosmosMsg::Stargate { type_url, .. } = msg else {
+                panic!("expected stargate msg");
+            };
+            type_url
+        }
+
+        #[test]
+        fn is_paid_by_the_instantiator() -> anyhow::Result<()> {
+            let mut querier = app_base_mock_querier().build();
+            querier.update_balance(MOCK_CONTRACT_ADDR, fee());
+
+            let (_, resp) =
+                instantiate_with_creation_fee(querier, test_instantiate_msg(None, false), fee())?;
+            assert_that!(create_denom_type_url(&resp.messages[0].msg))
+                .is_equal_to("/juno.tokenfactory.v1beta1.MsgCreateDenom");
+            Ok(())
+        }
+
+        #[test]
+        fn is_transferred_from_the_proxy() -> anyhow::Result<()> {
+            let mut querier = app_base_mock_querier().build();
+            querier.update_balance(TEST_PROXY, fee());
+
+            let (_, resp) =
+                instantiate_with_creation_fee(querier, test_instantiate_msg(None, false), fee())?;
+            // the proxy sends the fee to the module before the denom is created
+            assert_that!(resp.messages[0].msg).matches(|msg| {
+                matches!(msg, CosmosMsg::Wasm(WasmMsg::Execute { contract_addr, .. }) if contract_addr == TEST_PROXY)
+            });
+            assert_that!(create_denom_type_url(&resp.messages[1].msg))
+                .is_equal_to("/juno.tokenfactory.v1beta1.MsgCreateDenom");
+            Ok(())
+        }
+
+        #[test]
+        fn is_required() {
+            let querier = app_base_mock_querier().build();
+
+            let resp =
+                instantiate_with_creation_fee(querier, test_instantiate_msg(None, false), fee())
+                    .map(|(_, resp)| resp);
+            assert_that!(resp)
+                .is_err()
+                .is_equal_to(AutocompounderError::MissingCreationFee {
+                    fee: format!("100{TEST_CREATION_FEE_DENOM}"),
+                });
+        }
+
+        #[test]
+        fn is_not_charged_for_cw20_vault_tokens() -> anyhow::Result<()> {
+            let querier = app_base_mock_querier().build();
+            instantiate_with_creation_fee(querier, test_instantiate_msg(None, true), fee())?;
+            Ok(())
+        }
+    }
 }
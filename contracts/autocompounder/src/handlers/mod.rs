@@ -6,7 +6,7 @@ pub mod query;
 pub mod reply;
 
 pub use crate::handlers::{
-    execute::execute_handler, execute::receive, helpers::convert_to_assets,
+    execute::execute_handler, execute::receive, execute::receive_nft, helpers::convert_to_assets,
     helpers::convert_to_shares, helpers::swap_rewards, instantiate::instantiate_handler,
     migrate::migrate_handler, query::query_handler, reply::*,
 };
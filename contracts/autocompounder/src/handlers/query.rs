@@ -1,19 +1,35 @@
+use crate::claims_nft::query_all_claim_nfts_of;
 use crate::contract::{AutocompounderApp, AutocompounderResult};
 use crate::error::AutocompounderError;
 use crate::state::{
-    Claim, Config, FeeConfig, CLAIMS, CONFIG, FEE_CONFIG, LATEST_UNBONDING, PENDING_CLAIMS,
+    Claim, Config, EmergencyShutdownState, FeeConfig, ACCESS_MODE, CLAIMS, CLAIMS_NFT,
+    COMPOUND_ON_DEPOSIT, CONFIG, DEPOSITORS, DEPOSIT_CAPS, EMERGENCY_SHUTDOWN, FEE_CONFIG,
+    GUARDIAN, LATEST_UNBONDING, LIQUIDITY_BUFFER, LIQUIDITY_BUFFER_CONFIG, NFT_CLAIMS, OPERATORS,
+    PAUSE_STATE, PENDING_CLAIMS, REWARD_FILTER,
 };
+use abstract_core::objects::ans_host::AnsHostError;
+use abstract_core::objects::pool_id::PoolAddressBase;
+use abstract_core::objects::AnsAsset;
+use abstract_dex_adapter::api::DexInterface;
+use abstract_sdk::features::AbstractNameService;
 use abstract_sdk::features::AccountIdentification;
-use abstract_sdk::AdapterInterface;
-use cosmwasm_std::{to_json_binary, Addr, Binary, Deps, Env, Order, StdResult, Uint128};
+use abstract_sdk::{AdapterInterface, Resolve};
+use cosmwasm_std::{to_json_binary, Addr, Binary, Decimal, Deps, Env, Order, StdResult, Uint128};
 
-use crate::msg::AutocompounderQueryMsg;
+use crate::msg::{
+    AutocompounderQueryMsg, LiquidityBufferResponse, PauseResponse, PreviewCompoundResponse,
+};
 use abstract_cw_staking::{msg::StakingQueryMsg, CW_STAKING_ADAPTER_ID};
+use cw_asset::{Asset, AssetInfo};
 use cw_storage_plus::Bound;
 use cw_utils::Expiration;
 
 use super::convert_to_assets;
-use super::helpers::{vault_token_balance, vault_token_total_supply};
+use super::helpers::{
+    decimal_offset, query_pending_rewards, query_reward_tokens, query_token_supply, reward_swaps,
+    vault_token_balance, vault_token_total_supply,
+};
+use super::reply::{deduct_fees_from_rewards, filter_staking_rewards, StakingRewards};
 
 const DEFAULT_PAGE_SIZE: u8 = 5;
 const MAX_PAGE_SIZE: u8 = 20;
@@ -21,7 +37,7 @@ const MAX_PAGE_SIZE: u8 = 20;
 /// Handle queries sent to this app.
 pub fn query_handler(
     deps: Deps,
-    _env: Env,
+    env: Env,
     app: &AutocompounderApp,
     msg: AutocompounderQueryMsg,
 ) -> AutocompounderResult<Binary> {
@@ -49,11 +65,116 @@ pub fn query_handler(
         AutocompounderQueryMsg::Balance { address } => {
             Ok(to_json_binary(&query_balance(deps, address)?)?)
         }
-        AutocompounderQueryMsg::TotalSupply {} => Ok(to_json_binary(&query_total_supply(deps)?)?),
+        AutocompounderQueryMsg::TotalSupply {} => {
+            Ok(to_json_binary(&query_total_supply(deps, &env)?)?)
+        }
         AutocompounderQueryMsg::AssetsPerShares { shares } => Ok(to_json_binary(
-            &query_assets_per_shares(app, deps, shares)?,
+            &query_assets_per_shares(app, deps, &env, shares)?,
+        )?),
+        AutocompounderQueryMsg::ClaimsNft {} => Ok(to_json_binary(&query_claims_nft(deps)?)?),
+        AutocompounderQueryMsg::NftClaim { token_id } => {
+            Ok(to_json_binary(&query_nft_claim(deps, token_id)?)?)
+        }
+        AutocompounderQueryMsg::LiquidityBuffer {} => {
+            Ok(to_json_binary(&query_liquidity_buffer(deps)?)?)
+        }
+        AutocompounderQueryMsg::EmergencyShutdown {} => {
+            Ok(to_json_binary(&query_emergency_shutdown(deps)?)?)
+        }
+        AutocompounderQueryMsg::Pause {} => Ok(to_json_binary(&query_pause(deps)?)?),
+        AutocompounderQueryMsg::DepositCaps {} => Ok(to_json_binary(
+            &DEPOSIT_CAPS.may_load(deps.storage)?.unwrap_or_default(),
+        )?),
+        AutocompounderQueryMsg::MaxDeposit { address } => Ok(to_json_binary(&query_max_deposit(
+            app, deps, &env, address,
+        )?)?),
+        AutocompounderQueryMsg::AccessMode {} => Ok(to_json_binary(
+            &ACCESS_MODE.may_load(deps.storage)?.unwrap_or_default(),
+        )?),
+        AutocompounderQueryMsg::Depositors { start_after, limit } => Ok(to_json_binary(
+            &query_depositors(deps, start_after, limit)?,
+        )?),
+        AutocompounderQueryMsg::Operator { owner, operator } => Ok(to_json_binary(
+            &OPERATORS.may_load(deps.storage, (owner, operator))?,
+        )?),
+        AutocompounderQueryMsg::CompoundOnDeposit {} => Ok(to_json_binary(
+            &COMPOUND_ON_DEPOSIT
+                .may_load(deps.storage)?
+                .unwrap_or_default(),
+        )?),
+        AutocompounderQueryMsg::SweepableBalances {} => {
+            Ok(to_json_binary(&query_sweepable_balances(app, deps)?)?)
+        }
+        AutocompounderQueryMsg::RewardFilter {} => Ok(to_json_binary(
+            &REWARD_FILTER.may_load(deps.storage)?.unwrap_or_default(),
         )?),
+        AutocompounderQueryMsg::PreviewCompound {} => {
+            Ok(to_json_binary(&query_preview_compound(app, deps, &env)?)?)
+        }
+        AutocompounderQueryMsg::RewardTokens {} => {
+            Ok(to_json_binary(&query_compounded_reward_tokens(app, deps)?)?)
+        }
+    }
+}
+
+/// Reward tokens that are compounded, being the core assets and the rewards allowed by the reward filter that are registered in ANS
+pub fn query_compounded_reward_tokens(
+    app: &AutocompounderApp,
+    deps: Deps,
+) -> AutocompounderResult<Vec<AssetInfo>> {
+    let config = CONFIG.load(deps.storage)?;
+    let ans_host = app.ans_host(deps)?;
+    let reward_filter = REWARD_FILTER.may_load(deps.storage)?.unwrap_or_default();
+    let mut reward_tokens = vec![];
+    for token in query_reward_tokens(deps, app, &config)? {
+        if !config.is_core_asset(&token) && !reward_filter.is_allowed(&token) {
+            continue;
+        }
+        match token.resolve(&deps.querier, &ans_host) {
+            Ok(_) => reward_tokens.push(token),
+            Err(AnsHostError::CwAssetNotFound { .. }) => {}
+            Err(err) => return Err(err.into()),
+        }
     }
+    Ok(reward_tokens)
+}
+
+/// Balances of the proxy that are not core assets of the vault.
+/// Cw20 balances can't be enumerated, so only the cw20 reward tokens and the cw20 tokens of the reward filter are listed.
+pub fn query_sweepable_balances(
+    app: &AutocompounderApp,
+    deps: Deps,
+) -> AutocompounderResult<Vec<Asset>> {
+    let config = CONFIG.load(deps.storage)?;
+    let proxy = app.proxy_address(deps)?;
+    let mut balances: Vec<Asset> = deps
+        .querier
+        .query_all_balances(&proxy)?
+        .into_iter()
+        .map(Asset::from)
+        .collect();
+
+    // the cw20 tokens that the vault knows about
+    let reward_filter = REWARD_FILTER.may_load(deps.storage)?.unwrap_or_default();
+    let mut cw20_tokens: Vec<AssetInfo> = vec![];
+    for token in query_reward_tokens(deps, app, &config)?
+        .into_iter()
+        .chain(reward_filter.allowlist)
+        .chain(reward_filter.denylist)
+    {
+        if matches!(token, AssetInfo::Cw20(_)) && !cw20_tokens.contains(&token) {
+            cw20_tokens.push(token);
+        }
+    }
+    for token in cw20_tokens {
+        let balance = token.query_balance(&deps.querier, proxy.to_string())?;
+        balances.push(Asset::new(token, balance));
+    }
+
+    Ok(balances
+        .into_iter()
+        .filter(|asset| !asset.amount.is_zero() && !config.is_core_asset(&asset.info))
+        .collect())
 }
 
 /// Returns the current configuration.
@@ -103,7 +224,31 @@ pub fn query_all_pending_claims(
     Ok(claims)
 }
 
+pub fn query_depositors(
+    deps: Deps,
+    start_after: Option<Addr>,
+    limit: Option<u8>,
+) -> AutocompounderResult<Vec<Addr>> {
+    let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE).min(MAX_PAGE_SIZE) as usize;
+    let start = start_after.map(Bound::exclusive);
+    let depositors = DEPOSITORS
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<Addr>>>()?;
+
+    Ok(depositors)
+}
+
 pub fn query_claims(deps: Deps, address: Addr) -> AutocompounderResult<Vec<Claim>> {
+    // the claims are held as nfts when the vault tokenises them
+    if let Some(claims_nft) = CLAIMS_NFT.may_load(deps.storage)? {
+        let claims = query_all_claim_nfts_of(deps, &claims_nft, &address)?
+            .into_iter()
+            .map(|token_id| NFT_CLAIMS.load(deps.storage, token_id))
+            .collect::<StdResult<Vec<Claim>>>()?;
+        return Ok(claims);
+    }
+
     let claims = CLAIMS.may_load(deps.storage, address)?.unwrap_or_default();
     Ok(claims)
 }
@@ -131,6 +276,16 @@ pub fn query_all_claims(
     Ok(claims)
 }
 
+pub fn query_claims_nft(deps: Deps) -> AutocompounderResult<Option<Addr>> {
+    let claims_nft = CLAIMS_NFT.may_load(deps.storage)?;
+    Ok(claims_nft)
+}
+
+pub fn query_nft_claim(deps: Deps, token_id: String) -> AutocompounderResult<Claim> {
+    let claim = NFT_CLAIMS.load(deps.storage, token_id)?;
+    Ok(claim)
+}
+
 pub fn query_latest_unbonding(deps: Deps) -> AutocompounderResult<Expiration> {
     let latest_unbonding = LATEST_UNBONDING.load(deps.storage)?;
     Ok(latest_unbonding)
@@ -156,7 +311,58 @@ pub fn query_total_lp_position(
     let amount = res.amounts.first().ok_or(AutocompounderError::Std(
         cosmwasm_std::StdError::generic_err("No amount found"),
     ))?;
-    Ok(*amount)
+    let buffer = LIQUIDITY_BUFFER.may_load(deps.storage)?.unwrap_or_default();
+    Ok(*amount + buffer)
+}
+
+pub fn query_liquidity_buffer(deps: Deps) -> AutocompounderResult<LiquidityBufferResponse> {
+    Ok(LiquidityBufferResponse {
+        config: LIQUIDITY_BUFFER_CONFIG.may_load(deps.storage)?,
+        buffer: LIQUIDITY_BUFFER.may_load(deps.storage)?.unwrap_or_default(),
+    })
+}
+
+pub fn query_emergency_shutdown(
+    deps: Deps,
+) -> AutocompounderResult<Option<EmergencyShutdownState>> {
+    Ok(EMERGENCY_SHUTDOWN.may_load(deps.storage)?)
+}
+
+pub fn query_pause(deps: Deps) -> AutocompounderResult<PauseResponse> {
+    Ok(PauseResponse {
+        guardian: GUARDIAN.may_load(deps.storage)?,
+        paused: PAUSE_STATE.may_load(deps.storage)?.unwrap_or_default(),
+    })
+}
+
+/// Returns the lp tokens that `address` can deposit before reaching a deposit cap
+pub fn query_max_deposit(
+    app: &AutocompounderApp,
+    deps: Deps,
+    env: &Env,
+    address: Addr,
+) -> AutocompounderResult<Uint128> {
+    let Some(caps) = DEPOSIT_CAPS.may_load(deps.storage)? else {
+        return Ok(Uint128::MAX);
+    };
+    let total_lp = query_total_lp_position(app, deps)?;
+
+    let mut max_deposit = caps.max_total_lp.map_or(Uint128::MAX, |max_total_lp| {
+        max_total_lp.saturating_sub(total_lp)
+    });
+    if let Some(max_vault_tokens) = caps.max_vault_tokens_per_address {
+        let config = CONFIG.load(deps.storage)?;
+        let balance = vault_token_balance(deps, &config, address)?;
+        let total_supply = vault_token_total_supply(deps, &env.contract.address, &config)?;
+        let remaining_lp = convert_to_assets(
+            max_vault_tokens.saturating_sub(balance),
+            total_lp,
+            total_supply,
+            decimal_offset(deps.storage)?,
+        );
+        max_deposit = max_deposit.min(remaining_lp);
+    }
+    Ok(max_deposit)
 }
 
 pub fn query_balance(deps: Deps, address: Addr) -> AutocompounderResult<Uint128> {
@@ -164,14 +370,15 @@ pub fn query_balance(deps: Deps, address: Addr) -> AutocompounderResult<Uint128>
     vault_token_balance(deps, &config, address)
 }
 
-pub fn query_total_supply(deps: Deps) -> AutocompounderResult<Uint128> {
+pub fn query_total_supply(deps: Deps, env: &Env) -> AutocompounderResult<Uint128> {
     let config = CONFIG.load(deps.storage)?;
-    vault_token_total_supply(deps, &config)
+    vault_token_total_supply(deps, &env.contract.address, &config)
 }
 
 pub fn query_assets_per_shares(
     app: &AutocompounderApp,
     deps: Deps,
+    env: &Env,
     shares: Option<Uint128>,
 ) -> AutocompounderResult<Uint128> {
     let shares = if let Some(shares) = shares {
@@ -181,12 +388,131 @@ pub fn query_assets_per_shares(
     };
 
     let total_lp_position = query_total_lp_position(app, deps)?;
-    let total_supply = query_total_supply(deps)?;
-    let assets = convert_to_assets(shares, total_lp_position, total_supply);
+    let total_supply = query_total_supply(deps, env)?;
+    let assets = convert_to_assets(
+        shares,
+        total_lp_position,
+        total_supply,
+        decimal_offset(deps.storage)?,
+    );
 
     Ok(assets)
 }
 
+/// Previews a compound of the rewards that the proxy can claim from the staking contract.
+/// Balances already held by the proxy are not compounded and not included.
+pub fn query_preview_compound(
+    app: &AutocompounderApp,
+    deps: Deps,
+    env: &Env,
+) -> AutocompounderResult<PreviewCompoundResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let fee_config = FEE_CONFIG.load(deps.storage)?;
+    let pending_rewards = query_pending_rewards(deps, app, &config)?;
+
+    let StakingRewards {
+        compound: mut rewards,
+        denied,
+    } = filter_staking_rewards(deps, app, &config, pending_rewards)?;
+    let performance_fees = deduct_fees_from_rewards(&mut rewards, fee_config.performance);
+
+    let dex = app.ans_dex(deps, config.pool_data.dex.clone());
+    let swap_outputs = reward_swaps(app, deps, rewards.clone())?
+        .into_iter()
+        .map(|(reward, target_asset)| -> AutocompounderResult<AnsAsset> {
+            let simulation = dex.simulate_swap(reward, target_asset.clone())?;
+            Ok(AnsAsset::new(target_asset, simulation.return_amount))
+        })
+        .collect::<AutocompounderResult<Vec<AnsAsset>>>()?;
+
+    // the pool asset rewards and the swapped rewards are provided as liquidity
+    let provided_assets = config
+        .pool_data
+        .assets
+        .iter()
+        .map(|entry| {
+            let amount = rewards
+                .iter()
+                .chain(&swap_outputs)
+                .filter(|asset| asset.name == *entry)
+                .map(|asset| asset.amount)
+                .sum::<Uint128>();
+            AnsAsset::new(entry.clone(), amount)
+        })
+        .collect::<Vec<AnsAsset>>();
+    let expected_lp = expected_lp_minted(app, deps, &config, &provided_assets)?;
+
+    let total_lp_position = query_total_lp_position(app, deps)?;
+    let total_supply = query_total_supply(deps, env)?;
+    let decimal_offset = decimal_offset(deps.storage)?;
+
+    Ok(PreviewCompoundResponse {
+        rewards,
+        performance_fees,
+        denied_rewards: denied,
+        swap_outputs,
+        provided_assets,
+        expected_lp,
+        assets_per_share: assets_per_share(total_lp_position, total_supply, decimal_offset),
+        expected_assets_per_share: expected_lp
+            .map(|lp| assets_per_share(total_lp_position + lp, total_supply, decimal_offset)),
+    })
+}
+
+/// Estimates the lp tokens minted for `assets` from the reserves of the pool, ignoring swap fees and slippage.
+/// Returns None for pools without a contract holding the reserves.
+fn expected_lp_minted(
+    app: &AutocompounderApp,
+    deps: Deps,
+    config: &Config,
+    assets: &[AnsAsset],
+) -> AutocompounderResult<Option<Uint128>> {
+    let pool = match &config.pool_address {
+        PoolAddressBase::Contract(pool) => pool,
+        PoolAddressBase::SeparateAddresses { liquidity, .. } => liquidity,
+        PoolAddressBase::Id(_) => return Ok(None),
+    };
+    let ans_host = app.ans_host(deps)?;
+    let provided_and_reserves = assets
+        .iter()
+        .map(|asset| -> AutocompounderResult<(Uint128, Uint128)> {
+            let info = asset.name.resolve(&deps.querier, &ans_host)?;
+            Ok((asset.amount, info.query_balance(&deps.querier, pool)?))
+        })
+        .collect::<AutocompounderResult<Vec<_>>>()?;
+    let lp_supply = query_token_supply(deps, &config.liquidity_token)?;
+
+    Ok(estimate_lp_minted(&provided_and_reserves, lp_supply))
+}
+
+/// Lp tokens minted for the provided amounts, valuing every asset at the pool price.
+/// The average provided share of the reserves is minted, so an unbalanced provide is valued as if it was swapped without slippage.
+fn estimate_lp_minted(
+    provided_and_reserves: &[(Uint128, Uint128)],
+    lp_supply: Uint128,
+) -> Option<Uint128> {
+    if provided_and_reserves.is_empty()
+        || provided_and_reserves
+            .iter()
+            .any(|(_, reserve)| reserve.is_zero())
+    {
+        return None;
+    }
+    let minted = provided_and_reserves
+        .iter()
+        .map(|(provided, reserve)| lp_supply.multiply_ratio(*provided, *reserve))
+        .sum::<Uint128>();
+    Some(minted / Uint128::from(provided_and_reserves.len() as u128))
+}
+
+/// Lp tokens per vault token, with the virtual shares of [`convert_to_assets`]
+fn assets_per_share(total_lp: Uint128, total_supply: Uint128, decimal_offset: u32) -> Decimal {
+    Decimal::from_ratio(
+        total_lp + Uint128::one(),
+        total_supply + Uint128::from(10u128).pow(decimal_offset),
+    )
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -194,7 +520,7 @@ mod test {
     use crate::test_common::app_init;
     use abstract_core::objects::pool_id::PoolAddressBase;
     use abstract_core::objects::{AssetEntry, PoolMetadata};
-    use cosmwasm_std::testing::mock_info;
+    use cosmwasm_std::testing::{mock_env, mock_info};
     use cosmwasm_std::Addr;
     use cosmwasm_std::Decimal;
 
@@ -221,7 +547,8 @@ mod test {
     }
 
     mod claims {
-        use cosmwasm_std::Timestamp;
+        use crate::claims_nft::{Cw721QueryMsg, TokensResponse};
+        use cosmwasm_std::{from_json, ContractResult, SystemResult, Timestamp, WasmQuery};
 
         use super::*;
 
@@ -251,6 +578,43 @@ mod test {
             assert_that!(claims).is_equal_to(expected_claims)
         }
 
+        #[test]
+        fn nft_claims_are_queried_by_holder() -> anyhow::Result<()> {
+            let mut deps = app_init(true, true);
+            let claim = |seconds: u64| Claim {
+                unbonding_timestamp: Expiration::AtTime(Timestamp::from_seconds(seconds)),
+                amount_of_vault_tokens_to_burn: 1000u128.into(),
+                amount_of_lp_tokens_to_unbond: 1000u128.into(),
+            };
+            CLAIMS_NFT.save(deps.as_mut().storage, &Addr::unchecked("claims_nft"))?;
+            for (token_id, seconds) in [("1", 100), ("2", 200), ("3", 300)] {
+                NFT_CLAIMS.save(deps.as_mut().storage, token_id.to_string(), &claim(seconds))?;
+            }
+            // the user holds the claim nfts 1 and 3
+            deps.querier.update_wasm(|query| {
+                let WasmQuery::Smart { msg, .. } = query else {
+                    panic!("unexpected query");
+                };
+                let Cw721QueryMsg::Tokens {
+                    owner, start_after, ..
+                } = from_json(msg).unwrap()
+                else {
+                    panic!("unexpected query");
+                };
+                let tokens = match (owner.as_str(), start_after) {
+                    ("user", None) => vec!["1".to_string(), "3".to_string()],
+                    _ => vec![],
+                };
+                SystemResult::Ok(ContractResult::Ok(
+                    to_json_binary(&TokensResponse { tokens }).unwrap(),
+                ))
+            });
+
+            let claims = query_claims(deps.as_ref(), Addr::unchecked("user"))?;
+            assert_that!(claims).is_equal_to(vec![claim(100), claim(300)]);
+            Ok(())
+        }
+
         #[test]
         fn test_query_all_claims() {
             let mut deps = app_init(true, true);
@@ -374,7 +738,7 @@ mod test {
             config.vault_token = AssetInfo::cw20(Addr::unchecked(TEST_VAULT_TOKEN));
             CONFIG.save(deps.as_mut().storage, &config).unwrap();
 
-            let total_supply = query_total_supply(deps.as_ref()).unwrap();
+            let total_supply = query_total_supply(deps.as_ref(), &mock_env()).unwrap();
             assert_eq!(total_supply, vault_balance);
         }
 
@@ -400,14 +764,66 @@ mod test {
             let app = AutocompounderApp::new("test", "test_version", None);
 
             let assets_per_share =
-                convert_to_assets(1000u128.into(), 100u128.into(), 1000u128.into());
+                convert_to_assets(1000u128.into(), 100u128.into(), 1000u128.into(), 1);
             let mut config = default_config();
             config.vault_token = AssetInfo::cw20(Addr::unchecked(TEST_VAULT_TOKEN));
             CONFIG.save(deps.as_mut().storage, &config).unwrap();
 
             let result =
-                query_assets_per_shares(&app, deps.as_ref(), Some(1000u128.into())).unwrap();
+                query_assets_per_shares(&app, deps.as_ref(), &mock_env(), Some(1000u128.into()))
+                    .unwrap();
             assert_eq!(result, assets_per_share);
         }
     }
+
+    mod preview_compound {
+        use super::*;
+
+        #[test]
+        fn lp_minted_is_estimated_from_the_reserves() {
+            let lp_supply = Uint128::new(1000);
+            // balanced provision mints the share of both reserves
+            let balanced = [
+                (100u128.into(), 1000u128.into()),
+                (50u128.into(), 500u128.into()),
+            ];
+            assert_eq!(
+                estimate_lp_minted(&balanced, lp_supply),
+                Some(Uint128::new(100))
+            );
+            // unbalanced provision is valued at the pool price, without slippage
+            let unbalanced = [
+                (100u128.into(), 1000u128.into()),
+                (20u128.into(), 500u128.into()),
+            ];
+            assert_eq!(
+                estimate_lp_minted(&unbalanced, lp_supply),
+                Some(Uint128::new(70))
+            );
+            let single_sided = [
+                (100u128.into(), 1000u128.into()),
+                (0u128.into(), 500u128.into()),
+            ];
+            assert_eq!(
+                estimate_lp_minted(&single_sided, lp_supply),
+                Some(Uint128::new(50))
+            );
+            // empty pools can't be estimated
+            let empty = [
+                (100u128.into(), 0u128.into()),
+                (50u128.into(), 500u128.into()),
+            ];
+            assert_eq!(estimate_lp_minted(&empty, lp_supply), None);
+        }
+
+        #[test]
+        fn assets_per_share_matches_convert_to_assets() {
+            let shares = Uint128::new(1_000_000);
+            let expected = convert_to_assets(shares, 500u128.into(), 1000u128.into(), 1);
+            assert_eq!(
+                shares * assets_per_share(500u128.into(), 1000u128.into(), 1),
+                expected
+            );
+        }
+    }
 }
@@ -1,20 +1,26 @@
+use super::execute::mint_lp_deposit;
 use super::helpers::{
-    convert_to_shares, get_last_msgs_with_reply, mint_vault_tokens_msg,
-    parse_instantiate_reply_cw20, query_stake, stake_lp_tokens, swap_rewards,
-    vault_token_total_supply,
+    burn_vault_tokens_msg, check_deposit_caps, convert_to_assets, convert_to_shares,
+    decimal_offset, get_last_msgs_with_reply, mint_vault_tokens_msgs, parse_instantiate_reply_cw20,
+    pool_assets_context, query_reward_tokens, query_stake, query_total_lp,
+    rebalance_liquidity_buffer, save_reply_context, stake_lp_tokens, swap_rewards,
+    take_reply_context, update_reply_context, vault_token_balance, vault_token_total_supply,
 };
 use crate::contract::{
-    AutocompounderApp, AutocompounderResult, CP_PROVISION_REPLY_ID, SWAPPED_REPLY_ID,
+    AutocompounderApp, AutocompounderResult, CP_PROVISION_REPLY_ID,
+    MATURED_CLAIMS_WITHDRAWAL_REPLY_ID, SWAPPED_REPLY_ID,
 };
 use crate::error::AutocompounderError;
+use crate::msg::DeniedRewards;
 
-use crate::state::{Config, FeeConfig, CACHED_ASSETS, CACHED_USER_ADDR, CONFIG, FEE_CONFIG};
-use abstract_core::objects::{AnsEntryConvertor, AssetEntry};
-use abstract_cw_staking::{
-    msg::{RewardTokensResponse, StakingQueryMsg},
-    CW_STAKING_ADAPTER_ID,
+use crate::state::{
+    Config, FeeConfig, ReplyContext, CLAIMS_NFT, CONFIG, DEFAULT_BATCH_SIZE, DEPOSIT_CAPS,
+    EMERGENCY_SHUTDOWN, FEE_CONFIG, LIQUIDITY_BUFFER, LIQUIDITY_BUFFER_CONFIG, PENDING_CLAIMS,
+    REWARD_FILTER,
 };
+use abstract_core::objects::{ans_host::AnsHostError, AnsEntryConvertor, AssetEntry};
 use abstract_dex_adapter::api::DexInterface;
+use abstract_sdk::AccountAction;
 use abstract_sdk::Execution;
 use abstract_sdk::{
     core::objects::AnsAsset,
@@ -22,9 +28,12 @@ use abstract_sdk::{
     features::{AbstractNameService, AccountIdentification},
     AbstractSdkResult, Resolve, TransferInterface,
 };
-use abstract_sdk::{AccountAction, AdapterInterface};
-use cosmwasm_std::{CosmosMsg, Decimal, Deps, DepsMut, Env, Reply, StdResult, SubMsg, Uint128};
+use cosmwasm_std::{
+    Addr, CosmosMsg, Decimal, Deps, DepsMut, Env, Order, Reply, StdError, StdResult, Storage,
+    SubMsg, SubMsgResult, Uint128,
+};
 use cw_asset::{Asset, AssetInfo};
+use cw_utils::parse_reply_instantiate_data;
 
 /// Handle a reply for the [`INSTANTIATE_REPLY_ID`] reply.
 pub fn instantiate_reply(
@@ -56,90 +65,307 @@ pub fn instantiate_reply(
     ))
 }
 
-pub fn lp_provision_reply(
+/// Handle a reply for the [`CLAIMS_NFT_INSTANTIATE_REPLY_ID`](crate::contract::CLAIMS_NFT_INSTANTIATE_REPLY_ID) reply.
+pub fn claims_nft_instantiate_reply(
     deps: DepsMut,
+    _env: Env,
+    app: AutocompounderApp,
+    reply: Reply,
+) -> AutocompounderResult {
+    let response = parse_reply_instantiate_data(reply)
+        .map_err(|err| AutocompounderError::Std(StdError::generic_err(err.to_string())))?;
+    let claims_nft = deps.api.addr_validate(&response.contract_address)?;
+    CLAIMS_NFT.save(deps.storage, &claims_nft)?;
+
+    Ok(app.custom_response(
+        "claims_nft_instantiate_reply",
+        vec![("claims_nft", claims_nft.to_string())],
+    ))
+}
+
+pub fn lp_provision_reply(
+    mut deps: DepsMut,
     env: Env,
     app: AutocompounderApp,
-    _reply: Reply,
+    reply: Reply,
 ) -> AutocompounderResult {
     let config = CONFIG.load(deps.storage)?;
     let fee_config = FEE_CONFIG.load(deps.storage)?;
-    let user_address = CACHED_USER_ADDR.load(deps.storage)?;
+    let context = take_reply_context(deps.storage, &reply)?;
+    let depositor = context
+        .depositor
+        .ok_or_else(|| StdError::not_found("depositor"))?;
     let proxy_address = app.proxy_address(deps.as_ref())?;
     let ans_host = app.ans_host(deps.as_ref())?;
-    CACHED_USER_ADDR.remove(deps.storage);
+
+    // pool assets that were not provided as liquidity are refunded to the depositor
+    let owned_assets = app.bank(deps.as_ref()).balances(&config.pool_data.assets)?;
+    let unused_assets = unused_deposit_assets(
+        owned_assets,
+        &context.cached_assets,
+        &config.pool_data.assets,
+    );
 
     // get the total supply of Vault token
-    let current_vault_supply = vault_token_total_supply(deps.as_ref(), &config)?;
+    let current_vault_supply =
+        vault_token_total_supply(deps.as_ref(), &env.contract.address, &config)?;
 
-    // Retrieve the number of LP tokens minted/staked.
+    // Retrieve the number of LP tokens minted/staked, the liquidity buffer is not part of the deposit.
     let lp_token = config.lp_token();
-    let received_lp = lp_token
+    let lp_balance = lp_token
         .resolve(&deps.querier, &ans_host)?
         .query_balance(&deps.querier, proxy_address.to_string())?;
+    let received_lp =
+        lp_balance.checked_sub(LIQUIDITY_BUFFER.may_load(deps.storage)?.unwrap_or_default())?;
 
     // subtract the deposit fee from the received LP tokens
     let user_allocated_lp = received_lp.checked_sub(received_lp * fee_config.deposit)?;
 
-    let staked_lp = query_stake(
-        deps.as_ref(),
-        &app,
-        config.pool_data.dex.clone(),
-        config.lp_asset_entry(),
-        config.unbonding_period,
-    )?;
+    let staked_lp = query_total_lp(deps.as_ref(), &app, &config)?;
 
     // The increase in LP tokens held by the vault should be reflected by an equal increase (% wise) in vault tokens.
     // Calculate the number of vault tokens to mint
-    let mint_amount = convert_to_shares(user_allocated_lp, staked_lp, current_vault_supply);
+    let mint_amount = convert_to_shares(
+        user_allocated_lp,
+        staked_lp,
+        current_vault_supply,
+        decimal_offset(deps.storage)?,
+    );
     if mint_amount.is_zero() {
         return Err(AutocompounderError::ZeroMintAmount {});
     }
 
-    // Mint vault tokens to the user
-    let mint_msg = mint_vault_tokens_msg(
+    // The received LP tokens first fill pending claims so they don't need to be unbonded.
+    let (filled_lp, claim_msgs) = fill_pending_claims_msgs(
+        deps.branch(),
+        &env,
+        &app,
         &config,
-        &env.contract.address,
-        user_address,
-        mint_amount,
-        config.pool_data.dex.clone(),
+        &fee_config,
+        received_lp,
+        staked_lp,
+        current_vault_supply,
     )?;
+    let lp_to_stake = received_lp.checked_sub(filled_lp)?;
 
-    // Stake the LP tokens
-    let stake_msg = stake_lp_tokens(
-        deps.as_ref(),
-        &app,
-        config.pool_data.dex,
-        AnsAsset::new(AnsEntryConvertor::new(lp_token).asset_entry(), received_lp), // stake the total amount of LP tokens received
-        config.unbonding_period,
-    )?;
+    let allocations =
+        vault_token_allocations(context.recipient, context.batch_recipients, mint_amount)?;
 
-    Ok(app
+    let caps = DEPOSIT_CAPS.may_load(deps.storage)?;
+    let mut mint_msgs = vec![];
+    for (recipient, amount) in allocations {
+        if let Some(caps) = &caps {
+            let balance = vault_token_balance(deps.as_ref(), &config, recipient.clone())?;
+            check_deposit_caps(caps, staked_lp + lp_to_stake, balance + amount)?;
+        }
+
+        // Mint vault tokens to the user
+        mint_msgs.extend(mint_vault_tokens_msgs(
+            deps.as_ref(),
+            &config,
+            &env.contract.address,
+            recipient,
+            amount,
+        )?);
+    }
+
+    let mut response = app
         .custom_response(
             "lp_provision_reply",
-            vec![("vault_token_minted", mint_amount)],
+            vec![
+                ("vault_token_minted", mint_amount),
+                ("lp_filling_claims", filled_lp),
+            ],
         )
-        .add_message(mint_msg)
-        .add_message(stake_msg))
+        .add_messages(mint_msgs);
+
+    // Stake the net amount of LP tokens
+    if !lp_to_stake.is_zero() {
+        let stake_msg = stake_lp_tokens(
+            deps.as_ref(),
+            &app,
+            config.pool_data.dex.clone(),
+            AnsAsset::new(AnsEntryConvertor::new(lp_token).asset_entry(), lp_to_stake),
+            config.unbonding_period,
+        )?;
+        response = response.add_message(stake_msg);
+    }
+
+    // Burn the vault tokens of the filled claims and pay out the owners in the reply
+    response = response.add_submessages(claim_msgs);
+
+    // Refund last, as the claims withdrawal reply above relies on the pool assets cached in this reply
+    if !unused_assets.is_empty() {
+        let refund_msg = app
+            .bank(deps.as_ref())
+            .transfer(unused_assets, &depositor)?;
+        response = response.add_message(app.executor(deps.as_ref()).execute(vec![refund_msg])?);
+    }
+
+    Ok(response)
+}
+
+/// Splits the minted vault tokens over the recipients of a deposit.
+/// The last recipient of a batch deposit receives the rounding remainder. Recipients without allocation are left out.
+fn vault_token_allocations(
+    recipient: Option<Addr>,
+    batch_recipients: Vec<(Addr, Decimal)>,
+    mint_amount: Uint128,
+) -> AutocompounderResult<Vec<(Addr, Uint128)>> {
+    if batch_recipients.is_empty() {
+        let recipient = recipient.ok_or_else(|| StdError::not_found("recipient"))?;
+        return Ok(vec![(recipient, mint_amount)]);
+    }
+
+    let mut remaining = mint_amount;
+    let last = batch_recipients.len() - 1;
+    let mut allocations = vec![];
+    for (i, (recipient, share)) in batch_recipients.into_iter().enumerate() {
+        let amount = if i == last {
+            remaining
+        } else {
+            (mint_amount * share).min(remaining)
+        };
+        remaining -= amount;
+        if !amount.is_zero() {
+            allocations.push((recipient, amount));
+        }
+    }
+    Ok(allocations)
+}
+
+/// Calculates the pool assets that the proxy holds on top of the cached balances.
+/// Assets of which the balance decreased, e.g. because they were compounded, are left out.
+fn unused_deposit_assets(
+    owned_assets: Vec<Asset>,
+    cached_assets: &[Asset],
+    pool_assets: &[AssetEntry],
+) -> Vec<AnsAsset> {
+    let mut unused_assets = vec![];
+    for (asset, entry) in owned_assets.into_iter().zip(pool_assets) {
+        let prev_amount = cached_amount(cached_assets, &asset.info);
+        let amount = asset.amount.saturating_sub(prev_amount);
+        if !amount.is_zero() {
+            unused_assets.push(AnsAsset::new(entry.clone(), amount));
+        }
+    }
+    unused_assets
+}
+
+/// In vaults with an unbonding period, fills pending claims with `available_lp` deposited LP tokens.
+/// Returns the filled LP tokens and the messages that burn the vault tokens of the filled claims and withdraw their LP tokens,
+/// the owners are paid out in the [`MATURED_CLAIMS_WITHDRAWAL_REPLY_ID`] reply.
+#[allow(clippy::too_many_arguments)]
+pub fn fill_pending_claims_msgs(
+    deps: DepsMut,
+    env: &Env,
+    app: &AutocompounderApp,
+    config: &Config,
+    fee_config: &FeeConfig,
+    available_lp: Uint128,
+    staked_lp: Uint128,
+    vault_supply: Uint128,
+) -> AutocompounderResult<(Uint128, Vec<SubMsg>)> {
+    if config.unbonding_period.is_none() {
+        return Ok((Uint128::zero(), vec![]));
+    }
+
+    let (filled_lp, vault_tokens_to_burn, payouts) = fill_pending_claims(
+        deps.storage,
+        fee_config,
+        available_lp,
+        staked_lp,
+        vault_supply,
+    )?;
+    if filled_lp.is_zero() {
+        return Ok((filled_lp, vec![]));
+    }
+
+    let burn_msg = burn_vault_tokens_msg(
+        deps.as_ref(),
+        config,
+        &env.contract.address,
+        vault_tokens_to_burn,
+    )?;
+
+    let context = ReplyContext {
+        claim_payouts: payouts,
+        ..pool_assets_context(deps.as_ref(), app, config)?
+    };
+    let withdrawal_id =
+        save_reply_context(deps.storage, context, MATURED_CLAIMS_WITHDRAWAL_REPLY_ID)?;
+
+    let dex = app.ans_dex(deps.as_ref(), config.pool_data.dex.clone());
+    let withdraw_msg: CosmosMsg =
+        dex.withdraw_liquidity(AnsAsset::new(config.lp_asset_entry(), filled_lp))?;
+
+    Ok((
+        filled_lp,
+        vec![
+            SubMsg::new(burn_msg),
+            SubMsg::reply_on_success(withdraw_msg, withdrawal_id),
+        ],
+    ))
+}
+
+/// Fills pending claims with `available_lp` newly provided LP tokens, at the current share price and minus the withdrawal fee.
+/// Only claims that can be filled completely are filled.
+/// Returns the filled LP tokens, the vault tokens to burn and the LP tokens per owner.
+#[allow(clippy::type_complexity)]
+fn fill_pending_claims(
+    storage: &mut dyn Storage,
+    fee_config: &FeeConfig,
+    available_lp: Uint128,
+    staked_lp: Uint128,
+    vault_supply: Uint128,
+) -> Result<(Uint128, Uint128, Vec<(Addr, Uint128)>), AutocompounderError> {
+    let pending_claims = PENDING_CLAIMS
+        .range(storage, None, None, Order::Ascending)
+        .take(DEFAULT_BATCH_SIZE as usize)
+        .collect::<StdResult<Vec<(Addr, Uint128)>>>()?;
+
+    let offset = decimal_offset(storage)?;
+
+    let mut filled_lp = Uint128::zero();
+    let mut vault_tokens_to_burn = Uint128::zero();
+    let mut payouts: Vec<(Addr, Uint128)> = vec![];
+    for (owner, vault_tokens) in pending_claims {
+        let lp_tokens = convert_to_assets(vault_tokens, staked_lp, vault_supply, offset);
+        let lp_tokens = lp_tokens.checked_sub(lp_tokens * fee_config.withdrawal)?;
+        if lp_tokens.is_zero() || filled_lp + lp_tokens > available_lp {
+            continue;
+        }
+
+        filled_lp += lp_tokens;
+        vault_tokens_to_burn += vault_tokens;
+        PENDING_CLAIMS.remove(storage, owner.clone());
+        payouts.push((owner, lp_tokens));
+    }
+
+    Ok((filled_lp, vault_tokens_to_burn, payouts))
 }
 
 pub fn lp_withdrawal_reply(
     deps: DepsMut,
     _env: Env,
     app: AutocompounderApp,
-    _reply: Reply,
+    reply: Reply,
 ) -> AutocompounderResult {
     let config = CONFIG.load(deps.storage)?;
-    let user_address = CACHED_USER_ADDR.load(deps.storage)?;
-    CACHED_USER_ADDR.remove(deps.storage);
+    let context = take_reply_context(deps.storage, &reply)?;
+    let user_address = context
+        .recipient
+        .ok_or_else(|| StdError::not_found("recipient"))?;
     let bank = app.bank(deps.as_ref());
 
     let owned_assets = bank.balances(&config.pool_data.assets)?;
-    let funds =
-        cached_asset_balance_differences(owned_assets, &deps.as_ref(), &config.pool_data.assets)?;
+    let funds = cached_asset_balance_differences(
+        owned_assets,
+        &context.cached_assets,
+        &config.pool_data.assets,
+    )?;
 
     let transfer_msg = bank.transfer(funds.clone(), &user_address)?;
-    CACHED_ASSETS.clear(deps.storage);
 
     Ok(app
         .custom_response(
@@ -151,17 +377,107 @@ pub fn lp_withdrawal_reply(
         .add_messages(app.executor(deps.as_ref()).execute(vec![transfer_msg])))
 }
 
-/// Calculates the difference between the currently proxy-owned assets and the assets that were cached before the lp_withdrawal_reply
+/// Handle a reply for the [`MATURED_CLAIMS_WITHDRAWAL_REPLY_ID`](crate::contract::MATURED_CLAIMS_WITHDRAWAL_REPLY_ID) reply.
+/// Sends every owner of the processed claims their share of the withdrawn assets.
+pub fn matured_claims_withdrawal_reply(
+    deps: DepsMut,
+    _env: Env,
+    app: AutocompounderApp,
+    reply: Reply,
+) -> AutocompounderResult {
+    let config = CONFIG.load(deps.storage)?;
+    let context = take_reply_context(deps.storage, &reply)?;
+    let payouts = context.claim_payouts;
+    let bank = app.bank(deps.as_ref());
+
+    let owned_assets = bank.balances(&config.pool_data.assets)?;
+    let funds = cached_asset_balance_differences(
+        owned_assets,
+        &context.cached_assets,
+        &config.pool_data.assets,
+    )?;
+
+    let transfer_msgs = split_pro_rata(&funds, &payouts)
+        .into_iter()
+        .map(|(owner, owner_funds)| bank.transfer(owner_funds, &owner))
+        .collect::<AbstractSdkResult<Vec<AccountAction>>>()?;
+
+    Ok(app
+        .custom_response(
+            "matured_claims_withdrawal_reply",
+            vec![("owners", payouts.len().to_string())],
+        )
+        .add_messages(app.executor(deps.as_ref()).execute(transfer_msgs)))
+}
+
+/// Handle a reply for the [`EMERGENCY_WITHDRAWAL_REPLY_ID`](crate::contract::EMERGENCY_WITHDRAWAL_REPLY_ID) reply.
+/// Stores the withdrawn pool assets as the holdings of the shut down vault.
+pub fn emergency_withdrawal_reply(
+    deps: DepsMut,
+    _env: Env,
+    app: AutocompounderApp,
+    reply: Reply,
+) -> AutocompounderResult {
+    let config = CONFIG.load(deps.storage)?;
+    let context = take_reply_context(deps.storage, &reply)?;
+
+    let owned_assets = app.bank(deps.as_ref()).balances(&config.pool_data.assets)?;
+    let assets = cached_asset_balance_differences(
+        owned_assets,
+        &context.cached_assets,
+        &config.pool_data.assets,
+    )?;
+
+    EMERGENCY_SHUTDOWN.update(deps.storage, |mut state| -> StdResult<_> {
+        state.lp_tokens = Uint128::zero();
+        state.assets = assets.clone();
+        Ok(state)
+    })?;
+
+    Ok(app.custom_response(
+        "emergency_withdrawal_reply",
+        assets
+            .into_iter()
+            .map(|asset| ("withdrawn", asset.to_string())),
+    ))
+}
+
+/// Splits `funds` over the owners pro rata to their lp tokens. Owners that would receive nothing are left out.
+fn split_pro_rata(funds: &[AnsAsset], payouts: &[(Addr, Uint128)]) -> Vec<(Addr, Vec<AnsAsset>)> {
+    let total_lp_tokens: Uint128 = payouts.iter().map(|(_, lp_tokens)| *lp_tokens).sum();
+    if total_lp_tokens.is_zero() {
+        return vec![];
+    }
+
+    payouts
+        .iter()
+        .filter_map(|(owner, lp_tokens)| {
+            let owner_funds = funds
+                .iter()
+                .map(|asset| {
+                    AnsAsset::new(
+                        asset.name.clone(),
+                        asset.amount.multiply_ratio(*lp_tokens, total_lp_tokens),
+                    )
+                })
+                .filter(|asset| !asset.amount.is_zero())
+                .collect::<Vec<AnsAsset>>();
+            (!owner_funds.is_empty()).then(|| (owner.clone(), owner_funds))
+        })
+        .collect()
+}
+
+/// Calculates the difference between the currently proxy-owned assets and the assets that were cached in the reply context
 fn cached_asset_balance_differences(
     owned_assets: Vec<Asset>,
-    deps: &Deps,
+    cached_assets: &[Asset],
     pool_assets: &[AssetEntry],
 ) -> Result<Vec<AnsAsset>, AutocompounderError> {
     let funds = owned_assets
         .into_iter()
         .enumerate()
         .map(|(i, asset)| -> StdResult<_> {
-            let prev_amount = CACHED_ASSETS.load(deps.storage, asset.info.to_string())?;
+            let prev_amount = cached_amount(cached_assets, &asset.info);
             let amount = asset.amount.checked_sub(prev_amount)?;
             Ok(AnsAsset::new(pool_assets[i].clone(), amount))
         })
@@ -170,11 +486,76 @@ fn cached_asset_balance_differences(
     Ok(funds)
 }
 
+/// Returns the cached amount of `info`, zero if it was not cached
+fn cached_amount(cached_assets: &[Asset], info: &AssetInfo) -> Uint128 {
+    cached_assets
+        .iter()
+        .find(|cached| cached.info == *info)
+        .map(|cached| cached.amount)
+        .unwrap_or_default()
+}
+
+/// Handle a reply for the [`DEPOSIT_COMPOUND_REPLY_ID`](crate::contract::DEPOSIT_COMPOUND_REPLY_ID) reply.
+/// The deposit continues whether or not the rewards could be compounded, e.g. when there were none.
+/// Lp token deposits are minted here, after the compound.
+pub fn deposit_compound_reply(
+    deps: DepsMut,
+    env: Env,
+    app: AutocompounderApp,
+    reply: Reply,
+) -> AutocompounderResult {
+    let context = take_reply_context(deps.storage, &reply)?;
+    let mut response = app.response("deposit_compound_reply");
+    if let SubMsgResult::Err(err) = reply.result {
+        response = response.add_attribute("compound_error", err);
+    }
+
+    // cache the pool assets of the deposit after the compound, so only the unused deposit assets are refunded
+    if let Some(deposit_submsg_id) = context.deposit_submsg_id {
+        let config = CONFIG.load(deps.storage)?;
+        let cached_assets = pool_assets_context(deps.as_ref(), &app, &config)?.cached_assets;
+        update_reply_context(deps.storage, deposit_submsg_id, |deposit| ReplyContext {
+            cached_assets,
+            ..deposit
+        })?;
+    }
+
+    let Some(lp_deposit) = context.lp_deposit else {
+        return Ok(response);
+    };
+    let depositor = context
+        .depositor
+        .ok_or_else(|| StdError::not_found("depositor"))?;
+    let recipient = context
+        .recipient
+        .ok_or_else(|| StdError::not_found("recipient"))?;
+    mint_lp_deposit(
+        deps,
+        &env,
+        &app,
+        lp_deposit,
+        depositor,
+        recipient,
+        context.received_lp,
+        response,
+    )
+}
+
 pub fn lp_compound_reply(
     deps: DepsMut,
     _env: Env,
     app: AutocompounderApp,
-    _reply: Reply,
+    reply: Reply,
+) -> AutocompounderResult {
+    let context = take_reply_context(deps.storage, &reply)?;
+    compound_rewards(deps, app, context)
+}
+
+/// Compounds the rewards harvested since the balances of `context` were cached
+fn compound_rewards(
+    deps: DepsMut,
+    app: AutocompounderApp,
+    context: ReplyContext,
 ) -> AutocompounderResult {
     let config = CONFIG.load(deps.storage)?;
 
@@ -185,12 +566,28 @@ pub fn lp_compound_reply(
     let dex = app.ans_dex(deps.as_ref(), config.pool_data.dex.clone());
 
     // query the rewards and filters out zero rewards
-    let mut rewards = get_staking_rewards(deps.as_ref(), &app, &config)?;
+    let StakingRewards {
+        compound: mut rewards,
+        denied,
+    } = get_staking_rewards(deps.as_ref(), &app, &config, &context.cached_assets)?;
+
+    if !denied.is_empty() {
+        let transfer_msg = app
+            .bank(deps.as_ref())
+            .transfer(denied.clone(), &fee_config.fee_collector_addr)?;
+        messages.push(transfer_msg);
+    }
 
     if rewards.is_empty() {
-        return Err(AutocompounderError::NoRewards {});
+        if messages.is_empty() {
+            return Err(AutocompounderError::NoRewards {});
+        }
+        return Ok(app
+            .response("lp_compound_reply")
+            .add_messages(app.executor(deps.as_ref()).execute(messages)));
     }
 
+    let harvested = rewards.clone();
     deduct_performance_fees(fee_config, &mut rewards, &app, &deps, &mut messages)?;
 
     // Swap rewards to token in pool
@@ -224,8 +621,16 @@ pub fn lp_compound_reply(
 
         Ok(response)
     } else {
+        // cache the pool assets that are not compounded, so the swapped reply only provides the harvested rewards
+        let held_assets = app.bank(deps.as_ref()).balances(&pool_assets)?;
+        let context = ReplyContext {
+            cached_assets: uncompounded_pool_assets(held_assets, &pool_assets, &harvested, &denied),
+            ..Default::default()
+        };
+        let swapped_id = save_reply_context(deps.storage, context, SWAPPED_REPLY_ID)?;
+
         let mut swap_msgs = swap_rewards(&app, deps.as_ref(), rewards)?;
-        let submsg = get_last_msgs_with_reply(&mut swap_msgs, SWAPPED_REPLY_ID)?;
+        let submsg = get_last_msgs_with_reply(&mut swap_msgs, swapped_id)?;
 
         submessages.push(submsg);
 
@@ -262,7 +667,10 @@ fn deduct_performance_fees(
     Ok(())
 }
 
-fn deduct_fees_from_rewards(rewards: &mut [AnsAsset], performance_fee: Decimal) -> Vec<AnsAsset> {
+pub fn deduct_fees_from_rewards(
+    rewards: &mut [AnsAsset],
+    performance_fee: Decimal,
+) -> Vec<AnsAsset> {
     let fees = rewards
         .iter_mut()
         .map(|reward| -> AnsAsset {
@@ -276,31 +684,51 @@ fn deduct_fees_from_rewards(rewards: &mut [AnsAsset], performance_fee: Decimal)
     fees
 }
 
-/// Queries the balances of pool assets and provides liquidity to the pool
+/// Pool asset balances of the proxy that are not compounded: the `held_assets` without the `harvested` rewards
+/// (before fees, which are sent away) and the `sent` denied rewards.
+fn uncompounded_pool_assets(
+    held_assets: Vec<Asset>,
+    pool_assets: &[AssetEntry],
+    harvested: &[AnsAsset],
+    sent: &[Asset],
+) -> Vec<Asset> {
+    held_assets
+        .into_iter()
+        .zip(pool_assets)
+        .map(|(mut asset, entry)| {
+            let compounded = harvested
+                .iter()
+                .find(|reward| reward.name == *entry)
+                .map(|reward| reward.amount)
+                .unwrap_or_default();
+            let sent = cached_amount(sent, &asset.info);
+            asset.amount = asset.amount.saturating_sub(compounded + sent);
+            asset
+        })
+        .collect()
+}
+
+/// Provides liquidity with the pool assets received from the swaps and the harvested pool asset rewards
 ///
-/// This function is triggered after the last swap message of the lp_compound_reply
-/// and assumes the contract has no other rewards than the ones in the pool assets
+/// This function is triggered after the last swap message of the lp_compound_reply.
+/// Pool assets the proxy held before the compound are left untouched.
 pub fn swapped_reply(
     deps: DepsMut,
     _env: Env,
     app: AutocompounderApp,
-    _reply: Reply,
+    reply: Reply,
 ) -> AutocompounderResult {
-    let ans_host = app.ans_host(deps.as_ref())?;
+    let context = take_reply_context(deps.storage, &reply)?;
     let config = CONFIG.load(deps.storage)?;
     let dex = app.ans_dex(deps.as_ref(), config.pool_data.dex);
 
     // query balance of pool tokens
-    let rewards = config
-        .pool_data
-        .assets
-        .iter()
-        .map(|entry| -> AutocompounderResult<AnsAsset> {
-            let tkn = entry.resolve(&deps.querier, &ans_host)?;
-            let balance = tkn.query_balance(&deps.querier, app.proxy_address(deps.as_ref())?)?;
-            Ok(AnsAsset::new(entry.clone(), balance))
-        })
-        .collect::<AutocompounderResult<Vec<AnsAsset>>>()?;
+    let owned_assets = app.bank(deps.as_ref()).balances(&config.pool_data.assets)?;
+    let rewards = cached_asset_balance_differences(
+        owned_assets,
+        &context.cached_assets,
+        &config.pool_data.assets,
+    )?;
 
     // provide liquidity
     let lp_msg: CosmosMsg = dex.provide_liquidity(rewards, Some(Decimal::percent(10)))?;
@@ -321,72 +749,117 @@ pub fn compound_lp_provision_reply(
 
     let lp_token = config.lp_asset_entry();
 
-    // query balance of lp tokens
+    // query balance of lp tokens, excluding the liquidity buffer
     let lp_balance = lp_token
         .resolve(&deps.querier, &ans_host)?
         .query_balance(&deps.querier, proxy)?;
+    let buffer = LIQUIDITY_BUFFER.may_load(deps.storage)?.unwrap_or_default();
+    let new_lp = lp_balance.checked_sub(buffer)?;
+
+    // rebalance the liquidity buffer with the new lp tokens
+    let (lp_to_stake, buffer) = match LIQUIDITY_BUFFER_CONFIG.may_load(deps.storage)? {
+        Some(buffer_config) => {
+            let staked_lp = query_stake(
+                deps.as_ref(),
+                &app,
+                config.pool_data.dex.clone(),
+                lp_token.clone(),
+                config.unbonding_period,
+            )?;
+            rebalance_liquidity_buffer(new_lp, staked_lp, buffer, buffer_config.target)
+        }
+        None => (new_lp, buffer),
+    };
+    LIQUIDITY_BUFFER.save(deps.storage, &buffer)?;
+
+    let mut response = app.custom_response(
+        "compound_lp_provision_reply",
+        vec![("liquidity_buffer", buffer.to_string())],
+    );
 
     // stake lp tokens
-    let stake_msg = stake_lp_tokens(
-        deps.as_ref(),
-        &app,
-        config.pool_data.dex.clone(),
-        AnsAsset::new(lp_token, lp_balance),
-        config.unbonding_period,
-    )?;
+    if !lp_to_stake.is_zero() {
+        let stake_msg = stake_lp_tokens(
+            deps.as_ref(),
+            &app,
+            config.pool_data.dex.clone(),
+            AnsAsset::new(lp_token, lp_to_stake),
+            config.unbonding_period,
+        )?;
+        response = response.add_message(stake_msg);
+    }
 
-    Ok(app
-        .response("compound_lp_provision_reply")
-        .add_message(stake_msg))
+    Ok(response)
 }
 
-fn query_rewards(
-    deps: Deps,
-    app: &AutocompounderApp,
-    config: &Config,
-) -> AbstractSdkResult<Vec<AssetInfo>> {
-    // query staking module for which rewards are available
-    let adapters = app.adapters(deps);
-    let query = StakingQueryMsg::RewardTokens {
-        provider: config.pool_data.dex.clone(),
-        staking_tokens: vec![config.lp_asset_entry()],
-    };
-    let RewardTokensResponse { tokens } = adapters.query(CW_STAKING_ADAPTER_ID, query)?;
-    let first_tokens = tokens.first().unwrap();
-
-    Ok(first_tokens.clone())
+/// Staking rewards, split by the [`RewardFilter`](crate::msg::RewardFilter)
+pub struct StakingRewards {
+    /// Allowed rewards that are registered in ANS
+    pub compound: Vec<AnsAsset>,
+    /// Denied rewards that are sent to the fee collector
+    pub denied: Vec<Asset>,
 }
 
-/// queries available staking rewards assets and the corresponding balances
-fn get_staking_rewards(
+/// queries available staking rewards assets and the amounts harvested since `cached_assets`
+pub fn get_staking_rewards(
     deps: Deps,
     app: &AutocompounderApp,
     config: &Config,
-) -> AutocompounderResult<Vec<AnsAsset>> {
-    let ans_host = app.ans_host(deps)?;
-    let rewards = query_rewards(deps, app, config)?;
+    cached_assets: &[Asset],
+) -> AutocompounderResult<StakingRewards> {
+    let rewards = query_reward_tokens(deps, app, config)?;
     // query balance of rewards
     let rewards = rewards
         .into_iter()
         .map(|tkn| -> AutocompounderResult<Asset> {
-            //  get the number of LP tokens minted in this transaction
+            // only the rewards harvested since the balances were cached
             let balance = tkn.query_balance(&deps.querier, app.proxy_address(deps)?)?;
-            Ok(Asset::new(tkn, balance))
+            let harvested = balance.saturating_sub(cached_amount(cached_assets, &tkn));
+            Ok(Asset::new(tkn, harvested))
         })
         .collect::<AutocompounderResult<Vec<Asset>>>()?;
-    // resolve rewards to AnsAssets for dynamic processing (swaps)
-    let rewards = rewards
+    filter_staking_rewards(deps, app, config, rewards)
+}
+
+/// Splits `rewards` into the rewards that are compounded and the denied rewards
+///
+/// Core assets are always compounded. Denied rewards are only returned if they are sent to the fee collector.
+/// Allowed rewards that are not registered in ANS are skipped, as they can't be swapped.
+pub fn filter_staking_rewards(
+    deps: Deps,
+    app: &AutocompounderApp,
+    config: &Config,
+    rewards: Vec<Asset>,
+) -> AutocompounderResult<StakingRewards> {
+    let ans_host = app.ans_host(deps)?;
+    let reward_filter = REWARD_FILTER.may_load(deps.storage)?.unwrap_or_default();
+    let (allowed, denied): (Vec<Asset>, Vec<Asset>) = rewards
         .into_iter()
         .filter(|reward| reward.amount != Uint128::zero())
-        .map(|asset| asset.resolve(&deps.querier, &ans_host))
-        .collect::<Result<Vec<AnsAsset>, _>>()?;
-    Ok(rewards)
+        .partition(|reward| {
+            config.is_core_asset(&reward.info) || reward_filter.is_allowed(&reward.info)
+        });
+    let denied = match reward_filter.denied_rewards {
+        DeniedRewards::Skip => vec![],
+        DeniedRewards::SendToFeeCollector => denied,
+    };
+    // resolve rewards to AnsAssets for dynamic processing (swaps)
+    let mut compound = vec![];
+    for reward in allowed {
+        match reward.resolve(&deps.querier, &ans_host) {
+            Ok(reward) => compound.push(reward),
+            Err(AnsHostError::CwAssetNotFound { .. }) => {}
+            Err(err) => return Err(err.into()),
+        }
+    }
+    Ok(StakingRewards { compound, denied })
 }
 
 #[cfg(test)]
 mod test {
     use crate::contract::{AUTOCOMPOUNDER_APP, LP_WITHDRAWAL_REPLY_ID};
     use crate::handlers::helpers::helpers_tests::min_cooldown_config;
+    use crate::state::REPLY_CONTEXTS;
 
     // use abstract_sdk::mock_module::MockModule;
     use abstract_testing::prelude::TEST_PROXY;
@@ -416,6 +889,13 @@ mod test {
             }
         }
 
+        fn context_reply(id: u64) -> Reply {
+            Reply {
+                id,
+                ..empty_reply()
+            }
+        }
+
         #[test]
         fn instantiate_with_invalid_reply() -> anyhow::Result<()> {
             let mut deps = app_init(false, true); // Assuming you have this helper function already set up.
@@ -438,10 +918,10 @@ mod test {
         /// This function tests the withdrawal reply function by the following steps:
         /// 0. Set up the app, config, and env.
         /// 1. set up the balances(1000eur, 1000usd) of the proxy contract in the bank
-        /// 2. setup stored balances(500eur, 400usd) of the CACHED_ASSETS in the storage and the user address
+        /// 2. setup the reply context with cached balances(500eur, 400usd) and the user address
         /// 3. call the withdraw_liquidity_reply function
         /// 4. check the response messages and attributes
-        /// 5. check the stored balances of the CACHED_ASSETS in the storage and the user address
+        /// 5. check that the reply context is removed from the storage
         fn succesful_withdrawal_with_balances() -> anyhow::Result<()> {
             let mut deps = app_init(false, true); // Assuming you have this helper function already set up.
                                                   // let module = MockModule::new();
@@ -468,23 +948,24 @@ mod test {
                 ],
             );
             let user_addr = Addr::unchecked("user_address");
-            CACHED_USER_ADDR.save(deps.as_mut().storage, &user_addr)?;
 
-            // 2. setup stored balances(500eur, 400usd) of the CACHED_ASSETS in the storage and the user address
-            CACHED_ASSETS.save(
-                deps.as_mut().storage,
-                eur_asset.to_string(),
-                &Uint128::new(500),
-            )?;
-            CACHED_ASSETS.save(
+            // 2. setup the reply context with cached balances(500eur, 400usd) and the user address
+            let id = save_reply_context(
                 deps.as_mut().storage,
-                usd_asset.to_string(),
-                &Uint128::new(400),
+                ReplyContext {
+                    recipient: Some(user_addr.clone()),
+                    cached_assets: vec![
+                        Asset::new(eur_asset, 500u128),
+                        Asset::new(usd_asset, 400u128),
+                    ],
+                    ..Default::default()
+                },
+                LP_WITHDRAWAL_REPLY_ID,
             )?;
 
             // 3. call the withdraw_liquidity_reply function
             let response =
-                lp_withdrawal_reply(deps.as_mut(), env, AUTOCOMPOUNDER_APP, empty_reply())?;
+                lp_withdrawal_reply(deps.as_mut(), env, AUTOCOMPOUNDER_APP, context_reply(id))?;
             let msg = &response.messages[0].msg;
 
             // 4. check the response messages and attributes
@@ -507,49 +988,46 @@ mod test {
             assert_that!(abstract_attributes[2].value).is_equal_to(eur_ans_asset.to_string());
             assert_that!(abstract_attributes[3].value).is_equal_to(usd_ans_asset.to_string());
 
-            // 5. check the stored balances of the CACHED_ASSETS in the storage and the user address
-            // Assert that the user address cache has been cleared.
-            let err = CACHED_USER_ADDR.load(&deps.storage);
-            assert_that!(err).is_err();
-
-            // Assert that the cached assets have been cleared.
-            let cached_assets: Vec<(String, Uint128)> = CACHED_ASSETS
+            // 5. check that the reply context is removed from the storage
+            let contexts: Vec<(u64, ReplyContext)> = REPLY_CONTEXTS
                 .range(&deps.storage, None, None, Order::Ascending)
                 .map(|x| x.unwrap())
                 .collect();
-            assert_that!(cached_assets).is_empty();
+            assert_that!(contexts).is_empty();
+            assert_that!(take_reply_context(
+                deps.as_mut().storage,
+                &context_reply(id)
+            ))
+            .is_err();
 
             Ok(())
         }
 
         #[test]
-        fn no_cached_addr_or_assets() -> anyhow::Result<()> {
+        fn no_reply_context_or_recipient() -> anyhow::Result<()> {
             let mut deps = app_init(false, true); // Assuming you have this helper function already set up.
 
             let res =
                 lp_withdrawal_reply(deps.as_mut(), mock_env(), AUTOCOMPOUNDER_APP, empty_reply());
             assert_that!(res).is_err();
             assert_that!(res.unwrap_err()).is_equal_to(AutocompounderError::Std(
-                StdError::NotFound {
-                    kind: format!(
-                        "type: cosmwasm_std::addresses::Addr; key: {:02X?}",
-                        CACHED_USER_ADDR.as_slice()
-                    ),
-                },
+                StdError::not_found("ReplyContext"),
             ));
 
-            CACHED_USER_ADDR.save(deps.as_mut().storage, &Addr::unchecked("user_address"))?;
-            let res =
-                lp_withdrawal_reply(deps.as_mut(), mock_env(), AUTOCOMPOUNDER_APP, empty_reply());
+            let id = save_reply_context(
+                deps.as_mut().storage,
+                ReplyContext::default(),
+                LP_WITHDRAWAL_REPLY_ID,
+            )?;
+            let res = lp_withdrawal_reply(
+                deps.as_mut(),
+                mock_env(),
+                AUTOCOMPOUNDER_APP,
+                context_reply(id),
+            );
             assert_that!(res).is_err();
-            assert_that!(res.unwrap_err()).is_equal_to(AutocompounderError::Std(
-                StdError::NotFound {
-                    kind: format!(
-                        "type: cosmwasm_std::math::uint128::Uint128; key: {:02X?}",
-                        CACHED_ASSETS.key("native:eur".to_string()).to_vec()
-                    ),
-                },
-            ));
+            assert_that!(res.unwrap_err())
+                .is_equal_to(AutocompounderError::Std(StdError::not_found("recipient")));
             Ok(())
         }
     }
@@ -558,7 +1036,7 @@ mod test {
     mod cached_assets {
 
         use super::*;
-        use cosmwasm_std::{testing::mock_dependencies, Addr};
+        use cosmwasm_std::Addr;
 
         fn asset1(amount: u128) -> Asset {
             Asset {
@@ -576,18 +1054,11 @@ mod test {
         #[test]
         fn cached_asset_balance_differences_all_greater() -> anyhow::Result<()> {
             let owned_assets = vec![asset1(1000u128), asset2()];
-            let mut deps = mock_dependencies();
+            let cached_assets = owned_assets.clone();
             let pool_assets = vec![AssetEntry::new("asset1"), AssetEntry::new("asset2")];
 
-            // Mock the CACHED_ASSETS load
-            owned_assets.iter().for_each(|f| {
-                CACHED_ASSETS
-                    .save(deps.as_mut().storage, f.info.to_string(), &f.amount)
-                    .unwrap()
-            });
-
             let result =
-                cached_asset_balance_differences(owned_assets, &deps.as_ref(), &pool_assets);
+                cached_asset_balance_differences(owned_assets, &cached_assets, &pool_assets);
 
             assert_that!(&result).is_ok().is_equal_to(vec![
                 AnsAsset::new("asset1".to_string(), 0u128),
@@ -599,39 +1070,50 @@ mod test {
         #[test]
         fn cached_asset_balance_differences_some_less() -> anyhow::Result<()> {
             let owned_assets = vec![asset1(50u128), asset2()];
-            let mut deps = mock_dependencies();
+            let cached_assets = vec![asset1(100u128), asset2()];
             let pool_assets = vec![AssetEntry::new("asset1"), AssetEntry::new("asset2")];
 
-            // Mock the CACHED_ASSETS load
-            CACHED_ASSETS.save(
-                deps.as_mut().storage,
-                "asset1".to_string(),
-                &Uint128::from(50u128),
-            )?;
-            CACHED_ASSETS.save(
-                deps.as_mut().storage,
-                "asset2".to_string(),
-                &Uint128::from(100u128),
-            )?;
-
             let result =
-                cached_asset_balance_differences(owned_assets, &deps.as_ref(), &pool_assets);
+                cached_asset_balance_differences(owned_assets, &cached_assets, &pool_assets);
 
             assert_that!(&result).is_err();
             Ok(())
         }
 
+        #[test]
+        fn unused_deposit_assets_leaves_out_spent_assets() -> anyhow::Result<()> {
+            let owned_assets = vec![asset1(50u128), asset2()];
+            let cached_assets = vec![asset1(100u128), Asset::new(asset2().info, 150u128)];
+            let pool_assets = vec![AssetEntry::new("asset1"), AssetEntry::new("asset2")];
+
+            let result = unused_deposit_assets(owned_assets, &cached_assets, &pool_assets);
+
+            assert_that!(result).is_equal_to(vec![AnsAsset::new("asset2".to_string(), 50u128)]);
+            Ok(())
+        }
+
         #[test]
         fn cached_asset_balance_differences_empty_assets() {
             let owned_assets = vec![];
-            let deps = mock_dependencies();
             let pool_assets = vec![];
 
-            let result =
-                cached_asset_balance_differences(owned_assets, &deps.as_ref(), &pool_assets);
+            let result = cached_asset_balance_differences(owned_assets, &[], &pool_assets);
 
             assert_that!(&result).is_ok().is_empty();
         }
+
+        #[test]
+        fn uncompounded_pool_assets_leave_out_harvested_rewards() {
+            let held_assets = vec![asset1(1000u128), asset2()];
+            let pool_assets = vec![AssetEntry::new("asset1"), AssetEntry::new("asset2")];
+            let harvested = vec![AnsAsset::new("asset1", 300u128)];
+            let sent = vec![Asset::new(asset2().info, 50u128)];
+
+            let result = uncompounded_pool_assets(held_assets, &pool_assets, &harvested, &sent);
+
+            assert_that!(result)
+                .is_equal_to(vec![asset1(700u128), Asset::new(asset2().info, 150u128)]);
+        }
     }
 
     #[cfg(test)]
@@ -689,4 +1171,200 @@ mod test {
             assert_that!(&fees).is_empty();
         }
     }
+
+    mod split_pro_rata {
+        use super::*;
+
+        #[test]
+        fn splits_by_lp_tokens() {
+            let funds = vec![
+                AnsAsset::new("asset1", 300u128),
+                AnsAsset::new("asset2", 30u128),
+            ];
+            let payouts = vec![
+                (Addr::unchecked("alice"), Uint128::new(200)),
+                (Addr::unchecked("bob"), Uint128::new(100)),
+            ];
+
+            let split = split_pro_rata(&funds, &payouts);
+
+            assert_that!(split).is_equal_to(vec![
+                (
+                    Addr::unchecked("alice"),
+                    vec![
+                        AnsAsset::new("asset1", 200u128),
+                        AnsAsset::new("asset2", 20u128),
+                    ],
+                ),
+                (
+                    Addr::unchecked("bob"),
+                    vec![
+                        AnsAsset::new("asset1", 100u128),
+                        AnsAsset::new("asset2", 10u128),
+                    ],
+                ),
+            ]);
+        }
+
+        #[test]
+        fn leaves_out_owners_without_funds() {
+            let funds = vec![AnsAsset::new("asset1", 1u128)];
+            let payouts = vec![
+                (Addr::unchecked("alice"), Uint128::new(1)),
+                (Addr::unchecked("bob"), Uint128::new(1)),
+            ];
+
+            assert_that!(split_pro_rata(&funds, &payouts)).is_empty();
+            assert_that!(split_pro_rata(&funds, &[])).is_empty();
+        }
+    }
+
+    mod vault_token_allocations {
+        use super::*;
+
+        #[test]
+        fn splits_batch_deposits_by_share() -> anyhow::Result<()> {
+            let batch = vec![
+                (Addr::unchecked("alice"), Decimal::percent(70)),
+                (Addr::unchecked("bob"), Decimal::zero()),
+                (Addr::unchecked("carol"), Decimal::percent(30)),
+            ];
+
+            let allocations = vault_token_allocations(None, batch, Uint128::new(1_001))?;
+
+            assert_that!(allocations).is_equal_to(vec![
+                (Addr::unchecked("alice"), Uint128::new(700)),
+                (Addr::unchecked("carol"), Uint128::new(301)),
+            ]);
+            Ok(())
+        }
+
+        #[test]
+        fn mints_to_single_recipient() -> anyhow::Result<()> {
+            let allocations =
+                vault_token_allocations(Some(Addr::unchecked("alice")), vec![], Uint128::new(100))?;
+            assert_that!(allocations)
+                .is_equal_to(vec![(Addr::unchecked("alice"), Uint128::new(100))]);
+            assert_that!(vault_token_allocations(None, vec![], Uint128::new(100))).is_err();
+            Ok(())
+        }
+    }
+
+    mod netting {
+        use super::*;
+        use cosmwasm_std::testing::mock_dependencies;
+
+        fn fee_config(withdrawal: u64) -> FeeConfig {
+            FeeConfig {
+                performance: Decimal::zero(),
+                deposit: Decimal::zero(),
+                withdrawal: Decimal::percent(withdrawal),
+                fee_collector_addr: Addr::unchecked("fee_collector"),
+            }
+        }
+
+        #[test]
+        fn fills_claims_that_fit() -> anyhow::Result<()> {
+            let mut deps = mock_dependencies();
+            let storage = deps.as_mut().storage;
+            PENDING_CLAIMS.save(storage, Addr::unchecked("alice"), &Uint128::new(1_000))?;
+            PENDING_CLAIMS.save(storage, Addr::unchecked("bob"), &Uint128::new(10_000))?;
+            PENDING_CLAIMS.save(storage, Addr::unchecked("carol"), &Uint128::new(500))?;
+
+            // share price of ~1 lp token per 10 vault tokens
+            let (filled_lp, burn, payouts) = fill_pending_claims(
+                deps.as_mut().storage,
+                &fee_config(0),
+                Uint128::new(200),
+                Uint128::new(10_000),
+                Uint128::new(100_000),
+            )?;
+
+            assert_that!(filled_lp).is_equal_to(Uint128::new(150));
+            assert_that!(burn).is_equal_to(Uint128::new(1_500));
+            assert_that!(payouts).is_equal_to(vec![
+                (Addr::unchecked("alice"), Uint128::new(100)),
+                (Addr::unchecked("carol"), Uint128::new(50)),
+            ]);
+            // bob's claim does not fit and stays pending
+            let pending = PENDING_CLAIMS
+                .keys(deps.as_ref().storage, None, None, Order::Ascending)
+                .collect::<StdResult<Vec<Addr>>>()?;
+            assert_that!(pending).is_equal_to(vec![Addr::unchecked("bob")]);
+            Ok(())
+        }
+
+        #[test]
+        fn deducts_withdrawal_fee() -> anyhow::Result<()> {
+            let mut deps = mock_dependencies();
+            PENDING_CLAIMS.save(
+                deps.as_mut().storage,
+                Addr::unchecked("alice"),
+                &Uint128::new(1_000),
+            )?;
+
+            let (filled_lp, burn, _) = fill_pending_claims(
+                deps.as_mut().storage,
+                &fee_config(10),
+                Uint128::new(1_000),
+                Uint128::new(1_000),
+                Uint128::new(1_000),
+            )?;
+
+            assert_that!(burn).is_equal_to(Uint128::new(1_000));
+            assert_that!(filled_lp).is_equal_to(Uint128::new(892));
+            Ok(())
+        }
+    }
+
+    mod deposit_compound {
+        use super::*;
+        use crate::contract::{DEPOSIT_COMPOUND_REPLY_ID, LP_PROVISION_REPLY_ID, REPLY_ID_BITS};
+        use cosmwasm_std::coins;
+
+        #[test]
+        fn deposit_caches_the_pool_assets_left_by_the_compound() -> anyhow::Result<()> {
+            let mut deps = app_init(false, true);
+            CONFIG.save(deps.as_mut().storage, &min_cooldown_config(None, false))?;
+            let depositor = Addr::unchecked("depositor");
+
+            let deposit_id = save_reply_context(
+                deps.as_mut().storage,
+                ReplyContext {
+                    depositor: Some(depositor.clone()),
+                    cached_assets: vec![Asset::native("eur", 0u128), Asset::native("usd", 0u128)],
+                    ..Default::default()
+                },
+                LP_PROVISION_REPLY_ID,
+            )?;
+            let compound_id = save_reply_context(
+                deps.as_mut().storage,
+                ReplyContext {
+                    deposit_submsg_id: Some(deposit_id),
+                    ..Default::default()
+                },
+                DEPOSIT_COMPOUND_REPLY_ID,
+            )?;
+
+            // the compound left a skipped pool asset reward in the account
+            deps.querier.update_balance(TEST_PROXY, coins(500, "eur"));
+            let reply = Reply {
+                id: compound_id,
+                result: SubMsgResult::Ok(SubMsgResponse {
+                    events: vec![],
+                    data: None,
+                }),
+            };
+            deposit_compound_reply(deps.as_mut(), mock_env(), AUTOCOMPOUNDER_APP, reply)?;
+
+            let deposit =
+                REPLY_CONTEXTS.load(deps.as_ref().storage, deposit_id >> REPLY_ID_BITS)?;
+            assert_that!(deposit.depositor).is_equal_to(Some(depositor));
+            assert_that!(deposit.cached_assets).is_equal_to(vec![
+                Asset::native("eur", 500u128),
+                Asset::native("usd", 0u128),
+            ]);
+            Ok(())
+        }
+    }
 }
@@ -1,3 +1,4 @@
+use crate::error::AutocompounderError;
 /// This file contains the manually written protobuf encoding for the kujira token factory messages.
 /// The protobuf file can be found here: https://github.com/Team-Kujira/core/blob/master/proto/denom/tx.proto
 /// A mapping of the typeUrls can be found here: https://github.com/Team-Kujira/kujira.js/blob/master/src/kujira/kujira.denom/index.ts
@@ -7,40 +8,188 @@
 /// Hans, [21 Aug 2023 at 16:21:07]: subdenom in the custom bindings maps to the nonce parameter in MsgCreateDenom https://github.com/Team-Kujira/core/blob/master/x/denom/wasm/interface_msg.go#L74
 use anybuf::Anybuf;
 use cosmwasm_std::{
-    from_json, to_json_binary, Addr, Binary, Coin, CosmosMsg, Deps, Empty, QueryRequest, StdError,
-    Uint128,
+    coins, Addr, BankMsg, Binary, Coin, CosmosMsg, Deps, QueryRequest, StdError, Uint128,
 };
 use serde::{Deserialize, Serialize};
 
 pub const SUPPLY_OF_PATH: &str = "/cosmos.bank.v1beta1.Query/SupplyOf";
+pub const DENOM_METADATA_PATH: &str = "/cosmos.bank.v1beta1.Query/DenomMetadata";
 
-/// create as functions with kujira replaced as variable
-pub fn msg_create_denom_type_url(chain: &str) -> String {
-    tokenfactory_prefix_for(chain) + "MsgCreateDenom"
-}
+/// Osmosis pool shares are named `gamm/pool/{pool_id}` and have 18 decimals
+pub const OSMOSIS_POOL_SHARE_PREFIX: &str = "gamm/pool/";
+pub const OSMOSIS_POOL_SHARE_DECIMALS: u8 = 18;
 
-pub fn msg_mint_type_url(chain: &str) -> String {
-    tokenfactory_prefix_for(chain) + "MsgMint"
+/// The tokenfactory flavours of the chains the vault is deployed to.
+/// The modules are forks of each other, but differ in their proto package, the fields of `MsgMint` and the creation fee params.
+#[cosmwasm_schema::cw_serde]
+#[derive(Copy, Eq)]
+pub enum TokenFactory {
+    /// The kujira `denom` module
+    Kujira,
+    Osmosis,
+    Juno,
+    /// Neutron runs the osmosis tokenfactory module
+    Neutron,
+    Injective,
+    Sei,
+    /// Terra runs the osmosis tokenfactory module
+    Terra,
 }
 
-pub fn msg_burn_type_url(chain: &str) -> String {
-    tokenfactory_prefix_for(chain) + "MsgBurn"
-}
+impl TokenFactory {
+    /// Determines the tokenfactory from the bech32 prefix of an address on the chain, like the contract address.
+    pub fn from_address(address: &Addr) -> Result<Self, AutocompounderError> {
+        let prefix = address
+            .as_str()
+            .rsplit_once('1')
+            .map(|(prefix, _)| prefix)
+            .unwrap_or_default();
+        match prefix {
+            "kujira" => Ok(TokenFactory::Kujira),
+            "osmo" => Ok(TokenFactory::Osmosis),
+            "juno" => Ok(TokenFactory::Juno),
+            "neutron" => Ok(TokenFactory::Neutron),
+            "inj" => Ok(TokenFactory::Injective),
+            "sei" => Ok(TokenFactory::Sei),
+            "terra" => Ok(TokenFactory::Terra),
+            _ => Err(AutocompounderError::UnsupportedTokenFactory {
+                address: address.clone(),
+            }),
+        }
+    }
 
-pub fn tokenfactory_prefix_for(chain: &str) -> String {
-    match chain {
-        "kujira" => "/kujira.denom.".to_string(),
-        "osmosis" => "/osmosis.tokenfactory.v1beta1.".to_string(),
-        "wyndex" => "/juno.tokenfactory.v1beta1.".to_string(),
-        _ => panic!("chain {:} not supported", chain),
+    /// Proto package prefix of the messages and queries of the module
+    pub fn type_url_prefix(&self) -> &'static str {
+        match self {
+            TokenFactory::Kujira => "/kujira.denom.",
+            TokenFactory::Osmosis | TokenFactory::Neutron | TokenFactory::Terra => {
+                "/osmosis.tokenfactory.v1beta1."
+            }
+            TokenFactory::Juno => "/juno.tokenfactory.v1beta1.",
+            TokenFactory::Injective => "/injective.tokenfactory.v1beta1.",
+            TokenFactory::Sei => "/seiprotocol.seichain.tokenfactory.",
+        }
     }
-}
 
-pub fn denom_params_path(chain: &str) -> String {
-    tokenfactory_prefix_for(chain) + "Query/Params"
-}
+    pub fn msg_create_denom_type_url(&self) -> String {
+        self.type_url_prefix().to_string() + "MsgCreateDenom"
+    }
 
-pub const TOKEN_FACTORY_CREATION_FEE: u128 = 100_000_000u128;
+    pub fn msg_mint_type_url(&self) -> String {
+        self.type_url_prefix().to_string() + "MsgMint"
+    }
+
+    pub fn msg_burn_type_url(&self) -> String {
+        self.type_url_prefix().to_string() + "MsgBurn"
+    }
+
+    pub fn msg_change_admin_type_url(&self) -> String {
+        self.type_url_prefix().to_string() + "MsgChangeAdmin"
+    }
+
+    pub fn msg_set_denom_metadata_type_url(&self) -> String {
+        self.type_url_prefix().to_string() + "MsgSetDenomMetadata"
+    }
+
+    pub fn params_path(&self) -> String {
+        self.type_url_prefix().to_string() + "Query/Params"
+    }
+
+    /// Whether the denom admin can set the bank metadata of a denom. The kujira denom module has no such message.
+    pub fn supports_denom_metadata(&self) -> bool {
+        !matches!(self, TokenFactory::Kujira)
+    }
+
+    /// Whether `MsgMint` has a recipient field. Otherwise the tokens are minted to the sender.
+    pub fn supports_mint_recipient(&self) -> bool {
+        !matches!(self, TokenFactory::Injective | TokenFactory::Sei)
+    }
+
+    /// Whether the module charges a fee for creating a denom. Sei only consumes gas.
+    pub fn has_creation_fee(&self) -> bool {
+        !matches!(self, TokenFactory::Sei)
+    }
+
+    /// max length of subdenom for osmosis and its forks is 44 https://github.com/osmosis-labs/osmosis/blob/6a53f5611ae27b653a5758333c9a0862835917f4/x/tokenfactory/types/denoms.go#L10-L36
+    pub fn max_subdenom_length(&self) -> usize {
+        match self {
+            TokenFactory::Kujira => MAX_SUBDENOM_LEN_KUJIRA,
+            _ => MAX_SUBDENOM_LEN_OSMOSIS,
+        }
+    }
+
+    pub fn create_denom_msg(&self, minter: String, subdenom: String) -> CosmosMsg {
+        CosmosMsg::Stargate {
+            type_url: self.msg_create_denom_type_url(),
+            value: encode_msg_create_denom(&minter, &subdenom).into(),
+        }
+    }
+
+    /// Creates the messages to mint `amount` of `denom` to `recipient`.
+    /// Chains without a mint recipient mint to the minter, which then sends the tokens on.
+    pub fn mint_msgs(
+        &self,
+        minter: &Addr,
+        denom: String,
+        amount: Uint128,
+        recipient: &str,
+    ) -> Vec<CosmosMsg> {
+        if self.supports_mint_recipient() {
+            return vec![CosmosMsg::Stargate {
+                type_url: self.msg_mint_type_url(),
+                value: Binary(encode_msg_mint(minter.as_str(), &denom, amount, recipient)),
+            }];
+        }
+        let mut msgs = vec![CosmosMsg::Stargate {
+            type_url: self.msg_mint_type_url(),
+            value: Binary(encode_msg_mint_to_sender(minter.as_str(), &denom, amount)),
+        }];
+        if recipient != minter.as_str() {
+            msgs.push(CosmosMsg::Bank(BankMsg::Send {
+                to_address: recipient.to_string(),
+                amount: coins(amount.u128(), denom),
+            }));
+        }
+        msgs
+    }
+
+    pub fn burn_msg(&self, minter: &Addr, denom: String, amount: Uint128) -> CosmosMsg {
+        CosmosMsg::Stargate {
+            type_url: self.msg_burn_type_url(),
+            value: Binary(encode_msg_burn(minter.as_str(), &denom, amount)),
+        }
+    }
+
+    pub fn change_admin_msg(&self, sender: &Addr, denom: String, new_admin: &Addr) -> CosmosMsg {
+        CosmosMsg::Stargate {
+            type_url: self.msg_change_admin_type_url(),
+            value: Binary(encode_msg_change_admin(
+                sender.as_str(),
+                &denom,
+                new_admin.as_str(),
+            )),
+        }
+    }
+
+    pub fn set_denom_metadata_msg(&self, sender: &Addr, metadata: &DenomMetadata) -> CosmosMsg {
+        CosmosMsg::Stargate {
+            type_url: self.msg_set_denom_metadata_type_url(),
+            value: Binary(encode_msg_set_denom_metadata(sender.as_str(), metadata)),
+        }
+    }
+
+    /// Queries the fee for creating a denom
+    pub fn query_creation_fee(&self, deps: Deps) -> Result<Vec<Coin>, StdError> {
+        if !self.has_creation_fee() {
+            return Ok(vec![]);
+        }
+        let response: ParamsResponse = deps.querier.query(&QueryRequest::Stargate {
+            path: self.params_path(),
+            data: encode_query_params().into(),
+        })?;
+        Ok(response.params.creation_fee)
+    }
+}
 
 /// Encodes a Kujira's MsgCreateDenom message to binary.
 /// Denom will be in the format: factory/{sender}/{`denom`}.
@@ -56,7 +205,7 @@ pub const TOKEN_FACTORY_CREATION_FEE: u128 = 100_000_000u128;
 ///   string nonce = 2 [ (gogoproto.moretags) = "yaml:\"nonce\"" ]; // unique nonce. Mapped by kujira to be the CreateSubDenom(?)
 /// }
 /// ```
-pub fn encode_msg_create_denom(sender: &str, denom: &str, _chain: &str) -> Vec<u8> {
+pub fn encode_msg_create_denom(sender: &str, denom: &str) -> Vec<u8> {
     Anybuf::new()
         .append_string(1, sender)
         .append_string(2, denom)
@@ -64,14 +213,6 @@ pub fn encode_msg_create_denom(sender: &str, denom: &str, _chain: &str) -> Vec<u
     // like from their docs: https://docs.kujira.app/developers/smart-contracts/token-factory#creation
 }
 
-pub fn tokenfactory_create_denom_msg(minter: String, subdenom: String, chain: &str) -> CosmosMsg {
-    let msg = encode_msg_create_denom(&minter, &subdenom, chain);
-    CosmosMsg::Stargate {
-        type_url: msg_create_denom_type_url(chain),
-        value: msg.into(),
-    }
-}
-
 /// // MsgMint is the sdk.Msg type for allowing an admin account to mint
 /// more of a token.
 /// ```ignore
@@ -83,6 +224,8 @@ pub fn tokenfactory_create_denom_msg(minter: String, subdenom: String, chain: &s
 ///   ];
 ///   string recipient = 3 [ (gogoproto.moretags) = "yaml:\"recipient\"" ];
 /// }
+/// ```
+/// The osmosis module and its forks call the third field `mintToAddress`. Injective and Sei have no third field.
 pub fn encode_msg_mint(sender: &str, denom: &str, amount: Uint128, recipient: &str) -> Vec<u8> {
     let coin = Anybuf::new()
         .append_string(1, denom)
@@ -95,20 +238,18 @@ pub fn encode_msg_mint(sender: &str, denom: &str, amount: Uint128, recipient: &s
         .into_vec()
 }
 
-pub fn tokenfactory_mint_msg(
-    minter: &Addr,
-    denom: String,
-    amount: Uint128,
-    recipient: &str,
-    chain: &str,
-) -> Result<CosmosMsg, StdError> {
-    let proto_msg = encode_msg_mint(minter.as_str(), denom.as_str(), amount, recipient);
-    let msg = CosmosMsg::Stargate {
-        type_url: msg_mint_type_url(chain),
-        value: Binary(proto_msg),
-    };
-    Ok(msg)
+/// Encodes a MsgMint message without recipient, as used by Injective and Sei.
+pub fn encode_msg_mint_to_sender(sender: &str, denom: &str, amount: Uint128) -> Vec<u8> {
+    let coin = Anybuf::new()
+        .append_string(1, denom)
+        .append_string(2, amount.to_string());
+
+    Anybuf::new()
+        .append_string(1, sender)
+        .append_message(2, &coin)
+        .into_vec()
 }
+
 /// // MsgBurn is the sdk.Msg type for allowing an admin account to burn
 /// // a token.  For now, we only support burning from the sender account.
 /// ```ignore
@@ -131,18 +272,78 @@ pub fn encode_msg_burn(sender: &str, denom: &str, amount: Uint128) -> Vec<u8> {
         .into_vec()
 }
 
-pub fn tokenfactory_burn_msg(
-    minter: &Addr,
-    denom: String,
-    amount: Uint128,
-    chain: &str,
-) -> Result<CosmosMsg, StdError> {
-    let proto_msg = encode_msg_burn(minter.as_str(), &denom, amount);
-    let msg = CosmosMsg::Stargate {
-        type_url: msg_burn_type_url(chain),
-        value: Binary(proto_msg),
+/// // MsgChangeAdmin is the sdk.Msg type for allowing an admin account to reassign
+/// // adminship of a denom to a new account
+/// ```ignore
+/// message MsgChangeAdmin {
+///   string sender = 1 [ (gogoproto.moretags) = "yaml:\"sender\"" ];
+///   string denom = 2 [ (gogoproto.moretags) = "yaml:\"denom\"" ];
+///   string newAdmin = 3 [ (gogoproto.moretags) = "yaml:\"new_admin\"" ];
+/// }
+/// ```
+/// The field layout is the same for all supported tokenfactory modules.
+pub fn encode_msg_change_admin(sender: &str, denom: &str, new_admin: &str) -> Vec<u8> {
+    Anybuf::new()
+        .append_string(1, sender)
+        .append_string(2, denom)
+        .append_string(3, new_admin)
+        .into_vec()
+}
+
+/// Bank metadata of a tokenfactory denom, with a base unit and a display unit of `exponent` decimals.
+/// Without decimals the base unit is the display unit.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DenomMetadata {
+    pub description: String,
+    pub base: String,
+    pub display: String,
+    pub exponent: u32,
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+}
+
+/// Encodes a MsgSetDenomMetadata message to binary.
+/// ```ignore
+/// message MsgSetDenomMetadata {
+///   string sender = 1 [ (gogoproto.moretags) = "yaml:\"sender\"" ];
+///   cosmos.bank.v1beta1.Metadata metadata = 2 [
+///     (gogoproto.moretags) = "yaml:\"metadata\"",
+///     (gogoproto.nullable) = false
+///   ];
+/// }
+/// ```
+/// The bank metadata is defined here: https://github.com/cosmos/cosmos-sdk/blob/v0.47.5/proto/cosmos/bank/v1beta1/bank.proto#L85-L124
+pub fn encode_msg_set_denom_metadata(sender: &str, metadata: &DenomMetadata) -> Vec<u8> {
+    let base_unit = Anybuf::new()
+        .append_string(1, &metadata.base)
+        .append_uint32(2, 0);
+
+    // denom_units is a repeated field, so every unit is appended with the same field number
+    let mut bank_metadata = Anybuf::new()
+        .append_string(1, &metadata.description)
+        .append_message(2, &base_unit);
+    // the bank module rejects two units with the same exponent
+    let display = if metadata.exponent == 0 {
+        &metadata.base
+    } else {
+        let display_unit = Anybuf::new()
+            .append_string(1, &metadata.display)
+            .append_uint32(2, metadata.exponent);
+        bank_metadata = bank_metadata.append_message(2, &display_unit);
+        &metadata.display
     };
-    Ok(msg)
+    let bank_metadata = bank_metadata
+        .append_string(3, &metadata.base)
+        .append_string(4, display)
+        .append_string(5, &metadata.name)
+        .append_string(6, &metadata.symbol)
+        .append_string(7, &metadata.uri);
+
+    Anybuf::new()
+        .append_string(1, sender)
+        .append_message(2, &bank_metadata)
+        .into_vec()
 }
 
 /// Encodes the stargate query message to get the total supply of a denom.
@@ -159,6 +360,49 @@ pub fn encode_query_supply_of(denom: &str) -> Vec<u8> {
     Anybuf::new().append_string(1, denom).into_vec()
 }
 
+/// Encodes the stargate query message to get the bank metadata of a denom.
+/// ```ignore
+/// // QueryDenomMetadataRequest is the request type for the Query/DenomMetadata RPC method.
+/// message QueryDenomMetadataRequest {
+///   // denom is the coin denom to query the metadata for.
+///   string denom = 1;
+/// }
+/// ```
+pub fn encode_query_denom_metadata(denom: &str) -> Vec<u8> {
+    Anybuf::new().append_string(1, denom).into_vec()
+}
+
+/// Unit of a denom, `exponent` is the power of 10 of the unit in base units
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BankDenomUnit {
+    pub denom: String,
+    #[serde(default)]
+    pub exponent: u32,
+}
+
+/// The bank metadata of a denom, as returned by the Query/DenomMetadata RPC method
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BankDenomMetadata {
+    #[serde(default)]
+    pub denom_units: Vec<BankDenomUnit>,
+    pub display: String,
+}
+
+impl BankDenomMetadata {
+    /// The exponent of the display unit
+    pub fn decimals(&self) -> Option<u8> {
+        self.denom_units
+            .iter()
+            .find(|unit| unit.denom == self.display)
+            .and_then(|unit| u8::try_from(unit.exponent).ok())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DenomMetadataResponse {
+    pub metadata: BankDenomMetadata,
+}
+
 /// Encodes the stargate query message to get the total supply of a denom.
 ///
 
@@ -166,7 +410,7 @@ pub fn encode_query_params() -> Vec<u8> {
     Anybuf::new().into_vec()
 }
 
-/// ParamsResponse is the response type for the Query/Params RPC method. https://github.com/Team-Kujira/core/blob/master/proto/denom/params.proto
+/// Params of the tokenfactory module, as returned by the Query/Params RPC method. https://github.com/Team-Kujira/core/blob/master/proto/denom/params.proto
 /// ```ignore
 /// // Params holds parameters for the denom module
 /// message Params {
@@ -177,28 +421,16 @@ pub fn encode_query_params() -> Vec<u8> {
 ///   ];
 /// }
 /// ```
-#[derive(Clone, PartialEq, Serialize, Deserialize)]
+/// The osmosis module and its forks name the fee `denom_creation_fee`, injective names it `denoms_creation_fee`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct Params {
-    pub creation_fee: Coin,
-}
-
-///
-///
-pub fn tokenfactory_params_query_request(chain: &str) -> Result<QueryRequest<Empty>, StdError> {
-    let proto_msg = encode_query_params();
-    let q_request: QueryRequest<Empty> = QueryRequest::Stargate {
-        path: denom_params_path(chain),
-        data: to_json_binary(&proto_msg)?,
-    };
-
-    Ok(q_request)
+    #[serde(default, alias = "denom_creation_fee", alias = "denoms_creation_fee")]
+    pub creation_fee: Vec<Coin>,
 }
 
-pub fn query_tokenfactory_params(deps: Deps, chain: &str) -> Result<Params, StdError> {
-    let q_request = tokenfactory_params_query_request(chain)?;
-    let response: Binary = deps.querier.query(&q_request)?;
-    let params_response: Params = from_json(response)?;
-    Ok(params_response)
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ParamsResponse {
+    pub params: Params,
 }
 
 /// Formats the native denom to the asset info for the vault token with denom "factory/{`sender`}/{`denom`}"
@@ -210,12 +442,3 @@ pub fn format_tokenfactory_denom(sender: &str, denom: &str) -> String {
 
 pub const MAX_SUBDENOM_LEN_OSMOSIS: usize = 44;
 pub const MAX_SUBDENOM_LEN_KUJIRA: usize = 64;
-
-/// max length of subdenom for osmosis is 44 https://github.com/osmosis-labs/osmosis/blob/6a53f5611ae27b653a5758333c9a0862835917f4/x/tokenfactory/types/denoms.go#L10-L36
-pub fn max_subdenom_length_for_chain(dex: &str) -> usize {
-    match dex {
-        "osmosis" => MAX_SUBDENOM_LEN_OSMOSIS,
-        "kujira" => MAX_SUBDENOM_LEN_KUJIRA,
-        _ => 64,
-    }
-}
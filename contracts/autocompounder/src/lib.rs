@@ -1,8 +1,10 @@
+pub mod claims_nft;
 pub mod contract;
 mod dependencies;
 pub mod error;
 mod handlers;
 pub mod msg;
+pub mod pending_rewards;
 pub mod response;
 pub mod state;
 
@@ -14,7 +16,9 @@ pub mod kujira_tx;
 
 #[cfg(test)]
 mod test_common {
-    use crate::msg::BondingData;
+    use crate::contract::AutocompounderResult;
+    use crate::kujira_tx::{Params, ParamsResponse, TokenFactory};
+    use crate::msg::{AutocompounderInstantiateMsg, BondingData};
 
     use abstract_cw_staking::msg::{
         StakeResponse, StakingInfo, StakingInfoResponse, StakingQueryMsg, StakingTarget,
@@ -35,7 +39,11 @@ mod test_common {
         MockDeps, MockQuerierBuilder,
     };
     pub use cosmwasm_std::testing::*;
-    use cosmwasm_std::{from_json, to_json_binary, Addr, Decimal, StdError, Uint128};
+    use cosmwasm_std::{
+        coins, from_json, to_json_binary, Addr, Coin, ContractResult, Decimal, Empty, OwnedDeps,
+        Querier, QuerierResult, QueryRequest, Response, StdError, SystemError, SystemResult,
+        Uint128,
+    };
     use cw_asset::AssetInfo;
     use cw_utils::Duration;
 
@@ -48,6 +56,100 @@ mod test_common {
     pub const SHORT_UNBONDING_PERIOD: Duration = Duration::Time(3600);
     pub const LONG_UNBONDING_PERIOD: Duration = Duration::Time(7200);
     pub const MAX_CLAIMS_PER_ADDRESS: u32 = 7;
+    pub const TEST_CREATION_FEE: u128 = 1_000_000;
+    pub const TEST_CREATION_FEE_DENOM: &str = "ujuno";
+
+    /// Answers the tokenfactory params query, which the mock querier doesn't support
+    pub struct TokenFactoryQuerier {
+        pub base: MockQuerier,
+        pub creation_fee: Vec<Coin>,
+    }
+
+    impl Querier for TokenFactoryQuerier {
+        fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
+            let request: QueryRequest<Empty> = match from_json(bin_request) {
+                Ok(request) => request,
+                Err(err) => {
+                    return SystemResult::Err(SystemError::InvalidRequest {
+                        error: err.to_string(),
+                        request: bin_request.into(),
+                    })
+                }
+            };
+            match request {
+                QueryRequest::Stargate { path, .. } if path.ends_with("Query/Params") => {
+                    let response = ParamsResponse {
+                        params: Params {
+                            creation_fee: self.creation_fee.clone(),
+                        },
+                    };
+                    SystemResult::Ok(ContractResult::Ok(to_json_binary(&response).unwrap()))
+                }
+                _ => self.base.raw_query(bin_request),
+            }
+        }
+    }
+
+    /// Instantiates the app on a chain that charges `creation_fee` for creating a denom
+    pub fn instantiate_with_creation_fee(
+        querier: MockQuerier,
+        module: AutocompounderInstantiateMsg,
+        creation_fee: Vec<Coin>,
+    ) -> AutocompounderResult<(MockDeps, Response)> {
+        let mut deps = OwnedDeps {
+            storage: MockStorage::default(),
+            api: MockApi::default(),
+            querier: TokenFactoryQuerier {
+                base: querier,
+                creation_fee,
+            },
+            custom_query_type: Default::default(),
+        };
+        let response = AUTOCOMPOUNDER_APP.instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(TEST_MODULE_FACTORY, &[]),
+            abstract_core::app::InstantiateMsg {
+                module,
+                base: abstract_core::app::BaseInstantiateMsg {
+                    ans_host_address: TEST_ANS_HOST.to_string(),
+                    version_control_address: TEST_VERSION_CONTROL.to_string(),
+                    account_base: AccountBase {
+                        manager: Addr::unchecked(TEST_MANAGER),
+                        proxy: Addr::unchecked(TEST_PROXY),
+                    },
+                },
+            },
+        )?;
+        let deps = OwnedDeps {
+            storage: deps.storage,
+            api: deps.api,
+            querier: deps.querier.base,
+            custom_query_type: Default::default(),
+        };
+        Ok((deps, response))
+    }
+
+    pub fn test_instantiate_msg(
+        bonding_data: Option<BondingData>,
+        vault_token_is_cw20: bool,
+    ) -> AutocompounderInstantiateMsg {
+        AutocompounderInstantiateMsg {
+            code_id: if vault_token_is_cw20 { Some(1) } else { None },
+            commission_addr: COMMISSION_RECEIVER.to_string(),
+            deposit_fees: Decimal::percent(3),
+            dex: WYNDEX.to_string(),
+            performance_fees: Decimal::percent(3),
+            pool_assets: vec!["eur".into(), "usd".into()],
+            withdrawal_fees: Decimal::percent(3),
+            bonding_data,
+            max_swap_spread: None,
+            claims_nft_code_id: None,
+            vault_token_metadata: None,
+            token_factory: (!vault_token_is_cw20).then_some(TokenFactory::Juno),
+            decimal_offset: None,
+        }
+    }
 
     // Mock Querier with a smart-query handler for the module factory
     // Because that query is performed when the App is instantiated to get the manager's address and set it as the Admin
@@ -118,6 +220,16 @@ mod test_common {
                 .unwrap()),
                 _ => panic!("unexpected message"),
             })
+            .with_smart_handler("usd_eur_lp", |msg| match from_json(msg).unwrap() {
+                cw20::Cw20QueryMsg::TokenInfo {} => Ok(to_json_binary(&cw20::TokenInfoResponse {
+                    name: "usd_eur_lp".to_string(),
+                    symbol: "uelp".to_string(),
+                    decimals: 18,
+                    total_supply: Uint128::new(1000),
+                })
+                .unwrap()),
+                _ => panic!("unexpected message"),
+            })
             .with_raw_handler(TEST_ANS_HOST, |key| match key {
                 "\0\u{6}assetseur_usd_lp" => {
                     Ok(to_json_binary(&AssetInfo::cw20(Addr::unchecked("eur_usd_lp"))).unwrap())
@@ -243,6 +355,16 @@ mod test_common {
                 }
                 _ => panic!("unexpected message"),
             })
+            .with_smart_handler("usd_eur_lp", |msg| match from_json(msg).unwrap() {
+                cw20::Cw20QueryMsg::TokenInfo {} => Ok(to_json_binary(&cw20::TokenInfoResponse {
+                    name: "usd_eur_lp".to_string(),
+                    symbol: "uelp".to_string(),
+                    decimals: 18,
+                    total_supply: Uint128::new(1000),
+                })
+                .unwrap()),
+                _ => panic!("unexpected message"),
+            })
             .with_raw_handler(TEST_ANS_HOST, |key| match key {
                 "\0\u{6}assetseur" => Ok(to_json_binary(&AssetInfo::Native("eur".into())).unwrap()),
                 "\0\nrev_assets\0\u{7}native:eur" => {
@@ -294,49 +416,30 @@ mod test_common {
     }
 
     pub fn app_init(is_unbonding_period_enabled: bool, vault_token_is_cw20: bool) -> MockDeps {
-        let mut deps = mock_dependencies();
-        let info = mock_info(TEST_MODULE_FACTORY, &[]);
-
-        let bonding_data: Option<BondingData>;
-        if is_unbonding_period_enabled {
-            deps.querier = app_base_mock_querier_with_unbonding_period().build();
-            bonding_data = Some(BondingData {
+        let (mut querier, bonding_data) = if is_unbonding_period_enabled {
+            let bonding_data = BondingData {
                 unbonding_period: SHORT_UNBONDING_PERIOD,
                 max_claims_per_address: Some(MAX_CLAIMS_PER_ADDRESS),
-            });
+            };
+            (
+                app_base_mock_querier_with_unbonding_period().build(),
+                Some(bonding_data),
+            )
         } else {
-            deps.querier = app_base_mock_querier().build();
-            bonding_data = None;
-        }
+            (app_base_mock_querier().build(), None)
+        };
 
-        AUTOCOMPOUNDER_APP
-            .instantiate(
-                deps.as_mut(),
-                mock_env(),
-                info,
-                abstract_core::app::InstantiateMsg {
-                    module: crate::msg::AutocompounderInstantiateMsg {
-                        code_id: if vault_token_is_cw20 { Some(1) } else { None },
-                        commission_addr: COMMISSION_RECEIVER.to_string(),
-                        deposit_fees: Decimal::percent(3),
-                        dex: WYNDEX.to_string(),
-                        performance_fees: Decimal::percent(3),
-                        pool_assets: vec!["eur".into(), "usd".into()],
-                        withdrawal_fees: Decimal::percent(3),
-                        bonding_data,
-                        max_swap_spread: None,
-                    },
-                    base: abstract_core::app::BaseInstantiateMsg {
-                        ans_host_address: TEST_ANS_HOST.to_string(),
-                        version_control_address: TEST_VERSION_CONTROL.to_string(),
-                        account_base: AccountBase {
-                            manager: Addr::unchecked(TEST_MANAGER),
-                            proxy: Addr::unchecked(TEST_PROXY),
-                        },
-                    },
-                },
-            )
-            .unwrap();
+        // the creation fee is sent along with the instantiation
+        let creation_fee = coins(TEST_CREATION_FEE, TEST_CREATION_FEE_DENOM);
+        querier.update_balance(MOCK_CONTRACT_ADDR, creation_fee.clone());
+        let (mut deps, _) = instantiate_with_creation_fee(
+            querier,
+            test_instantiate_msg(bonding_data, vault_token_is_cw20),
+            creation_fee,
+        )
+        .unwrap();
+        // and charged when the denom is created
+        deps.querier.update_balance(MOCK_CONTRACT_ADDR, vec![]);
 
         deps
     }
@@ -28,6 +28,8 @@
 //! ## Migration
 //! Migrating this contract is done by calling `ExecuteMsg::Upgrade` on [`crate::manager`] with `crate::AUTOCOMPOUNDER` as module.
 
+use crate::claims_nft::Cw721ReceiveMsg;
+use crate::kujira_tx::TokenFactory;
 use abstract_app::objects::AnsAsset;
 use abstract_core::objects::{AnsEntryConvertor, LpToken};
 use abstract_sdk::core::app;
@@ -35,8 +37,12 @@ use abstract_sdk::core::objects::{AssetEntry, DexName, PoolAddress, PoolMetadata
 use cosmwasm_schema::QueryResponses;
 use cosmwasm_std::{Addr, Decimal, Uint128};
 use cw20::Cw20ReceiveMsg;
-use cw_asset::{AssetInfo, AssetInfoBase};
+use cw_asset::{Asset, AssetInfo, AssetInfoBase};
 use cw_utils::{Duration, Expiration};
+use serde::de::value::MapAccessDeserializer;
+use serde::de::{self, DeserializeSeed, IgnoredAny, IntoDeserializer, MapAccess, Visitor};
+use serde::{Deserialize, Deserializer};
+use std::fmt;
 
 pub const AUTOCOMPOUNDER: &str = "autocompounder";
 pub const AUTOCOMPOUNDER_ID: &str = "4t2:autocompounder";
@@ -74,6 +80,39 @@ pub struct AutocompounderInstantiateMsg {
     pub bonding_data: Option<BondingData>,
     /// max swap spread
     pub max_swap_spread: Option<Decimal>,
+    /// cw721 code id. If set, unbonding claims are minted as NFTs to the redeemer
+    pub claims_nft_code_id: Option<u64>,
+    /// Name, symbol, decimals and marketing info of the vault token
+    pub vault_token_metadata: Option<VaultTokenMetadata>,
+    /// Tokenfactory for native vault tokens. Defaults to the chain of the contract address
+    pub token_factory: Option<TokenFactory>,
+    /// Decimal offset of the virtual shares that protect against inflation attacks.
+    /// Every lp token is worth 10^`decimal_offset` vault tokens at the start. Defaults to 1
+    pub decimal_offset: Option<u32>,
+}
+
+/// Metadata of the vault token. Fields that are not set fall back to the defaults.
+#[cosmwasm_schema::cw_serde]
+#[derive(Default)]
+pub struct VaultTokenMetadata {
+    /// Defaults to the subdenom of the vault token
+    pub name: Option<String>,
+    /// Defaults to [`VAULT_TOKEN_SYMBOL`](crate::state::VAULT_TOKEN_SYMBOL)
+    pub symbol: Option<String>,
+    /// Defaults to the decimals of the lp token. Required if they can't be queried
+    pub decimals: Option<u8>,
+    pub marketing: Option<VaultTokenMarketing>,
+}
+
+/// Marketing info of the vault token, shown by wallets and explorers
+#[cosmwasm_schema::cw_serde]
+#[derive(Default)]
+pub struct VaultTokenMarketing {
+    pub project: Option<String>,
+    pub description: Option<String>,
+    /// Address that can update the marketing info of a cw20 vault token
+    pub marketing: Option<String>,
+    pub logo_url: Option<String>,
 }
 
 #[cosmwasm_schema::cw_serde]
@@ -93,17 +132,46 @@ pub enum AutocompounderExecuteMsg {
         recipient: Option<Addr>,
         max_spread: Option<Decimal>,
     },
+    /// Deposit for many recipients at once. Liquidity is provided for the total and every recipient
+    /// receives vault tokens in proportion to their deposit. Every deposit must be in the asset ratio
+    /// of the total. Requires approval for cw20 tokens
+    #[cfg_attr(feature = "interface", payable)]
+    BatchDeposit {
+        deposits: Vec<(Addr, Vec<AnsAsset>)>,
+        max_spread: Option<Decimal>,
+    },
     /// Deposit LP tokens. Requires approval for cw20 tokens
     DepositLp {
         lp_token: AnsAsset,
         recipient: Option<Addr>,
     },
+    /// Redeem vault tokens of the sender, or of `owner` if the sender is an approved operator.
+    /// The vault tokens are taken from the owner, which requires approval for cw20 vault tokens.
     Redeem {
         amount: Uint128,
+        owner: Option<Addr>,
         recipient: Option<Addr>,
     },
-    /// Withdraw all unbonded funds
-    Withdraw {},
+    /// Withdraw all unbonded funds of the sender, or of `owner` if the sender is an approved operator.
+    /// If claims are tokenised, pays out the matured claim NFTs held by the owner. Requires approval of the NFTs,
+    /// without it a claim NFT can be sent to the autocompounder with [`Cw721HookMsg::Withdraw`].
+    Withdraw {
+        owner: Option<Addr>,
+    },
+    /// Cancel the pending claim of the sender, or of `owner` if the sender is an approved operator.
+    /// The vault tokens of the claim are returned to the owner.
+    CancelPendingClaim {
+        owner: Option<Addr>,
+    },
+    /// Allow `operator` to redeem, withdraw and cancel pending claims on behalf of the sender
+    ApproveOperator {
+        operator: String,
+        expires: Option<Expiration>,
+    },
+    /// Revoke the approval of `operator`
+    RevokeOperator {
+        operator: String,
+    },
     /// Compound all rewards in the vault
     Compound {},
     /// Unbond in batches
@@ -111,10 +179,78 @@ pub enum AutocompounderExecuteMsg {
         start_after: Option<String>,
         limit: Option<u32>,
     },
+    /// Withdraw the matured claims of up to `limit` claim owners and send every owner their assets.
+    /// Tokenised claims are not processed, their holders withdraw them so that the claim NFTs are burned.
+    ProcessMaturedClaims {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
     // Updates min_unbonding_cooldown and unbonding_period in the config with the latest staking contract data
     UpdateStakingConfig {
         bonding_data: Option<BondingData>,
     },
+    /// Update the liquidity buffer of a bonding vault
+    UpdateLiquidityBuffer {
+        target: Decimal,
+        instant_exit_fee: Decimal,
+    },
+    /// Unstake all lp tokens and stop the vault. Holders can exit through `EmergencyRedeem`.
+    /// Liquidity can only be withdrawn for vaults without unbonding period.
+    EmergencyShutdown {
+        withdraw_liquidity: bool,
+    },
+    /// Burn vault tokens for their share of the vault after an emergency shutdown.
+    /// Includes the pending claim of the sender.
+    #[cfg_attr(feature = "interface", payable)]
+    EmergencyRedeem {
+        amount: Uint128,
+    },
+    /// Set or remove the guardian that may pause the vault
+    UpdateGuardian {
+        guardian: Option<String>,
+    },
+    /// Pause or unpause operations of the vault. Fields that are not set are left unchanged.
+    /// The guardian can only pause.
+    UpdatePause {
+        deposits: Option<bool>,
+        redeems: Option<bool>,
+        withdrawals: Option<bool>,
+        compounding: Option<bool>,
+    },
+    /// Update the deposit caps of the vault. Caps that are not set are removed.
+    UpdateDepositCaps {
+        max_total_lp: Option<Uint128>,
+        max_vault_tokens_per_address: Option<Uint128>,
+    },
+    /// Set who can deposit into the vault
+    UpdateAccessMode {
+        access_mode: AccessMode,
+    },
+    /// Add and remove allowed depositors
+    UpdateDepositors {
+        to_add: Vec<String>,
+        to_remove: Vec<String>,
+    },
+    /// Compound the pending rewards before every deposit, so new shares don't capture rewards accrued before they joined.
+    /// A deposit still goes through when its compound fails.
+    UpdateCompoundOnDeposit {
+        enabled: bool,
+    },
+    /// Hand the denom admin (or cw20 minter) of the vault token to `new_admin`, e.g. a module that replaces this one.
+    /// This vault can no longer mint or burn vault tokens afterwards.
+    ChangeVaultTokenAdmin {
+        new_admin: String,
+    },
+    /// Send stray tokens (airdrops, mistaken transfers) held by the proxy to `recipient`.
+    /// The lp token, the pool assets and the vault token can't be swept.
+    Sweep {
+        assets: Vec<Asset>,
+        recipient: String,
+    },
+    /// Set which reward assets are compounded. Core assets are always compounded and can't be listed
+    UpdateRewardFilter {
+        reward_filter: RewardFilter,
+    },
 }
 
 #[cosmwasm_schema::cw_serde]
@@ -141,7 +277,7 @@ pub enum AutocompounderQueryMsg {
         start_after: Option<Addr>,
         limit: Option<u8>,
     },
-    /// Query the amount of claims
+    /// Query the claims of an address, or the claims of the claim NFTs it holds if claims are tokenised
     /// Returns [`Vec<Claim>`]
     #[returns(Vec<Claim>)]
     Claims { address: Addr },
@@ -172,13 +308,159 @@ pub enum AutocompounderQueryMsg {
     /// Returns [`Uint128`]
     #[returns(Uint128)]
     Balance { address: Addr },
+    /// Query the address of the claims NFT contract
+    /// Returns [`Option<Addr>`]
+    #[returns(Option<Addr>)]
+    ClaimsNft {},
+    /// Query the claim represented by a claims NFT
+    /// Returns [`Claim`]
+    #[returns(Claim)]
+    NftClaim { token_id: String },
+    /// Query the liquidity buffer of the vault
+    /// Returns [`LiquidityBufferResponse`]
+    #[returns(LiquidityBufferResponse)]
+    LiquidityBuffer {},
+    /// Query the emergency shutdown state of the vault
+    /// Returns [`Option<EmergencyShutdownState>`]
+    #[returns(Option<EmergencyShutdownState>)]
+    EmergencyShutdown {},
+    /// Returns [`PauseResponse`]
+    #[returns(PauseResponse)]
+    Pause {},
+    /// Returns [`DepositCaps`]
+    #[returns(DepositCaps)]
+    DepositCaps {},
+    /// Query the amount of lp tokens that `address` can still deposit, before deposit fees.
+    /// Returns [`Uint128`]
+    #[returns(Uint128)]
+    MaxDeposit { address: Addr },
+    /// Returns [`AccessMode`]
+    #[returns(AccessMode)]
+    AccessMode {},
+    /// Query the allowed depositors
+    /// Returns [`Vec<Addr>`]
+    #[returns(Vec<Addr>)]
+    Depositors {
+        start_after: Option<Addr>,
+        limit: Option<u8>,
+    },
+    /// Query the expiration of the approval of `operator` by `owner`
+    /// Returns [`Option<Expiration>`]
+    #[returns(Option<Expiration>)]
+    Operator { owner: Addr, operator: Addr },
+    /// Query whether rewards are compounded before every deposit
+    /// Returns [`bool`]
+    #[returns(bool)]
+    CompoundOnDeposit {},
+    /// Query the balances of the proxy that are not core assets of the vault and can be swept.
+    /// Cw20 balances can't be enumerated, only the cw20 reward tokens and the cw20 tokens of the [`RewardFilter`] are included.
+    /// Returns [`Vec<Asset>`]
+    #[returns(Vec<Asset>)]
+    SweepableBalances {},
+    /// Returns [`RewardFilter`]
+    #[returns(RewardFilter)]
+    RewardFilter {},
+    /// Query the reward tokens of all reward pools of the lp token, that are compounded by the vault.
+    /// Rewards denied by the [`RewardFilter`] and rewards that are not registered in ANS are left out.
+    /// Returns [`Vec<AssetInfo>`]
+    #[returns(Vec<AssetInfo>)]
+    RewardTokens {},
+    /// Preview a compound of the rewards that are pending at the staking contract, without executing it.
+    /// Returns [`PreviewCompoundResponse`]
+    #[returns(PreviewCompoundResponse)]
+    PreviewCompound {},
+}
+
+/// Messages that can be attached to a cw20 `Send` to the autocompounder
+#[cosmwasm_schema::cw_serde]
+pub enum Cw20HookMsg {
+    /// Redeem the sent cw20 vault tokens
+    Redeem { recipient: Option<Addr> },
+    /// Deposit the sent cw20 pool asset
+    Deposit {
+        recipient: Option<Addr>,
+        max_spread: Option<Decimal>,
+    },
+    /// Deposit the sent cw20 lp tokens
+    DepositLp { recipient: Option<Addr> },
+}
+
+/// Messages that can be attached to a cw721 `SendNft` of a claim NFT to the autocompounder
+#[cosmwasm_schema::cw_serde]
+pub enum Cw721HookMsg {
+    /// Withdraw the matured claim of the sent NFT to its previous holder and burn the NFT.
+    /// Unlike `Withdraw`, no approval of the NFT is needed.
+    Withdraw {},
+}
+
+/// Message of the execute entry point: an app message, or a claim NFT that is sent with the cw721 `SendNft`.
+/// cw721 contracts call `receive_nft`, which is not an endpoint of the app messages.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ContractExecuteMsg {
+    App(ExecuteMsg),
+    ReceiveNft(Cw721ReceiveMsg),
+}
+
+impl<'de> Deserialize<'de> for ContractExecuteMsg {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_map(ContractExecuteMsgVisitor)
+    }
 }
 
-// #[cosmwasm_schema::cw_serde]
-// pub enum Cw20HookMsg {
-//     /// Withdraws a given amount from the vault.
-//     // Redeem {},
-// }
+struct ContractExecuteMsgVisitor;
+
+impl<'de> Visitor<'de> for ContractExecuteMsgVisitor {
+    type Value = ContractExecuteMsg;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an execute message")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let Some(key) = map.next_key::<String>()? else {
+            return Err(de::Error::invalid_length(0, &self));
+        };
+        if key == "receive_nft" {
+            let msg = map.next_value()?;
+            if map.next_key::<IgnoredAny>()?.is_some() {
+                return Err(de::Error::custom("expected a single message"));
+            }
+            return Ok(ContractExecuteMsg::ReceiveNft(msg));
+        }
+        // the key was already taken from the map, so it is handed to the app message first
+        let map = PrependKey {
+            key: Some(key),
+            map,
+        };
+        ExecuteMsg::deserialize(MapAccessDeserializer::new(map)).map(ContractExecuteMsg::App)
+    }
+}
+
+/// Map that yields `key` before the entries of `map`
+struct PrependKey<A> {
+    key: Option<String>,
+    map: A,
+}
+
+impl<'de, A: MapAccess<'de>> MapAccess<'de> for PrependKey<A> {
+    type Error = A::Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, A::Error> {
+        match self.key.take() {
+            Some(key) => seed
+                .deserialize(IntoDeserializer::<A::Error>::into_deserializer(key))
+                .map(Some),
+            None => self.map.next_key_seed(seed),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, A::Error> {
+        self.map.next_value_seed(seed)
+    }
+}
 
 /// Vault fee structure
 #[cosmwasm_schema::cw_serde]
@@ -221,6 +503,135 @@ impl Config {
     pub fn lp_asset_entry(&self) -> AssetEntry {
         AnsEntryConvertor::new(self.lp_token()).asset_entry()
     }
+
+    /// Whether `asset` is the lp token, one of the pool assets or the vault token
+    pub fn is_core_asset(&self, asset: &AssetInfo) -> bool {
+        asset == &self.liquidity_token
+            || asset == &self.vault_token
+            || self.pool_assets.contains(asset)
+    }
+}
+
+/// Liquidity buffer of a bonding vault
+#[cosmwasm_schema::cw_serde]
+pub struct LiquidityBufferConfig {
+    /// Share of the vault's lp tokens that is kept unstaked to serve redemptions instantly
+    pub target: Decimal,
+    /// Fee on the lp tokens of instant redemptions, which stays in the vault
+    pub instant_exit_fee: Decimal,
+}
+
+#[cosmwasm_schema::cw_serde]
+pub struct LiquidityBufferResponse {
+    pub config: Option<LiquidityBufferConfig>,
+    /// Unstaked lp tokens in the buffer
+    pub buffer: Uint128,
+}
+
+/// The vault pays no keeper fees: anyone can compound and only the performance fees are taken from the rewards.
+#[cosmwasm_schema::cw_serde]
+pub struct PreviewCompoundResponse {
+    /// Claimable rewards that are compounded, after the performance fees
+    pub rewards: Vec<AnsAsset>,
+    /// Performance fees sent to the fee collector
+    pub performance_fees: Vec<AnsAsset>,
+    /// Denied rewards sent to the fee collector
+    pub denied_rewards: Vec<Asset>,
+    /// Simulated pool assets received from swapping the rewards that are not pool assets
+    pub swap_outputs: Vec<AnsAsset>,
+    /// Pool assets provided as liquidity
+    pub provided_assets: Vec<AnsAsset>,
+    /// Lp tokens expected from providing the liquidity, estimated from the provided shares of the pool reserves.
+    /// This is an upper bound for unbalanced provides, as it ignores the swap fees and slippage of the provide.
+    /// None if the reserves of the pool can't be queried.
+    pub expected_lp: Option<Uint128>,
+    /// Lp tokens per vault token before the compound
+    pub assets_per_share: Decimal,
+    /// Lp tokens per vault token after the compound, if `expected_lp` is known
+    pub expected_assets_per_share: Option<Decimal>,
+}
+
+/// Holdings of the vault after an emergency shutdown that are not yet redeemed
+#[cosmwasm_schema::cw_serde]
+pub struct EmergencyShutdownState {
+    /// Lp tokens that were unstaked
+    pub lp_tokens: Uint128,
+    /// Pool assets of the withdrawn liquidity
+    pub assets: Vec<AnsAsset>,
+    /// Moment at which the unstaked lp tokens are unbonded
+    pub unbonded_at: Expiration,
+}
+
+/// Operations of the vault that are paused
+#[cosmwasm_schema::cw_serde]
+#[derive(Default)]
+pub struct PauseState {
+    /// Deposit and DepositLp
+    pub deposits: bool,
+    /// Redeem
+    pub redeems: bool,
+    /// Withdraw and ProcessMaturedClaims
+    pub withdrawals: bool,
+    /// Compound
+    pub compounding: bool,
+}
+
+#[cosmwasm_schema::cw_serde]
+pub struct PauseResponse {
+    pub guardian: Option<Addr>,
+    pub paused: PauseState,
+}
+
+/// Limits on the deposits of a vault
+#[cosmwasm_schema::cw_serde]
+#[derive(Default)]
+pub struct DepositCaps {
+    /// Maximum amount of lp tokens held by the vault after a deposit
+    pub max_total_lp: Option<Uint128>,
+    /// Maximum vault token balance of an address after a deposit
+    pub max_vault_tokens_per_address: Option<Uint128>,
+}
+
+/// Who can deposit into the vault
+#[cosmwasm_schema::cw_serde]
+#[derive(Default)]
+pub enum AccessMode {
+    /// Anyone can deposit
+    #[default]
+    Open,
+    /// Only the allowed depositors can deposit or receive deposits
+    Allowlist,
+}
+
+/// Which reward assets are compounded, besides the core assets of the vault
+#[cosmwasm_schema::cw_serde]
+#[derive(Default)]
+pub struct RewardFilter {
+    /// Only these rewards are compounded, all rewards if empty
+    pub allowlist: Vec<AssetInfo>,
+    /// Rewards that are never compounded
+    pub denylist: Vec<AssetInfo>,
+    /// What happens to the rewards that are not compounded
+    pub denied_rewards: DeniedRewards,
+}
+
+impl RewardFilter {
+    /// Whether the reward `asset` may be compounded
+    pub fn is_allowed(&self, asset: &AssetInfo) -> bool {
+        !self.denylist.contains(asset)
+            && (self.allowlist.is_empty() || self.allowlist.contains(asset))
+    }
+}
+
+/// Handling of rewards that are not allowed by the [`RewardFilter`]
+#[cosmwasm_schema::cw_serde]
+#[derive(Default)]
+pub enum DeniedRewards {
+    /// Leave the rewards in the proxy, they can be swept by the admin
+    #[default]
+    Skip,
+    /// Send the rewards to the fee collector
+    SendToFeeCollector,
 }
 
 #[cosmwasm_schema::cw_serde]
@@ -0,0 +1,133 @@
+/// This file contains the manually written queries of the staking contracts for the rewards that are pending for the vault.
+/// The staking adapter doesn't query pending rewards, so they are queried from the staking contract of the provider.
+/// The wyndex-stake messages can be found here: https://github.com/cosmorama/wynddex/blob/main/contracts/stake/src/msg.rs
+use cosmwasm_std::{Addr, Deps, Uint128};
+use cw_asset::{Asset, AssetInfo};
+use serde::{Deserialize, Serialize};
+
+use crate::contract::AutocompounderResult;
+use crate::error::AutocompounderError;
+
+pub const WYNDEX: &str = "wyndex";
+
+/// Mirrors the `WithdrawableRewards` variant of `wyndex_stake::msg::QueryMsg`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WyndexStakeQueryMsg {
+    WithdrawableRewards { owner: String },
+}
+
+/// Mirrors `wyndex::asset::AssetInfoValidated`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WyndexAssetInfo {
+    Token(Addr),
+    SmartToken(String),
+    Native(String),
+}
+
+/// Mirrors `wyndex::asset::AssetValidated`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WyndexAsset {
+    pub info: WyndexAssetInfo,
+    pub amount: Uint128,
+}
+
+/// Mirrors `wyndex_stake::msg::WithdrawableRewardsResponse`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WithdrawableRewardsResponse {
+    pub rewards: Vec<WyndexAsset>,
+}
+
+impl From<WyndexAsset> for Asset {
+    fn from(asset: WyndexAsset) -> Self {
+        let info = match asset.info {
+            WyndexAssetInfo::Token(addr) => AssetInfo::cw20(addr),
+            WyndexAssetInfo::SmartToken(denom) | WyndexAssetInfo::Native(denom) => {
+                AssetInfo::native(denom)
+            }
+        };
+        Asset::new(info, asset.amount)
+    }
+}
+
+/// Queries the rewards that `staker` can claim from the staking contract of `provider`
+pub fn query_pending_rewards(
+    deps: Deps,
+    provider: &str,
+    staking_contract: &Addr,
+    staker: &Addr,
+) -> AutocompounderResult<Vec<Asset>> {
+    match provider {
+        WYNDEX => {
+            let res: WithdrawableRewardsResponse = deps.querier.query_wasm_smart(
+                staking_contract,
+                &WyndexStakeQueryMsg::WithdrawableRewards {
+                    owner: staker.to_string(),
+                },
+            )?;
+            Ok(res.rewards.into_iter().map(Asset::from).collect())
+        }
+        _ => Err(AutocompounderError::PendingRewardsNotQueryable {
+            provider: provider.to_string(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cosmwasm_std::testing::mock_dependencies;
+    use cosmwasm_std::{from_json, to_json_binary, ContractResult, SystemResult, WasmQuery};
+    use speculoos::prelude::*;
+
+    #[test]
+    fn queries_the_withdrawable_rewards_of_wyndex_stakers() -> anyhow::Result<()> {
+        let mut deps = mock_dependencies();
+        deps.querier.update_wasm(|query| {
+            let WasmQuery::Smart { contract_addr, msg } = query else {
+                panic!("expected smart query");
+            };
+            assert_eq!(contract_addr, "staking_addr");
+            let WyndexStakeQueryMsg::WithdrawableRewards { owner } = from_json(msg).unwrap();
+            assert_eq!(owner, "proxy");
+            let res = WithdrawableRewardsResponse {
+                rewards: vec![
+                    WyndexAsset {
+                        info: WyndexAssetInfo::Token(Addr::unchecked("wynd")),
+                        amount: Uint128::new(100),
+                    },
+                    WyndexAsset {
+                        info: WyndexAssetInfo::Native("eur".to_string()),
+                        amount: Uint128::new(10),
+                    },
+                ],
+            };
+            SystemResult::Ok(ContractResult::Ok(to_json_binary(&res).unwrap()))
+        });
+
+        let rewards = query_pending_rewards(
+            deps.as_ref(),
+            WYNDEX,
+            &Addr::unchecked("staking_addr"),
+            &Addr::unchecked("proxy"),
+        )?;
+        assert_that!(rewards).is_equal_to(vec![
+            Asset::cw20(Addr::unchecked("wynd"), 100u128),
+            Asset::native("eur", 10u128),
+        ]);
+
+        let res = query_pending_rewards(
+            deps.as_ref(),
+            "osmosis",
+            &Addr::unchecked("staking_addr"),
+            &Addr::unchecked("proxy"),
+        );
+        assert_that!(res)
+            .is_err()
+            .is_equal_to(AutocompounderError::PendingRewardsNotQueryable {
+                provider: "osmosis".to_string(),
+            });
+        Ok(())
+    }
+}
@@ -1,24 +1,87 @@
-pub use crate::msg::{Claim, Config, FeeConfig};
+use crate::kujira_tx::TokenFactory;
+pub use crate::msg::{
+    AccessMode, Claim, Config, DepositCaps, EmergencyShutdownState, FeeConfig,
+    LiquidityBufferConfig, PauseState, RewardFilter,
+};
+use abstract_core::objects::AnsAsset;
 use cosmwasm_std::Addr;
+use cosmwasm_std::Decimal;
+use cosmwasm_std::Empty;
 use cosmwasm_std::Uint128;
+use cw_asset::Asset;
 use cw_storage_plus::{Item, Map};
 use cw_utils::Expiration;
 
-pub const CACHED_USER_ADDR: Item<Addr> = Item::new("cached_user_addr");
-/// Cached contract addresses. Keys are computed by using [`cw_asset::AssetInfo.to_string()`](cw_asset::AssetInfo)
-pub const CACHED_ASSETS: Map<String, Uint128> = Map::new("cached_assets");
+/// Context of an operation, saved before its submessage and read by its reply
+#[cosmwasm_schema::cw_serde]
+#[derive(Default)]
+pub struct ReplyContext {
+    /// Receiver of the vault tokens or pool assets of the operation
+    pub recipient: Option<Addr>,
+    /// Sender of a deposit, refunded with the unused deposit assets
+    pub depositor: Option<Addr>,
+    /// Receivers of a batch deposit and their share of the minted vault tokens
+    pub batch_recipients: Vec<(Addr, Decimal)>,
+    /// Pool assets owned by the proxy before the operation, to compute the received assets
+    pub cached_assets: Vec<Asset>,
+    /// Owners and lp token amounts of the matured claims that are paid out
+    pub claim_payouts: Vec<(Addr, Uint128)>,
+    /// Lp tokens of a deposit that are minted after the rewards are compounded
+    pub lp_deposit: Option<AnsAsset>,
+    /// Cw20 lp tokens of that deposit that were already sent to the app
+    pub received_lp: Option<Asset>,
+    /// Submessage id of the deposit that follows a compound, its cached assets are refreshed after the compound
+    pub deposit_submsg_id: Option<u64>,
+}
+
+/// Nonce of the latest saved reply context
+pub const REPLY_CONTEXT_NONCE: Item<u64> = Item::new("reply_context_nonce");
+// Key: Nonce - Value: ReplyContext
+pub const REPLY_CONTEXTS: Map<u64, ReplyContext> = Map::new("reply_contexts");
 /// Most recent unbonding call
 pub const LATEST_UNBONDING: Item<Expiration> = Item::new("latest_unbonding");
 // Key: User addreess - Value: Amount of vault tokens to be burned
 pub const PENDING_CLAIMS: Map<Addr, Uint128> = Map::new("pending_claims");
 // Key: User address - Value: Claim
 pub const CLAIMS: Map<Addr, Vec<Claim>> = Map::new("claims");
+/// Address of the cw721 contract holding the claims, only set if claims are tokenised
+pub const CLAIMS_NFT: Item<Addr> = Item::new("claims_nft");
+// Key: NFT token id - Value: Claim
+pub const NFT_CLAIMS: Map<String, Claim> = Map::new("nft_claims");
+/// Number of claim NFTs minted, used to derive the next token id
+pub const CLAIMS_NFT_COUNT: Item<u64> = Item::new("claims_nft_count");
 pub const CONFIG: Item<Config> = Item::new("config");
 pub const FEE_CONFIG: Item<FeeConfig> = Item::new("fee_config");
+pub const LIQUIDITY_BUFFER_CONFIG: Item<LiquidityBufferConfig> =
+    Item::new("liquidity_buffer_config");
+/// Lp tokens held unstaked by the proxy to serve redemptions instantly
+pub const LIQUIDITY_BUFFER: Item<Uint128> = Item::new("liquidity_buffer");
+/// Only set when the vault is in emergency shutdown
+pub const EMERGENCY_SHUTDOWN: Item<EmergencyShutdownState> = Item::new("emergency_shutdown");
+pub const PAUSE_STATE: Item<PauseState> = Item::new("pause_state");
+/// Address that may pause but not unpause the vault
+pub const GUARDIAN: Item<Addr> = Item::new("guardian");
+pub const DEPOSIT_CAPS: Item<DepositCaps> = Item::new("deposit_caps");
+pub const ACCESS_MODE: Item<AccessMode> = Item::new("access_mode");
+/// Addresses that can deposit when the vault is in [`AccessMode::Allowlist`]
+pub const DEPOSITORS: Map<Addr, Empty> = Map::new("depositors");
+// Key: (Owner, Operator) - Value: Expiration of the approval
+pub const OPERATORS: Map<(Addr, Addr), Expiration> = Map::new("operators");
+/// Whether the pending rewards are compounded before every deposit
+pub const COMPOUND_ON_DEPOSIT: Item<bool> = Item::new("compound_on_deposit");
+/// Decimal offset of the virtual shares that protect the vault against inflation attacks
+pub const VAULT_DECIMAL_OFFSET: Item<u32> = Item::new("decimal_offset");
+/// Tokenfactory of the chain, set for native vault tokens
+pub const TOKEN_FACTORY: Item<TokenFactory> = Item::new("token_factory");
+/// Allow and deny lists of the compounded rewards
+pub const REWARD_FILTER: Item<RewardFilter> = Item::new("reward_filter");
 
 pub const DEFAULT_BATCH_SIZE: u32 = 100;
 pub const MAX_BATCH_SIZE: u32 = 1000;
+/// Decimal offset of vaults that don't set one
 pub const DECIMAL_OFFSET: u32 = 1;
+pub const MAX_DECIMAL_OFFSET: u32 = 18;
 /// Default max spread for the vault in percentage
 pub const DEFAULT_MAX_SPREAD: u32 = 20;
 pub const VAULT_TOKEN_SYMBOL: &str = "FTTV";
+pub const DEFAULT_VAULT_TOKEN_DECIMALS: u8 = 6;
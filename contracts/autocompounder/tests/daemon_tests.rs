@@ -1,7 +1,6 @@
 use autocompounder::kujira_tx::{
-    denom_params_path, encode_msg_burn, encode_msg_create_denom, encode_msg_mint,
-    encode_query_supply_of, format_tokenfactory_denom, max_subdenom_length_for_chain,
-    msg_burn_type_url, msg_create_denom_type_url, msg_mint_type_url, SUPPLY_OF_PATH,
+    encode_msg_burn, encode_msg_create_denom, encode_msg_mint, encode_query_supply_of,
+    format_tokenfactory_denom, TokenFactory, SUPPLY_OF_PATH,
 };
 use cosmrs::{
     rpc::{Client, HttpClient},
@@ -25,10 +24,10 @@ use test_case::test_case;
 use tokio::runtime::Runtime;
 const LOCAL_MNEMONIC: &str = "notice oak worry limit wrap speak medal online prefer cluster roof addict wrist behave treat actual wasp year salad speed social layer crew genius";
 
-#[test_case("harpoon-4", "kujira"; "testing for kujira testnet")]
-#[test_case("osmo-test-5", "osmosis"; "testing for osmosis testnet")]
+#[test_case("harpoon-4", "kujira", TokenFactory::Kujira; "testing for kujira testnet")]
+#[test_case("osmo-test-5", "osmosis", TokenFactory::Osmosis; "testing for osmosis testnet")]
 #[serial_test::serial]
-pub fn denom_query_msgs(chain_id: &str, chain_name: &str) {
+pub fn denom_query_msgs(chain_id: &str, chain_name: &str, token_factory: TokenFactory) {
     // There are two types of daemon, sync and async. Sync daemons can be used is generic code. Async daemons can be used
     // in async code (e.g. tokio), which enables multi-threaded and non-blocking code.
 
@@ -97,7 +96,7 @@ pub fn denom_query_msgs(chain_id: &str, chain_name: &str) {
 
     // query token factory params
     let response =
-        rt.block_on(client.abci_query(Some(denom_params_path(chain_name)), vec![], None, true));
+        rt.block_on(client.abci_query(Some(token_factory.params_path()), vec![], None, true));
 
     println!("tokenfactory params response: {:?}", response);
     let result: String = response
@@ -112,10 +111,10 @@ pub fn denom_query_msgs(chain_id: &str, chain_name: &str) {
 // this string is 140 characters long and the max for the sdk is 127
 const LONG_TEST_DENOM: &str = "_123456789_123456789_123456789_123456789_123456789_123456789_123456789_123456789_123456789_123456789_123456789_123456789_123456789_123456789";
 
-#[test_case("harpoon-4", "kujira"; "testing for kujira testnet")]
-#[test_case("osmo-test-5", "osmosis"; "testing for osmosis testnet")]
+#[test_case("harpoon-4", TokenFactory::Kujira; "testing for kujira testnet")]
+#[test_case("osmo-test-5", TokenFactory::Osmosis; "testing for osmosis testnet")]
 #[serial_test::serial]
-fn tokefactory_create_mint_burn(chain_id: &str, chain_name: &str) {
+fn tokefactory_create_mint_burn(chain_id: &str, token_factory: TokenFactory) {
     // We start by creating a runtime, which is required for a sync daemon.
     let rt = Runtime::new().unwrap();
 
@@ -138,20 +137,20 @@ fn tokefactory_create_mint_burn(chain_id: &str, chain_name: &str) {
     // let msg = tokenfactory_create_denom_msg(daemon.sender().to_string(), "4T2TEST1".to_string()).unwrap()
     fn create_mint_burn_msgs(
         sender_str: &str,
-        chain_name: &str,
+        token_factory: TokenFactory,
         new_subdenom: &str,
         factory_denom: &str,
     ) -> Vec<Any> {
         let create_denom_msg = Any {
-            type_url: msg_create_denom_type_url(chain_name),
-            value: encode_msg_create_denom(sender_str, new_subdenom, chain_name),
+            type_url: token_factory.msg_create_denom_type_url(),
+            value: encode_msg_create_denom(sender_str, new_subdenom),
         };
         let any_mint_msg = Any {
-            type_url: msg_mint_type_url(chain_name),
+            type_url: token_factory.msg_mint_type_url(),
             value: encode_msg_mint(sender_str, factory_denom, 1_000_000u128.into(), sender_str),
         };
         let any_burn_msg = Any {
-            type_url: msg_burn_type_url(chain_name),
+            type_url: token_factory.msg_burn_type_url(),
             value: encode_msg_burn(sender_str, factory_denom, 1_000_000u128.into()),
         };
 
@@ -176,13 +175,13 @@ fn tokefactory_create_mint_burn(chain_id: &str, chain_name: &str) {
     // ));
 
     let mut truncated_subdenom = LONG_TEST_DENOM.to_string().clone();
-    truncated_subdenom.truncate(max_subdenom_length_for_chain(chain_name));
+    truncated_subdenom.truncate(token_factory.max_subdenom_length());
     let factory_denom =
         format_tokenfactory_denom(daemon.sender().as_str(), truncated_subdenom.as_str());
 
     let short_denom_test_msgs = create_mint_burn_msgs(
         daemon.sender().as_str(),
-        chain_name,
+        token_factory,
         &truncated_subdenom,
         &factory_denom,
     );
@@ -4,7 +4,7 @@ use abstract_core::objects::gov_type::GovernanceDetails;
 use abstract_interface::{AbstractInterfaceError, AccountDetails};
 
 use autocompounder::error::AutocompounderError;
-use cw_asset::{AssetInfo, AssetInfoBase};
+use cw_asset::{Asset, AssetInfo, AssetInfoBase};
 use cw_plus_interface::cw20_base::Cw20Base;
 use std::ops::Mul;
 use std::str::FromStr;
@@ -18,16 +18,17 @@ use abstract_sdk::core as abstract_core;
 use autocompounder::state::{Claim, Config, FeeConfig, DECIMAL_OFFSET};
 use cw_orch::prelude::*;
 
+use autocompounder::claims_nft::{Cw721QueryMsg, OwnerOfResponse};
 use autocompounder::msg::{
     AutocompounderExecuteMsg, AutocompounderExecuteMsgFns, AutocompounderQueryMsgFns, BondingData,
-    AUTOCOMPOUNDER_ID,
+    Cw20HookMsg, Cw721HookMsg, DeniedRewards, RewardFilter, AUTOCOMPOUNDER_ID,
 };
 
 use common::abstract_helper::{self, init_auto_compounder};
 use common::vault::Vault;
 use common::AResult;
 use common::{TEST_NAMESPACE, VAULT_TOKEN};
-use cosmwasm_std::{coin, coins, to_json_binary, Addr, Decimal, Uint128};
+use cosmwasm_std::{coin, coins, to_json_binary, Addr, Decimal, Empty, Uint128};
 
 use cw_utils::{Duration, Expiration};
 use speculoos::assert_that;
@@ -37,6 +38,7 @@ use wyndex_stake::msg::ReceiveDelegationMsg;
 use cw20::msg::Cw20ExecuteMsgFns;
 use cw20_base::msg::QueryMsgFns;
 use speculoos::result::ResultAssertions;
+use speculoos::vec::VecAssertions;
 use wyndex_bundle::*;
 
 const WYNDEX: &str = "wyndex";
@@ -80,6 +82,17 @@ pub fn create_vault(
     asset1: &str,
     asset2: &str,
     vault_token_is_cw20: bool,
+) -> Result<Vault<MockBech32>, AbstractInterfaceError> {
+    create_vault_with_claims_nft(mock, asset1, asset2, vault_token_is_cw20, None)
+}
+
+/// Creates a vault that tokenises its claims as NFTs of `claims_nft_code_id`
+pub fn create_vault_with_claims_nft(
+    mock: MockBech32,
+    asset1: &str,
+    asset2: &str,
+    vault_token_is_cw20: bool,
+    claims_nft_code_id: Option<u64>,
 ) -> Result<Vault<MockBech32>, AbstractInterfaceError> {
     // Deploy abstract
     let abstract_ = Abstract::deploy_on(mock.clone(), mock.sender().to_string())?;
@@ -159,6 +172,10 @@ pub fn create_vault(
                 max_claims_per_address: None,
             }),
             max_swap_spread: Some(Decimal::percent(50)),
+            claims_nft_code_id,
+            vault_token_metadata: None,
+            token_factory: None,
+            decimal_offset: None,
         }),
         None,
     )?;
@@ -298,7 +315,7 @@ fn deposit_cw20_asset() -> AResult {
         .vault_token
         .call_as(&owner)
         .increase_allowance(redeem_amount, _ac_addres, None)?;
-    vault.auto_compounder.redeem(redeem_amount, None)?;
+    vault.auto_compounder.redeem(redeem_amount, None, None)?;
 
     // check that the vault token decreased
     let vault_token_balance = vault.vault_token.balance(owner.to_string())?;
@@ -380,7 +397,7 @@ fn generator_without_reward_proxies_balanced_assets() -> AResult {
     // withdraw part from the auto-compounder
     let redeem_amount = Uint128::from(20000u128);
     vault_token.increase_allowance(redeem_amount, auto_compounder_addr.clone(), None)?;
-    vault.auto_compounder.redeem(redeem_amount, None)?;
+    vault.auto_compounder.redeem(redeem_amount, None, None)?;
 
     // check that the vault token decreased
     let vault_token_balance = vault_token.balance(owner.to_string())?;
@@ -391,7 +408,7 @@ fn generator_without_reward_proxies_balanced_assets() -> AResult {
     // check that the pending claims are updated
     let redeem_amount = Uint128::from(20000u128);
     vault_token.increase_allowance(redeem_amount, auto_compounder_addr.clone(), None)?;
-    vault.auto_compounder.redeem(redeem_amount, None)?;
+    vault.auto_compounder.redeem(redeem_amount, None, None)?;
 
     let pending_claims: Uint128 = vault.auto_compounder.pending_claims(owner.clone())?;
     assert_that!(pending_claims.u128()).is_equal_to(40000u128);
@@ -414,7 +431,7 @@ fn generator_without_reward_proxies_balanced_assets() -> AResult {
         });
     }
     mock.next_block()?;
-    vault.auto_compounder.withdraw()?;
+    vault.auto_compounder.withdraw(None)?;
 
     // check that the claim is removed
     let claims: Vec<Claim> = vault.auto_compounder.claims(owner.clone())?;
@@ -437,11 +454,11 @@ fn generator_without_reward_proxies_balanced_assets() -> AResult {
 
     let redeem_amount = Uint128::from(60000u128);
     vault_token.increase_allowance(redeem_amount, auto_compounder_addr, None)?;
-    vault.auto_compounder.redeem(redeem_amount, None)?;
+    vault.auto_compounder.redeem(redeem_amount, None, None)?;
 
     vault.auto_compounder.batch_unbond(None, None)?;
     mock.wait_blocks(60 * 60 * 24 * 21)?;
-    vault.auto_compounder.withdraw()?;
+    vault.auto_compounder.withdraw(None)?;
 
     // and eur balance decreased and usd balance stayed the same
     let balances = mock.query_all_balances(&owner)?;
@@ -674,7 +691,7 @@ fn generator_without_reward_proxies_single_sided() -> AResult {
     vault.auto_compounder.set_sender(&owner);
     let redeem_amount = Uint128::from(4000u128 * 10u128.pow(DECIMAL_OFFSET));
     vault_token.increase_allowance(redeem_amount, auto_compounder_addr.clone(), None)?;
-    vault.auto_compounder.redeem(redeem_amount, None)?;
+    vault.auto_compounder.redeem(redeem_amount, None, None)?;
 
     // check that the vault token decreased
     let vault_token_balance = vault_token.balance(owner.to_string())?;
@@ -716,7 +733,7 @@ fn generator_without_reward_proxies_single_sided() -> AResult {
 
     // let total_lp_balance = vault.auto_compounder.total_lp_position()?;
     // assert_that!(total_lp_balance).is_equal_to(new_position);
-    vault.auto_compounder.withdraw()?;
+    vault.auto_compounder.withdraw(None)?;
 
     // and eur and usd balance increased
     let balances = mock.query_all_balances(&owner)?;
@@ -742,7 +759,7 @@ fn generator_without_reward_proxies_single_sided() -> AResult {
     // withdraw all owner funds from the auto-compounder
     let redeem_amount = Uint128::from(6000u128 * 10u128.pow(DECIMAL_OFFSET));
     vault_token.increase_allowance(redeem_amount, auto_compounder_addr.clone(), None)?;
-    vault.auto_compounder.redeem(redeem_amount, None)?;
+    vault.auto_compounder.redeem(redeem_amount, None, None)?;
 
     // testing general non unbonding staking contract functionality
     let pending_claims = vault.auto_compounder.pending_claims(owner.clone())?.into();
@@ -750,8 +767,8 @@ fn generator_without_reward_proxies_single_sided() -> AResult {
 
     vault.auto_compounder.batch_unbond(None, None)?; // batch unbonding not enabled
     mock.wait_blocks(60 * 60 * 24 * 10)?;
-    vault.auto_compounder.withdraw()?; // withdraw wont have any effect, because there are no pending claims
-                                       // mock.next_block()?;
+    vault.auto_compounder.withdraw(None)?; // withdraw wont have any effect, because there are no pending claims
+                                           // mock.next_block()?;
 
     let balances = mock.query_all_balances(&owner)?;
     assert_that!(balances).is_equal_to(vec![
@@ -769,7 +786,7 @@ fn generator_without_reward_proxies_single_sided() -> AResult {
     vault_token.set_sender(&user1);
     let redeem_amount = vault_token_balance_user1;
     vault_token.increase_allowance(redeem_amount, auto_compounder_addr, None)?;
-    vault.auto_compounder.redeem(redeem_amount, None)?;
+    vault.auto_compounder.redeem(redeem_amount, None, None)?;
 
     let pending_claims = vault.auto_compounder.pending_claims(user1.clone())?.into();
     assert_that!(pending_claims).is_equal_to(vault_token_balance_user1.u128());
@@ -785,7 +802,7 @@ fn generator_without_reward_proxies_single_sided() -> AResult {
     assert_that!(claims).is_equal_to(vec![expected_claim]);
 
     mock.wait_blocks(60 * 60 * 24 * 10)?;
-    vault.auto_compounder.withdraw()?;
+    vault.auto_compounder.withdraw(None)?;
     // mock.next_block()?;
     // a relative loss is experienced by the user due to swap fees and drainage of the pool to 0
     let balances = mock.query_all_balances(&user1)?;
@@ -878,6 +895,11 @@ fn generator_with_rewards_test_fee_and_reward_distribution() -> AResult {
     // the fee is then swapped using the astro/EUR pair
     // the price of the WYND/EUR pair is 10K:10K
     // which will result in a 29 EUR fee for the autocompounder due to spread + rounding.
+    let preview = vault.auto_compounder.preview_compound()?;
+    assert_that!(preview.performance_fees).has_length(1);
+    assert_that!(preview.performance_fees[0].amount.u128()).is_equal_to(30u128);
+    assert_that!(preview.expected_assets_per_share.unwrap())
+        .is_greater_than(preview.assets_per_share);
     vault.auto_compounder.compound()?;
 
     let commission_received: Uint128 = mock.query_balance(&commission_addr, WYND_TOKEN)?;
@@ -896,7 +918,7 @@ fn generator_with_rewards_test_fee_and_reward_distribution() -> AResult {
     // Redeem vault tokens and create pending claim of user tokens to see if the user actually received more of EUR and USD then they deposited
     let redeem_amount = vault_token_balance.balance;
     vault_token.increase_allowance(redeem_amount, auto_compounder_addr, None)?;
-    vault.auto_compounder.redeem(redeem_amount, None)?;
+    vault.auto_compounder.redeem(redeem_amount, None, None)?;
 
     // Unbond tokens & clear pending claims
     vault.auto_compounder.batch_unbond(None, None)?;
@@ -904,7 +926,7 @@ fn generator_with_rewards_test_fee_and_reward_distribution() -> AResult {
     mock.wait_blocks(1)?;
 
     // Withdraw EUR and USD tokens to user
-    vault.auto_compounder.withdraw()?;
+    vault.auto_compounder.withdraw(None)?;
 
     let new_owner_balance = mock.query_all_balances(&owner)?;
     let eur_diff = new_owner_balance[0].amount.u128() - owner_balance_eur.u128();
@@ -967,7 +989,7 @@ fn test_deposit_fees_fee_token_and_withdraw_fees() -> AResult {
 
     let redeem_amount = owner_balance;
     vault_token.increase_allowance(redeem_amount, vault.auto_compounder.addr_str()?, None)?;
-    vault.auto_compounder.redeem(redeem_amount, None)?;
+    vault.auto_compounder.redeem(redeem_amount, None, None)?;
 
     let amount: Uint128 = vault.auto_compounder.pending_claims(owner.clone())?;
     assert_that!(amount).is_equal_to(owner_balance);
@@ -986,7 +1008,7 @@ fn test_deposit_fees_fee_token_and_withdraw_fees() -> AResult {
         .is_equal_to(expected_asset.u128());
 
     mock.wait_blocks(60 * 60 * 24 * 10)?;
-    vault.auto_compounder.withdraw()?;
+    vault.auto_compounder.withdraw(None)?;
 
     let new_owner_balance = mock.query_all_balances(&owner)?;
     assert_that!(new_owner_balance[0].amount.u128()).is_equal_to(443u128); // estimated value
@@ -1041,7 +1063,7 @@ fn test_deposit_fees_non_fee_token() -> AResult {
 
     let redeem_amount = owner_balance;
     vault_token.increase_allowance(redeem_amount, vault.auto_compounder.addr_str()?, None)?;
-    vault.auto_compounder.redeem(redeem_amount, None)?;
+    vault.auto_compounder.redeem(redeem_amount, None, None)?;
 
     let amount: Uint128 = vault.auto_compounder.pending_claims(owner.clone())?;
     assert_that!(amount).is_equal_to(owner_balance);
@@ -1061,7 +1083,7 @@ fn test_deposit_fees_non_fee_token() -> AResult {
         .is_equal_to(expected_asset.u128());
 
     mock.wait_blocks(60 * 60 * 24 * 10)?;
-    vault.auto_compounder.withdraw()?;
+    vault.auto_compounder.withdraw(None)?;
 
     let new_owner_balance = mock.query_all_balances(&owner)?;
     assert_that!(new_owner_balance[0].amount.u128()).is_equal_to(403u128); // estimated value
@@ -1132,6 +1154,626 @@ fn test_zero_performance_fees() -> AResult {
     Ok(())
 }
 
+#[test]
+fn compound_on_deposit_prices_new_shares_with_pending_rewards() -> AResult {
+    let mock = MockBech32::new("mock");
+    let owner = mock.sender();
+    let depositor = mock.addr_make("depositor");
+    let wyndex_owner = mock.addr_make(WYNDEX_OWNER);
+    let vault = crate::create_vault(mock.clone(), EUR, USD, true)?;
+    let WynDex {
+        eur_token,
+        usd_token,
+        eur_usd_staking,
+        ..
+    } = vault.wyndex;
+    let vault_token = vault.vault_token;
+    let funds = [
+        coin(100_000u128, eur_token.to_string()),
+        coin(100_000u128, usd_token.to_string()),
+    ];
+    let deposit = vec![
+        AnsAsset::new(AssetEntry::new("eur"), 100_000u128),
+        AnsAsset::new(AssetEntry::new("usd"), 100_000u128),
+    ];
+
+    mock.set_balances(&[
+        (&owner, &funds),
+        (&depositor, &funds),
+        (&wyndex_owner, &[coin(1000, WYND_TOKEN)]),
+    ])?;
+
+    vault
+        .auto_compounder
+        .deposit(deposit.clone(), None, None, &funds)?;
+
+    let manager_addr = vault.account.manager.address()?;
+    vault.auto_compounder.call_as(&manager_addr).execute_app(
+        AutocompounderExecuteMsg::UpdateCompoundOnDeposit { enabled: true },
+        None,
+    )?;
+
+    mock.next_block()?;
+    vault.wyndex.suite.distribute_funds(
+        eur_usd_staking,
+        &wyndex_owner,
+        &coins(1000, WYND_TOKEN),
+    )?;
+
+    // the pending rewards are compounded before the second deposit, so it receives fewer shares
+    vault
+        .auto_compounder
+        .call_as(&depositor)
+        .deposit(deposit, None, None, &funds)?;
+
+    let owner_shares = vault_token.balance(owner.to_string())?.balance;
+    let depositor_shares = vault_token.balance(depositor.to_string())?.balance;
+    assert_that!(depositor_shares).is_less_than(owner_shares);
+    Ok(())
+}
+
+/// Mirrors `CreateDistributionFlow` of `wyndex_stake::msg::ExecuteMsg`, to reward the stakers with a pool asset
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+enum StakeExecuteMsg {
+    CreateDistributionFlow {
+        manager: String,
+        asset: StakeAssetInfo,
+        rewards: Vec<(u64, Decimal)>,
+    },
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+enum StakeAssetInfo {
+    Native(String),
+}
+
+#[test]
+fn compound_on_deposit_compounds_pool_asset_rewards() -> AResult {
+    let mock = MockBech32::new("mock");
+    let owner = mock.sender();
+    let depositor = mock.addr_make("depositor");
+    let wyndex_owner = mock.addr_make(WYNDEX_OWNER);
+    let vault = crate::create_vault(mock.clone(), EUR, USD, true)?;
+    let WynDex {
+        eur_token,
+        usd_token,
+        eur_usd_staking,
+        ..
+    } = vault.wyndex;
+    let proxy = vault.account.proxy.address()?;
+    let funds = [
+        coin(100_000u128, eur_token.to_string()),
+        coin(100_000u128, usd_token.to_string()),
+    ];
+    let deposit = vec![
+        AnsAsset::new(AssetEntry::new("eur"), 100_000u128),
+        AnsAsset::new(AssetEntry::new("usd"), 100_000u128),
+    ];
+    mock.set_balances(&[
+        (&owner, &funds),
+        (&depositor, &funds),
+        (
+            &wyndex_owner,
+            &[coin(1000, WYND_TOKEN), coin(1000, eur_token.to_string())],
+        ),
+    ])?;
+
+    // the stakers are rewarded with eur too, which can't be filtered out as it is a pool asset
+    mock.call_as(&wyndex_owner).execute(
+        &StakeExecuteMsg::CreateDistributionFlow {
+            manager: wyndex_owner.to_string(),
+            asset: StakeAssetInfo::Native(eur_token.to_string()),
+            rewards: vec![(1, Decimal::one())],
+        },
+        &[],
+        &eur_usd_staking,
+    )?;
+    let manager_addr = vault.account.manager.address()?;
+    let update_reward_filter = vault.auto_compounder.call_as(&manager_addr).execute_app(
+        AutocompounderExecuteMsg::UpdateRewardFilter {
+            reward_filter: RewardFilter {
+                allowlist: vec![],
+                denylist: vec![AssetInfo::native(eur_token.to_string())],
+                denied_rewards: DeniedRewards::Skip,
+            },
+        },
+        None,
+    );
+    assert_that!(update_reward_filter).is_err();
+
+    vault
+        .auto_compounder
+        .deposit(deposit.clone(), None, None, &funds)?;
+    vault.auto_compounder.call_as(&manager_addr).execute_app(
+        AutocompounderExecuteMsg::UpdateCompoundOnDeposit { enabled: true },
+        None,
+    )?;
+
+    mock.next_block()?;
+    vault.wyndex.suite.distribute_funds(
+        eur_usd_staking.clone(),
+        &wyndex_owner,
+        &coins(1000, WYND_TOKEN),
+    )?;
+    vault.wyndex.suite.distribute_funds(
+        eur_usd_staking,
+        &wyndex_owner,
+        &coins(1000, eur_token.to_string()),
+    )?;
+
+    // the eur reward is compounded before the deposit, and neither stays in the vault nor is refunded
+    vault
+        .auto_compounder
+        .call_as(&depositor)
+        .deposit(deposit, None, None, &funds)?;
+
+    assert_that!(mock.query_balance(&proxy, &eur_token.to_string())?).is_equal_to(Uint128::zero());
+    // the compounded rewards belong to the previous depositors
+    let owner_shares = vault.vault_token.balance(owner.to_string())?.balance;
+    let depositor_shares = vault.vault_token.balance(depositor.to_string())?.balance;
+    assert_that!(depositor_shares).is_greater_than(Uint128::zero());
+    assert_that!(depositor_shares).is_less_than(owner_shares);
+    Ok(())
+}
+
+#[test]
+fn compound_skips_unregistered_rewards_and_sends_denied_rewards_to_the_fee_collector() -> AResult {
+    let mock = MockBech32::new("mock");
+    let owner = mock.sender();
+    let commission_addr = mock.addr_make(COMMISSION_RECEIVER);
+    let wyndex_owner = mock.addr_make(WYNDEX_OWNER);
+    let vault = crate::create_vault(mock.clone(), EUR, USD, true)?;
+    let WynDex {
+        eur_token,
+        usd_token,
+        eur_usd_staking,
+        ..
+    } = vault.wyndex;
+    let proxy = vault.account.proxy.address()?;
+    let unregistered = "unregistered";
+    let funds = [
+        coin(100_000u128, eur_token.to_string()),
+        coin(100_000u128, usd_token.to_string()),
+    ];
+    mock.set_balances(&[
+        (&owner, &funds),
+        (
+            &wyndex_owner,
+            &[coin(1000, WYND_TOKEN), coin(1000, unregistered)],
+        ),
+    ])?;
+
+    // the stakers are also rewarded with a token that is not registered in ANS
+    mock.call_as(&wyndex_owner).execute(
+        &StakeExecuteMsg::CreateDistributionFlow {
+            manager: wyndex_owner.to_string(),
+            asset: StakeAssetInfo::Native(unregistered.to_string()),
+            rewards: vec![(1, Decimal::one())],
+        },
+        &[],
+        &eur_usd_staking,
+    )?;
+    assert_that!(vault.auto_compounder.reward_tokens()?)
+        .is_equal_to(vec![AssetInfo::native(WYND_TOKEN)]);
+    let manager_addr = vault.account.manager.address()?;
+    vault.auto_compounder.call_as(&manager_addr).execute_app(
+        AutocompounderExecuteMsg::UpdateRewardFilter {
+            reward_filter: RewardFilter {
+                allowlist: vec![],
+                denylist: vec![AssetInfo::native(WYND_TOKEN)],
+                denied_rewards: DeniedRewards::SendToFeeCollector,
+            },
+        },
+        None,
+    )?;
+    assert_that!(vault.auto_compounder.reward_tokens()?).is_empty();
+
+    vault.auto_compounder.deposit(
+        vec![
+            AnsAsset::new(AssetEntry::new("eur"), 100_000u128),
+            AnsAsset::new(AssetEntry::new("usd"), 100_000u128),
+        ],
+        None,
+        None,
+        &funds,
+    )?;
+    mock.next_block()?;
+    vault.wyndex.suite.distribute_funds(
+        eur_usd_staking.clone(),
+        &wyndex_owner,
+        &coins(1000, WYND_TOKEN),
+    )?;
+    vault.wyndex.suite.distribute_funds(
+        eur_usd_staking,
+        &wyndex_owner,
+        &coins(1000, unregistered),
+    )?;
+
+    vault.auto_compounder.compound()?;
+
+    // the denied reward is sent to the fee collector
+    assert_that!(mock.query_balance(&commission_addr, WYND_TOKEN)?)
+        .is_greater_than(Uint128::zero());
+    assert_that!(mock.query_balance(&proxy, WYND_TOKEN)?).is_equal_to(Uint128::zero());
+    // the unregistered reward can't be swapped, so it stays with the account
+    assert_that!(mock.query_balance(&proxy, unregistered)?).is_greater_than(Uint128::zero());
+    Ok(())
+}
+
+#[test]
+fn compound_on_deposit_applies_to_lp_deposits() -> AResult {
+    let mock = MockBech32::new("mock");
+    let owner = mock.sender();
+    let depositor = mock.addr_make("depositor");
+    let wyndex_owner = mock.addr_make(WYNDEX_OWNER);
+    let vault = crate::create_vault(mock.clone(), EUR, USD, true)?;
+    let WynDex {
+        eur_token,
+        usd_token,
+        eur_usd_staking,
+        eur_usd_pair,
+        eur_usd_lp,
+        ..
+    } = vault.wyndex;
+    let vault_token = vault.vault_token;
+    let funds = [
+        coin(100_000u128, eur_token.to_string()),
+        coin(100_000u128, usd_token.to_string()),
+    ];
+    mock.set_balances(&[(&owner, &funds), (&wyndex_owner, &[coin(1000, WYND_TOKEN)])])?;
+
+    let manager_addr = vault.account.manager.address()?;
+    vault.auto_compounder.call_as(&manager_addr).execute_app(
+        AutocompounderExecuteMsg::UpdateFeeConfig {
+            performance: Some(Decimal::zero()),
+            deposit: Some(Decimal::zero()),
+            withdrawal: None,
+            fee_collector_addr: None,
+        },
+        None,
+    )?;
+    vault.auto_compounder.deposit(
+        vec![
+            AnsAsset::new(AssetEntry::new("eur"), 100_000u128),
+            AnsAsset::new(AssetEntry::new("usd"), 100_000u128),
+        ],
+        None,
+        None,
+        &funds,
+    )?;
+    let owner_lp = vault.auto_compounder.total_lp_position()?;
+
+    vault.auto_compounder.call_as(&manager_addr).execute_app(
+        AutocompounderExecuteMsg::UpdateCompoundOnDeposit { enabled: true },
+        None,
+    )?;
+    mock.next_block()?;
+    vault.wyndex.suite.distribute_funds(
+        eur_usd_staking,
+        &wyndex_owner,
+        &coins(1000, WYND_TOKEN),
+    )?;
+
+    // the depositor brings as many lp tokens as the owner's, but the pending rewards are compounded first
+    let lp_entry = AnsEntryConvertor::new(LpToken::new(WYNDEX, vec![EUR, USD])).asset_entry();
+    eur_usd_lp
+        .call_as(&eur_usd_pair)
+        .mint(owner_lp, depositor.to_string())?;
+    eur_usd_lp.call_as(&depositor).increase_allowance(
+        owner_lp,
+        vault.auto_compounder.address()?.to_string(),
+        None,
+    )?;
+    vault
+        .auto_compounder
+        .call_as(&depositor)
+        .deposit_lp(AnsAsset::new(lp_entry, owner_lp), None)?;
+
+    let owner_shares = vault_token.balance(owner.to_string())?.balance;
+    let depositor_shares = vault_token.balance(depositor.to_string())?.balance;
+    assert_that!(depositor_shares).is_less_than(owner_shares);
+    assert_that!(vault.auto_compounder.total_lp_position()?)
+        .is_greater_than(owner_lp * Uint128::new(2));
+    Ok(())
+}
+
+#[test]
+fn deposit_continues_when_the_compound_fails() -> AResult {
+    let mock = MockBech32::new("mock");
+    let owner = mock.sender();
+    let vault = crate::create_vault(mock.clone(), EUR, USD, true)?;
+    let WynDex {
+        eur_token,
+        usd_token,
+        ..
+    } = vault.wyndex;
+    let vault_token = vault.vault_token;
+    let funds = [
+        coin(100_000u128, eur_token.to_string()),
+        coin(100_000u128, usd_token.to_string()),
+    ];
+    mock.set_balances(&[(&owner, &funds)])?;
+
+    let manager_addr = vault.account.manager.address()?;
+    vault.auto_compounder.call_as(&manager_addr).execute_app(
+        AutocompounderExecuteMsg::UpdateCompoundOnDeposit { enabled: true },
+        None,
+    )?;
+
+    // there are no rewards to compound, the failing compound is reverted on its own
+    vault.auto_compounder.deposit(
+        vec![
+            AnsAsset::new(AssetEntry::new("eur"), 100_000u128),
+            AnsAsset::new(AssetEntry::new("usd"), 100_000u128),
+        ],
+        None,
+        None,
+        &funds,
+    )?;
+
+    assert_that!(vault_token.balance(owner.to_string())?.balance).is_greater_than(Uint128::zero());
+    Ok(())
+}
+
+#[test]
+fn redeem_with_cw20_send() -> AResult {
+    let mock = MockBech32::new("mock");
+    let owner = mock.sender();
+    let vault = crate::create_vault(mock.clone(), EUR, USD, true)?;
+    let WynDex {
+        eur_token,
+        usd_token,
+        ..
+    } = vault.wyndex;
+    let vault_token = vault.vault_token;
+    let funds = [
+        coin(100_000u128, eur_token.to_string()),
+        coin(100_000u128, usd_token.to_string()),
+    ];
+    mock.set_balances(&[(&owner, &funds)])?;
+    vault.auto_compounder.deposit(
+        vec![
+            AnsAsset::new(AssetEntry::new("eur"), 100_000u128),
+            AnsAsset::new(AssetEntry::new("usd"), 100_000u128),
+        ],
+        None,
+        None,
+        &funds,
+    )?;
+
+    // no allowance is needed when the vault tokens are sent with the redeem hook
+    let redeem_amount = Uint128::new(20_000);
+    vault_token.send(
+        redeem_amount,
+        vault.auto_compounder.addr_str()?,
+        to_json_binary(&Cw20HookMsg::Redeem { recipient: None })?,
+    )?;
+
+    let pending_claims: Uint128 = vault.auto_compounder.pending_claims(owner.clone())?;
+    assert_that!(pending_claims).is_equal_to(redeem_amount);
+    Ok(())
+}
+
+#[test]
+fn deposit_with_cw20_send() -> AResult {
+    let mock = MockBech32::new("mock");
+    let owner = mock.sender();
+    let wyndex_owner = mock.addr_make(WYNDEX_OWNER);
+    let vault = crate::create_vault(mock, RAW_TOKEN, RAW_2_TOKEN, true)?;
+    let WynDex {
+        raw_token,
+        raw_2_token,
+        ..
+    } = vault.wyndex;
+    let vault_token = vault.vault_token;
+    let ac_addr = vault.auto_compounder.addr_str()?;
+    let amount = 10_000u128;
+    raw_token
+        .call_as(&wyndex_owner)
+        .transfer((2 * amount).into(), owner.to_string())?;
+    raw_2_token
+        .call_as(&wyndex_owner)
+        .transfer(amount.into(), owner.to_string())?;
+    raw_token
+        .call_as(&owner)
+        .increase_allowance(amount.into(), ac_addr.clone(), None)?;
+    raw_2_token
+        .call_as(&owner)
+        .increase_allowance(amount.into(), ac_addr.clone(), None)?;
+    vault.auto_compounder.deposit(
+        vec![
+            AnsAsset::new(AssetEntry::new(RAW_TOKEN), amount),
+            AnsAsset::new(AssetEntry::new(RAW_2_TOKEN), amount),
+        ],
+        None,
+        None,
+        &[],
+    )?;
+    let position = vault.auto_compounder.total_lp_position()?;
+    let balance = vault_token.balance(owner.to_string())?.balance;
+
+    // no allowance is needed when the pool asset is sent with the deposit hook
+    raw_token.call_as(&owner).send(
+        1000u128.into(),
+        ac_addr.clone(),
+        to_json_binary(&Cw20HookMsg::Deposit {
+            recipient: None,
+            max_spread: None,
+        })?,
+    )?;
+
+    // the received pool asset is forwarded to the account and provided as liquidity
+    assert_that!(raw_token.balance(ac_addr)?.balance).is_equal_to(Uint128::zero());
+    assert_that!(raw_token.balance(owner.to_string())?.balance)
+        .is_equal_to(Uint128::new(amount - 1000));
+    assert_that!(vault.auto_compounder.total_lp_position()?).is_greater_than(position);
+    assert_that!(vault_token.balance(owner.to_string())?.balance).is_greater_than(balance);
+    Ok(())
+}
+
+#[test]
+fn deposit_lp_with_cw20_send() -> AResult {
+    let mock = MockBech32::new("mock");
+    let owner = mock.sender();
+    let vault = crate::create_vault(mock.clone(), EUR, USD, true)?;
+    let WynDex {
+        eur_usd_pair,
+        eur_usd_lp,
+        ..
+    } = vault.wyndex;
+    let vault_token = vault.vault_token;
+    let ac_addr = vault.auto_compounder.addr_str()?;
+    let amount = 100_000u128;
+    eur_usd_lp
+        .call_as(&eur_usd_pair)
+        .mint(amount.into(), owner.to_string())?;
+
+    // no allowance is needed when the lp tokens are sent with the deposit lp hook
+    eur_usd_lp.call_as(&owner).send(
+        amount.into(),
+        ac_addr.clone(),
+        to_json_binary(&Cw20HookMsg::DepositLp { recipient: None })?,
+    )?;
+
+    // the received lp tokens are forwarded to the account and staked
+    assert_that!(eur_usd_lp.balance(ac_addr.clone())?.balance).is_equal_to(Uint128::zero());
+    assert_that!(eur_usd_lp.balance(owner.to_string())?.balance).is_equal_to(Uint128::zero());
+    assert_that!(vault.auto_compounder.total_lp_position()?).is_greater_than(Uint128::zero());
+    let balance = vault_token.balance(owner.to_string())?.balance;
+    assert_that!(balance).is_greater_than(Uint128::zero());
+
+    // the vault token is neither a pool asset nor the lp token
+    let res = vault_token.call_as(&owner).send(
+        balance,
+        ac_addr.clone(),
+        to_json_binary(&Cw20HookMsg::Deposit {
+            recipient: None,
+            max_spread: None,
+        })?,
+    );
+    assert_that!(res).is_err();
+    let res = vault_token.call_as(&owner).send(
+        balance,
+        ac_addr,
+        to_json_binary(&Cw20HookMsg::DepositLp { recipient: None })?,
+    );
+    assert_that!(res).is_err();
+    assert_that!(vault_token.balance(owner.to_string())?.balance).is_equal_to(balance);
+    Ok(())
+}
+
+#[test]
+fn batch_deposit_mints_to_every_recipient() -> AResult {
+    let mock = MockBech32::new("mock");
+    let owner = mock.sender();
+    let alice = mock.addr_make("alice");
+    let bob = mock.addr_make("bob");
+    let vault = crate::create_vault(mock.clone(), EUR, USD, true)?;
+    let WynDex {
+        eur_token,
+        usd_token,
+        ..
+    } = vault.wyndex;
+    let vault_token = vault.vault_token;
+    let funds = [
+        coin(150_000u128, eur_token.to_string()),
+        coin(150_000u128, usd_token.to_string()),
+    ];
+    mock.set_balances(&[(&owner, &funds)])?;
+
+    let deposits = vec![
+        (
+            alice.clone(),
+            vec![
+                AnsAsset::new(AssetEntry::new("eur"), 100_000u128),
+                AnsAsset::new(AssetEntry::new("usd"), 100_000u128),
+            ],
+        ),
+        (
+            bob.clone(),
+            vec![
+                AnsAsset::new(AssetEntry::new("eur"), 50_000u128),
+                AnsAsset::new(AssetEntry::new("usd"), 50_000u128),
+            ],
+        ),
+    ];
+    vault
+        .auto_compounder
+        .batch_deposit(deposits, None, &funds)?;
+
+    let alice_shares = vault_token.balance(alice.to_string())?.balance;
+    let bob_shares = vault_token.balance(bob.to_string())?.balance;
+    assert_that!(bob_shares.u128()).is_greater_than(0);
+    // alice deposited twice as much as bob, up to rounding
+    assert_that!(alice_shares.u128().abs_diff(2 * bob_shares.u128())).is_less_than_or_equal_to(2);
+    assert_that!(vault_token.balance(owner.to_string())?.balance).is_equal_to(Uint128::zero());
+    Ok(())
+}
+
+#[test]
+fn sweepable_balances_include_known_cw20_tokens() -> AResult {
+    let mock = MockBech32::new("mock");
+    let owner = mock.sender();
+    let vault = crate::create_vault(mock.clone(), EUR, USD, true)?;
+    let proxy = vault.account.proxy.address()?;
+
+    // airdrops of a native and a cw20 token
+    mock.set_balance(&proxy, coins(500, "uairdrop"))?;
+    let airdrop = Cw20Base::new("airdrop", mock.clone());
+    airdrop.upload()?;
+    airdrop.instantiate(
+        &cw20_base::msg::InstantiateMsg {
+            name: "Airdrop".to_string(),
+            symbol: "DROP".to_string(),
+            decimals: 6,
+            initial_balances: vec![cw20::Cw20Coin {
+                address: proxy.to_string(),
+                amount: Uint128::new(1_000),
+            }],
+            mint: None,
+            marketing: None,
+        },
+        None,
+        None,
+    )?;
+    let airdrop_token = AssetInfo::cw20(airdrop.address()?);
+
+    // unknown cw20 tokens can't be listed
+    assert_that!(vault.auto_compounder.sweepable_balances()?)
+        .is_equal_to(vec![Asset::native("uairdrop", 500u128)]);
+
+    let manager_addr = vault.account.manager.address()?;
+    vault.auto_compounder.call_as(&manager_addr).execute_app(
+        AutocompounderExecuteMsg::UpdateRewardFilter {
+            reward_filter: RewardFilter {
+                allowlist: vec![],
+                denylist: vec![airdrop_token.clone()],
+                denied_rewards: DeniedRewards::Skip,
+            },
+        },
+        None,
+    )?;
+    assert_that!(vault.auto_compounder.sweepable_balances()?).is_equal_to(vec![
+        Asset::native("uairdrop", 500u128),
+        Asset::new(airdrop_token.clone(), 1_000u128),
+    ]);
+
+    // the listed cw20 balance can be swept
+    vault.auto_compounder.call_as(&manager_addr).execute_app(
+        AutocompounderExecuteMsg::Sweep {
+            assets: vec![Asset::new(airdrop_token, 1_000u128)],
+            recipient: owner.to_string(),
+        },
+        None,
+    )?;
+    assert_that!(airdrop.balance(owner.to_string())?.balance.u128()).is_equal_to(1_000u128);
+    assert_that!(vault.auto_compounder.sweepable_balances()?)
+        .is_equal_to(vec![Asset::native("uairdrop", 500u128)]);
+    Ok(())
+}
+
 #[test]
 fn test_owned_funds_stay_in_vault() -> AResult {
     // test that the funds in the vault are not used for the autocompounding and fee reward distribution
@@ -1193,7 +1835,7 @@ fn test_owned_funds_stay_in_vault() -> AResult {
 
     let redeem_amount = vault_token.balance(owner.to_string())?.balance;
     vault_token.increase_allowance(redeem_amount, vault.auto_compounder.addr_str()?, None)?;
-    vault.auto_compounder.redeem(redeem_amount, None)?;
+    vault.auto_compounder.redeem(redeem_amount, None, None)?;
 
     // Unbond tokens & clear pending claims
     vault.auto_compounder.batch_unbond(None, None)?;
@@ -1210,7 +1852,7 @@ fn test_owned_funds_stay_in_vault() -> AResult {
     )?;
 
     // Withdraw EUR and USD tokens to user
-    vault.auto_compounder.withdraw()?; // this will call lp_withdraw_reply
+    vault.auto_compounder.withdraw(None)?; // this will call lp_withdraw_reply
 
     let vault_eur_balance = mock.query_balance(&vault.account.proxy.address()?, EUR)?;
     let vault_usd_balance = mock.query_balance(&vault.account.proxy.address()?, USD)?;
@@ -1277,7 +1919,7 @@ fn batch_unbond_pagination() -> anyhow::Result<()> {
         vault
             .auto_compounder
             .call_as(addr)
-            .redeem(redeem_amount, None)?;
+            .redeem(redeem_amount, None, None)?;
     }
     // max 20 page per call. Test it by doing 30
     let claims = vault.auto_compounder.all_pending_claims(Some(30), None)?;
@@ -1392,6 +2034,117 @@ fn test_lp_deposit() -> AResult {
     Ok(())
 }
 
+#[test]
+fn tokenised_claims_burn_their_nft_when_withdrawn() -> AResult {
+    let mock = MockBech32::new("mock");
+    let owner = mock.sender();
+
+    let claims_nft_code_id = mock
+        .upload_custom(
+            "claims_nft",
+            Box::new(ContractWrapper::new_with_empty(
+                cw721_base::entry::execute,
+                cw721_base::entry::instantiate,
+                cw721_base::entry::query,
+            )),
+        )?
+        .uploaded_code_id()?;
+    let vault = crate::create_vault_with_claims_nft(
+        mock.clone(),
+        EUR,
+        USD,
+        true,
+        Some(claims_nft_code_id),
+    )?;
+    let WynDex {
+        eur_token,
+        usd_token,
+        ..
+    } = vault.wyndex;
+    let vault_token = vault.vault_token;
+    let auto_compounder_addr = vault.auto_compounder.address()?;
+    let claims_nft = vault.auto_compounder.claims_nft()?.unwrap();
+
+    mock.set_balances(&[(
+        &owner,
+        &[
+            coin(100_000u128, eur_token.to_string()),
+            coin(100_000u128, usd_token.to_string()),
+        ],
+    )])?;
+    vault.auto_compounder.deposit(
+        vec![
+            AnsAsset::new(AssetEntry::new("eur"), 10_000u128),
+            AnsAsset::new(AssetEntry::new("usd"), 10_000u128),
+        ],
+        None,
+        None,
+        &[coin(10_000u128, EUR), coin(10_000u128, USD)],
+    )?;
+
+    let redeem_amount = Uint128::from(20_000u128);
+    vault_token.increase_allowance(redeem_amount, auto_compounder_addr.to_string(), None)?;
+    vault.auto_compounder.redeem(redeem_amount, None, None)?;
+    vault.auto_compounder.batch_unbond(None, None)?;
+
+    // the claim is minted to the owner as nft "1"
+    let owner_of: OwnerOfResponse = mock.query(
+        &Cw721QueryMsg::OwnerOf {
+            token_id: "1".to_string(),
+            include_expired: None,
+        },
+        &claims_nft,
+    )?;
+    assert_that!(owner_of.owner).is_equal_to(owner.to_string());
+
+    let claims = vault.auto_compounder.claims(owner.clone())?;
+    if let Expiration::AtTime(time) = claims[0].unbonding_timestamp {
+        mock.app.borrow_mut().update_block(|b| {
+            b.time = time.plus_seconds(10);
+        });
+    }
+    mock.next_block()?;
+
+    // keepers can't settle tokenised claims, as the vault can't burn their nfts
+    let res = vault.auto_compounder.execute_app(
+        AutocompounderExecuteMsg::ProcessMaturedClaims {
+            start_after: None,
+            limit: None,
+        },
+        None,
+    );
+    assert_that!(res).is_err();
+
+    // the holder sends the nft to the vault to withdraw its claim
+    mock.execute(
+        &cw721_base::ExecuteMsg::<Option<Empty>, Empty>::SendNft {
+            contract: auto_compounder_addr.to_string(),
+            token_id: "1".to_string(),
+            msg: to_json_binary(&Cw721HookMsg::Withdraw {})?,
+        },
+        &[],
+        &claims_nft,
+    )?;
+
+    // the nft no longer exists and the claim is paid out
+    let owner_of: Result<OwnerOfResponse, _> = mock.query(
+        &Cw721QueryMsg::OwnerOf {
+            token_id: "1".to_string(),
+            include_expired: None,
+        },
+        &claims_nft,
+    );
+    assert_that!(owner_of).is_err();
+    assert_that!(vault.auto_compounder.claims(owner.clone())?).is_empty();
+
+    let balances = mock.query_all_balances(&owner)?;
+    assert_that!(balances).is_equal_to(vec![
+        coin(92_000u128, eur_token.to_string()),
+        coin(92_000u128, usd_token.to_string()),
+    ]);
+    Ok(())
+}
+
 fn paginate_all_claims(
     vault: &Vault<MockBech32>,
 ) -> Result<Vec<(Addr, Vec<Claim>)>, anyhow::Error> {
@@ -1528,7 +2281,7 @@ fn vault_token_inflation_attack_original() -> AResult {
     vault
         .auto_compounder
         .call_as(&attacker)
-        .redeem(redeem_amount, None)?;
+        .redeem(redeem_amount, None, None)?;
 
     // attacker unbonds tokens
     let pending_claims: Uint128 = vault.auto_compounder.pending_claims(attacker.clone())?;
@@ -150,6 +150,10 @@ fn setup_vault() -> anyhow::Result<VaultOsmosis> {
                 max_claims_per_address: None,
             }),
             max_swap_spread: Some(Decimal::percent(50)),
+            claims_nft_code_id: None,
+            vault_token_metadata: None,
+            token_factory: None,
+            decimal_offset: None,
         },
         cosmwasm_std::Empty {},
         &[],
@@ -1,7 +1,6 @@
 use abstract_client::{AbstractClient, Account, Namespace};
 use abstract_core::objects::{AssetEntry, DexAssetPairing};
 use abstract_core::PROXY;
-use autocompounder::kujira_tx::TOKEN_FACTORY_CREATION_FEE;
 use cw_orch::daemon::networks::osmosis::OSMO_NETWORK;
 use cw_orch::daemon::queriers::Bank;
 use cw_orch::daemon::{ChainInfo, ChainKind, DaemonBuilder};
@@ -52,21 +51,16 @@ fn description(asset_string: String) -> String {
 fn init_vault(args: Arguments) -> anyhow::Result<()> {
     let rt = Arc::new(tokio::runtime::Runtime::new().unwrap());
 
-    let (dex, base_pair_asset, cw20_code_id, token_creation_fee) = match args.network_id.as_str() {
-        "uni-6" => ("wyndex", "juno>junox", Some(4012), None),
-        "juno-1" => ("wyndex", "juno>juno", Some(1), None),
-        "pion-1" => ("astroport", "neutron>astro", Some(188), None),
-        "neutron-1" => ("astroport", "neutron>astro", Some(180), None),
-        "pisco-1" => ("astroport", "terra2>luna", Some(83), None),
-        "phoenix-1" => ("astroport", "terra2>luna", Some(69), None),
-        "osmo-test-5" => ("osmosis", "osmosis>osmo", None, None),
-        "osmosis-1" => ("osmosis", "osmosis>osmo", None, None),
-        "harpoon-4" => (
-            "kujira",
-            "kujira>kuji",
-            None,
-            Some(TOKEN_FACTORY_CREATION_FEE),
-        ),
+    let (dex, base_pair_asset, cw20_code_id, has_creation_fee) = match args.network_id.as_str() {
+        "uni-6" => ("wyndex", "juno>junox", Some(4012), false),
+        "juno-1" => ("wyndex", "juno>juno", Some(1), false),
+        "pion-1" => ("astroport", "neutron>astro", Some(188), false),
+        "neutron-1" => ("astroport", "neutron>astro", Some(180), false),
+        "pisco-1" => ("astroport", "terra2>luna", Some(83), false),
+        "phoenix-1" => ("astroport", "terra2>luna", Some(69), false),
+        "osmo-test-5" => ("osmosis", "osmosis>osmo", None, false),
+        "osmosis-1" => ("osmosis", "osmosis>osmo", None, false),
+        "harpoon-4" => ("kujira", "kujira>kuji", None, true),
         _ => panic!("Unknown network id: {}", args.network_id),
     };
 
@@ -101,7 +95,10 @@ fn init_vault(args: Arguments) -> anyhow::Result<()> {
     }
 
     // Funds for creating the token denomination
-    let instantiation_funds: Vec<Coin> = if let Some(creation_fee) = token_creation_fee {
+    let instantiation_funds: Vec<Coin> = if has_creation_fee {
+        let creation_fee = args
+            .creation_fee
+            .expect("--creation-fee is required for chains that charge for creating a denom");
         let bank: Bank = chain.querier();
         let balance: u128 = bank.balance(&sender, Some("ukuji".to_string())).unwrap()[0]
             .amount
@@ -143,6 +140,10 @@ fn init_vault(args: Arguments) -> anyhow::Result<()> {
         pool_assets: pair_assets.clone().into_iter().map(Into::into).collect(),
         bonding_data,
         max_swap_spread: Some(Decimal::percent(10)),
+        claims_nft_code_id: None,
+        vault_token_metadata: None,
+        token_factory: None,
+        decimal_offset: None,
     };
     let new_vault_account = abstr_client
         .account_builder()
@@ -308,6 +309,9 @@ struct Arguments {
     /// Force creating a new vault instead of loading an existing one
     #[arg(long)]
     force_new: bool,
+    /// Tokenfactory creation fee in ukuji, as set in the params of the denom module
+    #[arg(long)]
+    creation_fee: Option<u128>,
 }
 
 fn main() {